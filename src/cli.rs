@@ -0,0 +1,193 @@
+//! Commands run with `ruxlog <command>` instead of starting the server.
+
+use std::{error::Error, fs, path::PathBuf};
+
+use crate::{
+    db::{
+        self,
+        migration::run_migrations,
+        models::{
+            import_record::ImportRecord,
+            post_ranking::{PostRanking, RankingFilter, RankingKind},
+            user::User,
+        },
+    },
+    services::{
+        archive, cache,
+        import::{markdown, wxr},
+        media, ranking,
+        redis::init_redis_store,
+    },
+};
+
+const USAGE: &str = "usage:
+  ruxlog import markdown <path> --site <name> --author <email> [--apply]
+  ruxlog import wxr <path> --author <email> [--apply]
+  ruxlog import archive <file>
+  ruxlog export <file> [--include-passwords]";
+
+pub enum Command {
+    Import {
+        format: ImportFormat,
+        path: PathBuf,
+        author_email: String,
+        apply: bool,
+    },
+    /// Restores an archive made by `export` into an empty database.
+    ImportArchive { path: PathBuf },
+    Export {
+        path: PathBuf,
+        include_passwords: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum ImportFormat {
+    /// A directory of Markdown files with YAML front matter, imported as the posts of `site`.
+    Markdown { site: String },
+    /// A WordPress export file.
+    Wxr,
+}
+
+impl Command {
+    /// The command in the process arguments, or `None` when the server should start.
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let args: Vec<String> = args.collect();
+        let Some((name, rest)) = args.split_first() else {
+            return Ok(None);
+        };
+
+        let mut positional = vec![];
+        let mut author_email = None;
+        let mut site = None;
+        let mut apply = false;
+        let mut include_passwords = false;
+        let mut rest = rest.iter();
+        while let Some(arg) = rest.next() {
+            match arg.as_str() {
+                "--apply" => apply = true,
+                "--include-passwords" => include_passwords = true,
+                "--author" => author_email = rest.next().cloned(),
+                "--site" => site = rest.next().cloned(),
+                _ if arg.starts_with("--") => {
+                    return Err(format!("unknown option {:?}\n{}", arg, USAGE))
+                }
+                _ => positional.push(arg.clone()),
+            }
+        }
+
+        match (name.as_str(), positional.as_slice()) {
+            ("export", [path]) => Ok(Some(Command::Export {
+                path: PathBuf::from(path),
+                include_passwords,
+            })),
+            ("import", [format, path]) if format == "archive" => Ok(Some(Command::ImportArchive {
+                path: PathBuf::from(path),
+            })),
+            ("import", [format, path]) => {
+                let format = match format.as_str() {
+                    "markdown" => ImportFormat::Markdown {
+                        site: site.ok_or_else(|| USAGE.to_string())?,
+                    },
+                    "wxr" => ImportFormat::Wxr,
+                    _ => return Err(format!("unknown import format {:?}\n{}", format, USAGE)),
+                };
+
+                Ok(Some(Command::Import {
+                    format,
+                    path: PathBuf::from(path),
+                    author_email: author_email.ok_or_else(|| USAGE.to_string())?,
+                    apply,
+                }))
+            }
+            ("import" | "export", _) => Err(USAGE.to_string()),
+            _ => Err(format!("unknown command {:?}\n{}", name, USAGE)),
+        }
+    }
+
+    pub async fn run(self) -> Result<(), Box<dyn Error>> {
+        match self {
+            Command::Import {
+                format,
+                path,
+                author_email,
+                apply,
+            } => import(format, path, author_email, apply).await,
+            Command::ImportArchive { path } => import_archive(path).await,
+            Command::Export {
+                path,
+                include_passwords,
+            } => export(path, include_passwords).await,
+        }
+    }
+}
+
+/// Imports posts and prints the report as JSON. Without `--apply` it is a dry run.
+async fn import(
+    format: ImportFormat,
+    path: PathBuf,
+    author_email: String,
+    apply: bool,
+) -> Result<(), Box<dyn Error>> {
+    let batch = match format {
+        ImportFormat::Markdown { site } => markdown::read_dir(&path, &site)?,
+        ImportFormat::Wxr => wxr::parse(&fs::read_to_string(&path)?)?,
+    };
+
+    let pool = db::connect::get_pool().await;
+    run_migrations(&pool).await?;
+    let author = User::find_by_email(&pool, author_email.clone())
+        .await?
+        .ok_or_else(|| format!("no user with email {}", author_email))?;
+    if !author.is_author() {
+        return Err(format!("{} can't author posts", author_email).into());
+    }
+
+    let report = ImportRecord::import(&pool, batch, author.id, !apply).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    if apply {
+        // The running server would keep serving cached listings and feeds without the posts
+        let (redis_pool, _connection) = init_redis_store().await?;
+        cache::invalidate_posts(&redis_pool).await;
+    } else {
+        eprintln!("Dry run, nothing was saved. Run again with --apply to import.");
+    }
+
+    Ok(())
+}
+
+/// Restores an archive and prints what was restored as JSON.
+async fn import_archive(path: PathBuf) -> Result<(), Box<dyn Error>> {
+    let pool = db::connect::get_pool().await;
+    run_migrations(&pool).await?;
+    let storage = media::storage::from_env()?;
+
+    let report = archive::import(&pool, storage.as_ref(), &path).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    // Cached listings, feeds and rankings still point at the posts the restore replaced
+    let (redis_pool, _connection) = init_redis_store().await?;
+    cache::invalidate_posts(&redis_pool).await;
+    for kind in RankingKind::ALL {
+        let scores =
+            PostRanking::compute(&pool, kind, RankingFilter::default(), PostRanking::SIZE).await?;
+        ranking::store(&redis_pool, kind, &scores).await?;
+    }
+
+    Ok(())
+}
+
+/// Writes an archive of the whole blog and prints its manifest.
+async fn export(path: PathBuf, include_passwords: bool) -> Result<(), Box<dyn Error>> {
+    let pool = db::connect::get_pool().await;
+    run_migrations(&pool).await?;
+    let storage = media::storage::from_env()?;
+
+    let manifest = archive::export(&pool, storage.as_ref(), &path, include_passwords).await?;
+    println!("{}", serde_json::to_string_pretty(&manifest)?);
+    if !include_passwords {
+        eprintln!("Password hashes were left out, users will have to reset their passwords.");
+    }
+
+    Ok(())
+}
@@ -13,4 +13,23 @@ pub enum DBError {
 
     #[error("Database query error {0:?}")]
     QueryError(#[from] diesel::result::Error),
+
+    #[error("Media {0} does not exist")]
+    MediaNotFound(i32),
+
+    #[error("Media {0} belongs to another user")]
+    MediaNotOwned(i32),
+}
+
+impl DBError {
+    /// Whether the query failed on a unique constraint, e.g. a slug that is already taken.
+    pub fn is_unique_violation(&self) -> bool {
+        matches!(
+            self,
+            DBError::QueryError(diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                _,
+            ))
+        )
+    }
 }
@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_posts_scheduled_published_at;
+
+UPDATE posts SET is_published = TRUE WHERE is_scheduled = TRUE;
+
+ALTER TABLE posts DROP COLUMN IF EXISTS is_scheduled;
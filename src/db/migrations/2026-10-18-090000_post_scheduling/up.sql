@@ -0,0 +1,8 @@
+ALTER TABLE posts ADD COLUMN is_scheduled BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Posts already flagged as published but dated in the future are scheduled posts
+UPDATE posts
+SET is_scheduled = TRUE, is_published = FALSE
+WHERE is_published = TRUE AND published_at > CURRENT_TIMESTAMP;
+
+CREATE INDEX idx_posts_scheduled_published_at ON posts(published_at) WHERE is_scheduled = TRUE;
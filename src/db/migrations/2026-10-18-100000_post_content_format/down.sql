@@ -0,0 +1,4 @@
+ALTER TABLE posts DROP COLUMN IF EXISTS rendered_html;
+ALTER TABLE posts DROP COLUMN IF EXISTS content_format;
+
+DROP TYPE IF EXISTS content_format;
@@ -0,0 +1,6 @@
+CREATE TYPE content_format AS ENUM ('markdown', 'html', 'plain');
+
+ALTER TABLE posts ADD COLUMN content_format content_format NOT NULL DEFAULT 'markdown';
+
+-- Rendered HTML cache, NULL until the post is (re)rendered
+ALTER TABLE posts ADD COLUMN rendered_html TEXT;
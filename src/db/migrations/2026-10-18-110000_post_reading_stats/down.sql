@@ -0,0 +1,4 @@
+ALTER TABLE posts DROP COLUMN IF EXISTS excerpt_generated;
+ALTER TABLE posts DROP COLUMN IF EXISTS toc;
+ALTER TABLE posts DROP COLUMN IF EXISTS reading_time;
+ALTER TABLE posts DROP COLUMN IF EXISTS word_count;
@@ -0,0 +1,7 @@
+ALTER TABLE posts ADD COLUMN word_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE posts ADD COLUMN reading_time INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE posts ADD COLUMN toc JSONB NOT NULL DEFAULT '[]';
+ALTER TABLE posts ADD COLUMN excerpt_generated BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Clear the HTML cache so the background renderer computes stats for existing posts
+UPDATE posts SET rendered_html = NULL;
@@ -0,0 +1,7 @@
+DROP TRIGGER IF EXISTS tags_refresh_post_search ON tags;
+DROP FUNCTION IF EXISTS tags_refresh_post_search();
+DROP TRIGGER IF EXISTS posts_search_vector ON posts;
+DROP FUNCTION IF EXISTS posts_search_vector_update();
+DROP INDEX IF EXISTS idx_posts_search_vector;
+ALTER TABLE posts DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE posts DROP COLUMN IF EXISTS search_language;
@@ -0,0 +1,40 @@
+ALTER TABLE posts ADD COLUMN search_language VARCHAR(32) NOT NULL DEFAULT 'english'
+    CHECK (search_language::regconfig IS NOT NULL);
+ALTER TABLE posts ADD COLUMN search_vector TSVECTOR;
+
+-- Title > excerpt > content > tag names, stemmed with the post's own language
+CREATE OR REPLACE FUNCTION posts_search_vector_update() RETURNS trigger AS $$
+DECLARE
+    config REGCONFIG := NEW.search_language::regconfig;
+    tag_names TEXT;
+BEGIN
+    SELECT string_agg(name, ' ') INTO tag_names FROM tags WHERE id = ANY(NEW.tag_ids);
+
+    NEW.search_vector :=
+        setweight(to_tsvector(config, coalesce(NEW.title, '')), 'A') ||
+        setweight(to_tsvector(config, coalesce(NEW.excerpt, '')), 'B') ||
+        setweight(to_tsvector(config, coalesce(NEW.content, '')), 'C') ||
+        setweight(to_tsvector(config, coalesce(tag_names, '')), 'D');
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER posts_search_vector
+    BEFORE INSERT OR UPDATE OF title, excerpt, content, tag_ids, search_language ON posts
+    FOR EACH ROW EXECUTE FUNCTION posts_search_vector_update();
+
+-- Renaming or deleting a tag re-indexes the posts carrying it
+CREATE OR REPLACE FUNCTION tags_refresh_post_search() RETURNS trigger AS $$
+BEGIN
+    UPDATE posts SET tag_ids = tag_ids WHERE OLD.id = ANY(tag_ids);
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER tags_refresh_post_search
+    AFTER UPDATE OF name OR DELETE ON tags
+    FOR EACH ROW EXECUTE FUNCTION tags_refresh_post_search();
+
+UPDATE posts SET title = title;
+
+CREATE INDEX idx_posts_search_vector ON posts USING GIN (search_vector);
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_categories_name_trgm;
+DROP INDEX IF EXISTS idx_tags_name_trgm;
+DROP INDEX IF EXISTS idx_posts_title_trgm;
+DROP EXTENSION IF EXISTS pg_trgm;
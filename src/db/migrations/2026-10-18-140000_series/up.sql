@@ -0,0 +1,22 @@
+CREATE TABLE series (
+    id SERIAL PRIMARY KEY,
+    title VARCHAR(255) NOT NULL,
+    slug VARCHAR(255) NOT NULL UNIQUE,
+    description TEXT,
+    cover_image VARCHAR(255),
+    author_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_series_author_id ON series(author_id);
+
+-- A post is part of at most one series. Positions are 1-based and contiguous; the unique
+-- check is deferred so parts can be shifted or reordered within a transaction.
+CREATE TABLE series_posts (
+    series_id INTEGER NOT NULL REFERENCES series(id) ON DELETE CASCADE,
+    post_id INTEGER NOT NULL UNIQUE REFERENCES posts(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL CHECK (position > 0),
+    PRIMARY KEY (series_id, post_id),
+    CONSTRAINT unique_series_position UNIQUE (series_id, position) DEFERRABLE INITIALLY DEFERRED
+);
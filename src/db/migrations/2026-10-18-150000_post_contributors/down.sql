@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS post_contributors;
+DROP TYPE IF EXISTS contributor_role;
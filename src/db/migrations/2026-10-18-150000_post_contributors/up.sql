@@ -0,0 +1,15 @@
+CREATE TYPE contributor_role AS ENUM ('author', 'co-author', 'editor', 'reviewer');
+
+CREATE TABLE post_contributors (
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role contributor_role NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (post_id, user_id)
+);
+
+CREATE INDEX idx_post_contributors_user_id ON post_contributors(user_id);
+
+-- posts.author_id stays the owning author and is always credited as one
+INSERT INTO post_contributors (post_id, user_id, role)
+SELECT id, author_id, 'author' FROM posts;
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS post_reactions;
+DROP TABLE IF EXISTS post_comment_likes;
+DROP TABLE IF EXISTS post_likes;
+DROP TYPE IF EXISTS reaction_kind;
@@ -0,0 +1,30 @@
+CREATE TYPE reaction_kind AS ENUM ('thumbs_up', 'heart', 'laugh', 'celebrate', 'insightful', 'confused');
+
+CREATE TABLE post_likes (
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (post_id, user_id)
+);
+
+CREATE INDEX idx_post_likes_user_id ON post_likes(user_id);
+
+CREATE TABLE post_comment_likes (
+    comment_id INTEGER NOT NULL REFERENCES post_comments(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (comment_id, user_id)
+);
+
+CREATE INDEX idx_post_comment_likes_user_id ON post_comment_likes(user_id);
+
+CREATE TABLE post_reactions (
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind reaction_kind NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (post_id, user_id, kind)
+);
+
+CREATE INDEX idx_post_reactions_user_id ON post_reactions(user_id);
+
@@ -0,0 +1,22 @@
+CREATE TABLE bookmark_collections (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT unique_bookmark_collection_name UNIQUE (user_id, name)
+);
+
+-- Positions are 1-based and contiguous within a collection, deferred like series parts so
+-- bookmarks can be reordered within a transaction.
+CREATE TABLE bookmarks (
+    collection_id INTEGER NOT NULL REFERENCES bookmark_collections(id) ON DELETE CASCADE,
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL CHECK (position > 0),
+    read_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (collection_id, post_id),
+    CONSTRAINT unique_bookmark_position UNIQUE (collection_id, position) DEFERRABLE INITIALLY DEFERRED
+);
+
+CREATE INDEX idx_bookmarks_post_id ON bookmarks(post_id);
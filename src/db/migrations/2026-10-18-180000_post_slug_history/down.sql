@@ -0,0 +1,3 @@
+DROP TRIGGER IF EXISTS posts_record_slug_history ON posts;
+DROP FUNCTION IF EXISTS posts_record_slug_history();
+DROP TABLE IF EXISTS post_slug_history;
@@ -0,0 +1,27 @@
+-- Slugs a post was previously published under, so old links can be redirected
+CREATE TABLE post_slug_history (
+    slug VARCHAR(255) PRIMARY KEY,
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_post_slug_history_post_id ON post_slug_history(post_id);
+
+-- A post taking a slug (new or historical) claims it; the slug it leaves behind is kept
+-- pointing at it.
+CREATE OR REPLACE FUNCTION posts_record_slug_history() RETURNS trigger AS $$
+BEGIN
+    DELETE FROM post_slug_history WHERE slug = NEW.slug;
+
+    IF TG_OP = 'UPDATE' AND OLD.slug IS NOT NULL AND OLD.slug IS DISTINCT FROM NEW.slug THEN
+        INSERT INTO post_slug_history (slug, post_id) VALUES (OLD.slug, NEW.id)
+        ON CONFLICT (slug) DO UPDATE SET post_id = EXCLUDED.post_id, created_at = CURRENT_TIMESTAMP;
+    END IF;
+
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER posts_record_slug_history
+    AFTER INSERT OR UPDATE OF slug ON posts
+    FOR EACH ROW EXECUTE FUNCTION posts_record_slug_history();
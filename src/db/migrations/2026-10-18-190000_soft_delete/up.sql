@@ -0,0 +1,21 @@
+-- Deleted rows stay in the trash until the purge job removes them after the retention period
+ALTER TABLE posts
+    ADD COLUMN deleted_at TIMESTAMP,
+    ADD COLUMN deleted_by INTEGER REFERENCES users(id) ON DELETE SET NULL;
+
+ALTER TABLE post_comments
+    ADD COLUMN deleted_at TIMESTAMP,
+    ADD COLUMN deleted_by INTEGER REFERENCES users(id) ON DELETE SET NULL;
+
+ALTER TABLE categories
+    ADD COLUMN deleted_at TIMESTAMP,
+    ADD COLUMN deleted_by INTEGER REFERENCES users(id) ON DELETE SET NULL;
+
+ALTER TABLE tags
+    ADD COLUMN deleted_at TIMESTAMP,
+    ADD COLUMN deleted_by INTEGER REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_posts_deleted_at ON posts(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX idx_post_comments_deleted_at ON post_comments(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX idx_categories_deleted_at ON categories(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX idx_tags_deleted_at ON tags(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,3 @@
+-- Bumped on every edit so concurrent editors can detect each other's changes
+ALTER TABLE posts ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE categories ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,13 @@
+-- Shareable links to unpublished posts. The token itself is an HMAC over the row id, post and
+-- expiry, so only its metadata is stored.
+CREATE TABLE post_preview_tokens (
+    id SERIAL PRIMARY KEY,
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    created_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    label VARCHAR(255),
+    expires_at TIMESTAMP NOT NULL,
+    revoked_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_post_preview_tokens_post_id ON post_preview_tokens(post_id);
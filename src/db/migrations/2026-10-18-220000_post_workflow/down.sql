@@ -0,0 +1,16 @@
+DROP TABLE IF EXISTS post_status_transitions;
+
+ALTER TABLE posts ADD COLUMN is_published BOOLEAN DEFAULT FALSE;
+ALTER TABLE posts ADD COLUMN is_scheduled BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Archived and not yet approved posts go back to plain drafts
+UPDATE posts
+SET is_published = (status = 'published'),
+    is_scheduled = (status = 'scheduled');
+
+DROP INDEX IF EXISTS idx_posts_scheduled_published_at;
+DROP INDEX IF EXISTS idx_posts_status;
+ALTER TABLE posts DROP COLUMN status;
+DROP TYPE IF EXISTS post_status;
+
+CREATE INDEX idx_posts_scheduled_published_at ON posts(published_at) WHERE is_scheduled = TRUE;
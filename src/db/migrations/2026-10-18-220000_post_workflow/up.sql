@@ -0,0 +1,39 @@
+CREATE TYPE post_status AS ENUM (
+    'draft',
+    'in_review',
+    'changes_requested',
+    'approved',
+    'scheduled',
+    'published',
+    'archived'
+);
+
+ALTER TABLE posts ADD COLUMN status post_status NOT NULL DEFAULT 'draft';
+
+UPDATE posts
+SET status = CASE
+    WHEN is_scheduled THEN 'scheduled'::post_status
+    WHEN is_published THEN 'published'::post_status
+    ELSE 'draft'::post_status
+END;
+
+DROP INDEX IF EXISTS idx_posts_scheduled_published_at;
+ALTER TABLE posts DROP COLUMN is_published;
+ALTER TABLE posts DROP COLUMN is_scheduled;
+
+CREATE INDEX idx_posts_status ON posts(status);
+CREATE INDEX idx_posts_scheduled_published_at ON posts(published_at) WHERE status = 'scheduled';
+
+-- Every status change of a post, with who made it. actor_id is NULL for changes made by the
+-- scheduler or for users that have since been deleted.
+CREATE TABLE post_status_transitions (
+    id SERIAL PRIMARY KEY,
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    from_status post_status,
+    to_status post_status NOT NULL,
+    actor_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    comment TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_post_status_transitions_post_id ON post_status_transitions(post_id, created_at);
@@ -0,0 +1,27 @@
+-- Private editorial notes on drafts, never shown to readers. A thread starts with a note
+-- anchored to a range of the content at a post version (character offsets); replies point at
+-- the thread's first note and carry no anchor.
+CREATE TABLE post_notes (
+    id SERIAL PRIMARY KEY,
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    parent_id INTEGER REFERENCES post_notes(id) ON DELETE CASCADE,
+    author_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    post_version INTEGER,
+    anchor_start INTEGER,
+    anchor_end INTEGER,
+    quote TEXT,
+    body TEXT NOT NULL,
+    resolved_at TIMESTAMP,
+    resolved_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT post_notes_anchor CHECK (
+        (parent_id IS NULL AND post_version IS NOT NULL
+            AND anchor_start >= 0 AND anchor_end > anchor_start)
+        OR (parent_id IS NOT NULL AND post_version IS NULL
+            AND anchor_start IS NULL AND anchor_end IS NULL)
+    )
+);
+
+CREATE INDEX idx_post_notes_post_id ON post_notes(post_id) WHERE parent_id IS NULL;
+CREATE INDEX idx_post_notes_parent_id ON post_notes(parent_id);
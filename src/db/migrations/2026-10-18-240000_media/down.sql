@@ -0,0 +1,6 @@
+ALTER TABLE users DROP COLUMN IF EXISTS avatar_media_id;
+ALTER TABLE categories DROP COLUMN IF EXISTS logo_media_id;
+ALTER TABLE categories DROP COLUMN IF EXISTS cover_media_id;
+ALTER TABLE posts DROP COLUMN IF EXISTS featured_media_id;
+
+DROP TABLE IF EXISTS media;
@@ -0,0 +1,31 @@
+-- Uploaded images. Files live in the configured storage backend under storage_key; url is
+-- where they were published at upload time. variants holds the resized WebP copies.
+CREATE TABLE media (
+    id SERIAL PRIMARY KEY,
+    owner_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    storage_key VARCHAR(255) NOT NULL UNIQUE,
+    url VARCHAR NOT NULL,
+    original_name VARCHAR(255),
+    content_type VARCHAR(100) NOT NULL,
+    byte_size INTEGER NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    alt_text VARCHAR(500),
+    variants JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_media_owner_id ON media(owner_id);
+
+-- The URL columns stay and are filled from the referenced media, so readers of the old
+-- columns keep working.
+ALTER TABLE posts ADD COLUMN featured_media_id INTEGER REFERENCES media(id) ON DELETE SET NULL;
+ALTER TABLE categories ADD COLUMN cover_media_id INTEGER REFERENCES media(id) ON DELETE SET NULL;
+ALTER TABLE categories ADD COLUMN logo_media_id INTEGER REFERENCES media(id) ON DELETE SET NULL;
+ALTER TABLE users ADD COLUMN avatar_media_id INTEGER REFERENCES media(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_posts_featured_media_id ON posts(featured_media_id);
+CREATE INDEX idx_categories_cover_media_id ON categories(cover_media_id);
+CREATE INDEX idx_categories_logo_media_id ON categories(logo_media_id);
+CREATE INDEX idx_users_avatar_media_id ON users(avatar_media_id);
@@ -0,0 +1,15 @@
+-- Remembers what an import created, so running it again skips what is already there.
+-- Purging the post or comment drops its record, so a later import brings it back.
+CREATE TABLE import_records (
+    id SERIAL PRIMARY KEY,
+    source VARCHAR(255) NOT NULL,
+    external_id VARCHAR(500) NOT NULL,
+    post_id INTEGER REFERENCES posts(id) ON DELETE CASCADE,
+    comment_id INTEGER REFERENCES post_comments(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT import_records_target CHECK ((post_id IS NULL) <> (comment_id IS NULL)),
+    UNIQUE (source, external_id)
+);
+
+CREATE INDEX idx_import_records_post_id ON import_records(post_id);
+CREATE INDEX idx_import_records_comment_id ON import_records(comment_id);
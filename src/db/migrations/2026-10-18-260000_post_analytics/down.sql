@@ -0,0 +1,14 @@
+DROP TABLE IF EXISTS post_referrer_daily;
+DROP TABLE IF EXISTS post_view_daily;
+
+DROP INDEX IF EXISTS idx_post_views_created_at;
+
+ALTER TABLE post_views
+    DROP COLUMN IF EXISTS device,
+    DROP COLUMN IF EXISTS utm_campaign,
+    DROP COLUMN IF EXISTS utm_medium,
+    DROP COLUMN IF EXISTS utm_source,
+    DROP COLUMN IF EXISTS referrer_domain,
+    DROP COLUMN IF EXISTS visitor_hash;
+
+DROP TYPE IF EXISTS device_class;
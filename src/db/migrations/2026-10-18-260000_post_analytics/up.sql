@@ -0,0 +1,43 @@
+CREATE TYPE device_class AS ENUM ('desktop', 'mobile', 'tablet');
+
+-- Where a view came from. visitor_hash is a salted hash that changes every day, so visitors
+-- can be counted per day without being identifiable. Views recorded before these columns
+-- existed leave them empty.
+ALTER TABLE post_views
+    ADD COLUMN visitor_hash VARCHAR(64),
+    ADD COLUMN referrer_domain VARCHAR(255),
+    ADD COLUMN utm_source VARCHAR(100),
+    ADD COLUMN utm_medium VARCHAR(100),
+    ADD COLUMN utm_campaign VARCHAR(100),
+    ADD COLUMN device device_class;
+
+CREATE INDEX idx_post_views_created_at ON post_views(created_at);
+
+-- Raw views are rolled up into these per UTC day, then deleted.
+CREATE TABLE post_view_daily (
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    views INTEGER NOT NULL DEFAULT 0,
+    unique_visitors INTEGER NOT NULL DEFAULT 0,
+    desktop_views INTEGER NOT NULL DEFAULT 0,
+    mobile_views INTEGER NOT NULL DEFAULT 0,
+    tablet_views INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (post_id, day)
+);
+
+CREATE INDEX idx_post_view_daily_day ON post_view_daily(day);
+
+-- Views per source. Empty strings stand for no referrer or UTM parameter, so they can be part
+-- of the key.
+CREATE TABLE post_referrer_daily (
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    referrer_domain VARCHAR(255) NOT NULL DEFAULT '',
+    utm_source VARCHAR(100) NOT NULL DEFAULT '',
+    utm_medium VARCHAR(100) NOT NULL DEFAULT '',
+    utm_campaign VARCHAR(100) NOT NULL DEFAULT '',
+    views INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (post_id, day, referrer_domain, utm_source, utm_medium, utm_campaign)
+);
+
+CREATE INDEX idx_post_referrer_daily_day ON post_referrer_daily(day);
@@ -0,0 +1 @@
+DROP TABLE post_view_batches;
@@ -0,0 +1,7 @@
+-- Batches of buffered views that are stored, so a flush retried after Redis failed to drop
+-- the batch doesn't count its views twice. Rows are only needed until the retry, so old ones
+-- are cleared as new batches come in.
+CREATE TABLE post_view_batches (
+    id VARCHAR(32) PRIMARY KEY,
+    stored_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
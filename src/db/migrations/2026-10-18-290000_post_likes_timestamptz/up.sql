@@ -0,0 +1,2 @@
+-- Like times match the other engagement timestamps, so rankings compare them as they are
+ALTER TABLE post_likes ALTER COLUMN created_at TYPE TIMESTAMP WITH TIME ZONE USING created_at AT TIME ZONE 'UTC';
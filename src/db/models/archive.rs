@@ -0,0 +1,685 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use deadpool_diesel::postgres::Pool;
+use diesel::{
+    prelude::*,
+    sql_types::{Integer, Text},
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::db::{errors::DBError, utils::execute_db_operation};
+
+/// Password given to users restored from an archive without password hashes. It matches no
+/// password, so they sign in again through the forgot password flow.
+const UNUSABLE_PASSWORD: &str = "!";
+
+/// A table as it is exported and restored.
+pub struct ArchiveTable {
+    pub name: &'static str,
+    /// Whether rows have a serial `id` that gets a new value on restore.
+    serial_id: bool,
+    order_by: &'static str,
+    /// Columns holding ids of rows in other tables, as `(column, table)`. They are remapped to
+    /// the new ids on restore, which is why referenced tables come first.
+    references: &'static [(&'static str, &'static str)],
+    /// References that may point at rows restored later (a table referencing itself, or a
+    /// cycle). Those are set once every table is restored.
+    deferred: &'static [(&'static str, &'static str)],
+    /// Columns holding URLs of media, rewritten to where the files are now.
+    media_urls: &'static [&'static str],
+    /// Columns of text that may embed URLs of media.
+    media_text: &'static [&'static str],
+    /// Columns that are derived from others and not exported.
+    skip: &'static [&'static str],
+}
+
+/// Every table of content, in the order they are restored. Verification codes, password
+/// resets, preview tokens and raw views aren't content and are left out; views are kept as
+/// the daily aggregates.
+pub const TABLES: &[ArchiveTable] = &[
+    ArchiveTable {
+        name: "users",
+        serial_id: true,
+        order_by: "id",
+        references: &[],
+        deferred: &[("avatar_media_id", "media")],
+        media_urls: &["avatar"],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "media",
+        serial_id: true,
+        order_by: "id",
+        references: &[("owner_id", "users")],
+        deferred: &[],
+        media_urls: &["url", "variants"],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "categories",
+        serial_id: true,
+        order_by: "id",
+        references: &[
+            ("deleted_by", "users"),
+            ("cover_media_id", "media"),
+            ("logo_media_id", "media"),
+        ],
+        deferred: &[("parent_id", "categories")],
+        media_urls: &["cover_image", "logo_image"],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "tags",
+        serial_id: true,
+        order_by: "id",
+        references: &[("deleted_by", "users")],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "posts",
+        serial_id: true,
+        order_by: "id",
+        references: &[
+            ("author_id", "users"),
+            ("category_id", "categories"),
+            ("tag_ids", "tags"),
+            ("deleted_by", "users"),
+            ("featured_media_id", "media"),
+        ],
+        deferred: &[],
+        media_urls: &["featured_image_url"],
+        media_text: &["content", "rendered_html"],
+        skip: &["search_vector"],
+    },
+    ArchiveTable {
+        name: "post_slug_history",
+        serial_id: false,
+        order_by: "slug",
+        references: &[("post_id", "posts")],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "post_status_transitions",
+        serial_id: true,
+        order_by: "id",
+        references: &[("post_id", "posts"), ("actor_id", "users")],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "post_contributors",
+        serial_id: false,
+        order_by: "post_id, user_id",
+        references: &[("post_id", "posts"), ("user_id", "users")],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "post_notes",
+        serial_id: true,
+        order_by: "id",
+        // Replies always point at an earlier note
+        references: &[
+            ("post_id", "posts"),
+            ("parent_id", "post_notes"),
+            ("author_id", "users"),
+            ("resolved_by", "users"),
+        ],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "post_comments",
+        serial_id: true,
+        order_by: "id",
+        references: &[
+            ("post_id", "posts"),
+            ("user_id", "users"),
+            ("deleted_by", "users"),
+        ],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "post_comment_likes",
+        serial_id: false,
+        order_by: "comment_id, user_id",
+        references: &[("comment_id", "post_comments"), ("user_id", "users")],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "post_likes",
+        serial_id: false,
+        order_by: "post_id, user_id",
+        references: &[("post_id", "posts"), ("user_id", "users")],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "post_reactions",
+        serial_id: false,
+        order_by: "post_id, user_id, kind",
+        references: &[("post_id", "posts"), ("user_id", "users")],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "post_view_daily",
+        serial_id: false,
+        order_by: "post_id, day",
+        references: &[("post_id", "posts")],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "post_referrer_daily",
+        serial_id: false,
+        order_by: "post_id, day, referrer_domain, utm_source, utm_medium, utm_campaign",
+        references: &[("post_id", "posts")],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "series",
+        serial_id: true,
+        order_by: "id",
+        references: &[("author_id", "users")],
+        deferred: &[],
+        media_urls: &["cover_image"],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "series_posts",
+        serial_id: false,
+        order_by: "series_id, position",
+        references: &[("series_id", "series"), ("post_id", "posts")],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "bookmark_collections",
+        serial_id: true,
+        order_by: "id",
+        references: &[("user_id", "users")],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "bookmarks",
+        serial_id: false,
+        order_by: "collection_id, position",
+        references: &[
+            ("collection_id", "bookmark_collections"),
+            ("post_id", "posts"),
+        ],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+    ArchiveTable {
+        name: "import_records",
+        serial_id: true,
+        order_by: "id",
+        references: &[("post_id", "posts"), ("comment_id", "post_comments")],
+        deferred: &[],
+        media_urls: &[],
+        media_text: &[],
+        skip: &[],
+    },
+];
+
+/// Rows of one table, each a JSON object of its columns.
+pub struct ArchiveRows {
+    pub table: &'static str,
+    pub rows: Vec<Map<String, Value>>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct RestoreReport {
+    /// Rows restored per table.
+    pub rows: BTreeMap<String, usize>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum ArchiveRestore {
+    Restored(RestoreReport),
+    /// The database already has content; restoring only works into an empty one.
+    NotEmpty,
+}
+
+#[derive(QueryableByName)]
+struct JsonRow {
+    #[diesel(sql_type = Text)]
+    row: String,
+}
+
+#[derive(QueryableByName)]
+struct ColumnName {
+    #[diesel(sql_type = Text)]
+    column_name: String,
+}
+
+#[derive(QueryableByName)]
+struct InsertedId {
+    #[diesel(sql_type = Integer)]
+    id: i32,
+}
+
+struct PreparedRow {
+    /// Id the row had when it was exported.
+    old_id: Option<i64>,
+    row: Map<String, Value>,
+    /// Values to set once everything is in.
+    deferred: Option<Map<String, Value>>,
+}
+
+/// A row whose deferred references are set after everything is restored.
+struct PendingUpdate {
+    table: &'static ArchiveTable,
+    id: i32,
+    values: Map<String, Value>,
+}
+
+pub struct Archive;
+
+impl Archive {
+    /// Every table of `TABLES` as JSON rows, read in one transaction so they are consistent.
+    /// Password hashes are left out unless `include_passwords` is set.
+    pub async fn export(pool: &Pool, include_passwords: bool) -> Result<Vec<ArchiveRows>, DBError> {
+        execute_db_operation(pool, move |conn| {
+            conn.build_transaction()
+                .read_only()
+                .repeatable_read()
+                .run(|conn| {
+                    let mut tables = Vec::with_capacity(TABLES.len());
+
+                    for table in TABLES {
+                        let rows: Vec<JsonRow> = diesel::sql_query(format!(
+                            "SELECT row_to_json(t)::text AS row FROM {} t ORDER BY {}",
+                            table.name, table.order_by
+                        ))
+                        .load(conn)?;
+
+                        let rows = rows
+                            .into_iter()
+                            .map(|row| {
+                                let mut row: Map<String, Value> = serde_json::from_str(&row.row)
+                                    .map_err(|err| {
+                                        diesel::result::Error::DeserializationError(Box::new(err))
+                                    })?;
+                                for column in table.skip {
+                                    row.remove(*column);
+                                }
+                                if table.name == "users" && !include_passwords {
+                                    row.remove("password");
+                                }
+                                Ok(row)
+                            })
+                            .collect::<QueryResult<_>>()?;
+
+                        tables.push(ArchiveRows {
+                            table: table.name,
+                            rows,
+                        });
+                    }
+
+                    Ok(tables)
+                })
+        })
+        .await
+    }
+
+    /// Restores exported rows into an empty database in one transaction. Every row gets a new
+    /// id and references to it are remapped. `media_urls` maps URLs media had to where the
+    /// files are now.
+    pub async fn restore(
+        pool: &Pool,
+        tables: Vec<ArchiveRows>,
+        media_urls: HashMap<String, String>,
+    ) -> Result<ArchiveRestore, DBError> {
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                if !Self::no_content(conn)? {
+                    return Ok(ArchiveRestore::NotEmpty);
+                }
+
+                let mut report = RestoreReport::default();
+                let mut ids: HashMap<&'static str, HashMap<i64, i64>> = HashMap::new();
+                let mut pending = vec![];
+
+                for rows in tables {
+                    let Some(table) = TABLES.iter().find(|table| table.name == rows.table) else {
+                        report
+                            .warnings
+                            .push(format!("{}: unknown table, skipped", rows.table));
+                        continue;
+                    };
+                    let columns = Self::columns(conn, table.name)?;
+                    let mut restored = 0;
+
+                    for row in rows.rows {
+                        let restorer = RowRestorer {
+                            table,
+                            columns: &columns,
+                            ids: &ids,
+                            media_urls: &media_urls,
+                        };
+                        let PreparedRow {
+                            old_id,
+                            row,
+                            deferred,
+                        } = restorer.prepare(row, &mut report.warnings);
+                        let new_id = Self::insert(conn, table, &row)?;
+
+                        if let (Some(old_id), Some(new_id)) = (old_id, new_id) {
+                            ids.entry(table.name)
+                                .or_default()
+                                .insert(old_id, new_id as i64);
+                            if let Some(values) = deferred {
+                                pending.push(PendingUpdate {
+                                    table,
+                                    id: new_id,
+                                    values,
+                                });
+                            }
+                        }
+                        restored += 1;
+                    }
+
+                    report.rows.insert(table.name.to_string(), restored);
+                }
+
+                for update in pending {
+                    let mut values = update.values;
+                    for (column, target) in update.table.deferred {
+                        if let Some(value) = values.get_mut(*column) {
+                            if !remap(value, ids.get(target)) {
+                                report.warnings.push(format!(
+                                    "{} {}: {} points at a missing row, cleared",
+                                    update.table.name, update.id, column
+                                ));
+                            }
+                        }
+                    }
+                    Self::update(conn, update.table, update.id, &values)?;
+                }
+
+                Ok(ArchiveRestore::Restored(report))
+            })
+        })
+        .await
+    }
+
+    /// Whether the database has no users, posts, categories, tags or media yet.
+    pub async fn is_empty(pool: &Pool) -> Result<bool, DBError> {
+        execute_db_operation(pool, Self::no_content).await
+    }
+
+    fn no_content(conn: &mut PgConnection) -> QueryResult<bool> {
+        use crate::db::schema::{categories, media, posts, tags, users};
+
+        let exists = diesel::select(
+            diesel::dsl::exists(users::table.select(users::id))
+                .or(diesel::dsl::exists(posts::table.select(posts::id)))
+                .or(diesel::dsl::exists(
+                    categories::table.select(categories::id),
+                ))
+                .or(diesel::dsl::exists(tags::table.select(tags::id)))
+                .or(diesel::dsl::exists(media::table.select(media::id))),
+        )
+        .get_result::<bool>(conn)?;
+
+        Ok(!exists)
+    }
+
+    /// Columns the table has in this database.
+    fn columns(conn: &mut PgConnection, table: &str) -> QueryResult<HashSet<String>> {
+        let columns: Vec<ColumnName> = diesel::sql_query(
+            "SELECT column_name::text AS column_name FROM information_schema.columns
+             WHERE table_schema = current_schema() AND table_name = $1",
+        )
+        .bind::<Text, _>(table)
+        .load(conn)?;
+
+        Ok(columns
+            .into_iter()
+            .map(|column| column.column_name)
+            .collect())
+    }
+
+    fn insert(
+        conn: &mut PgConnection,
+        table: &ArchiveTable,
+        row: &Map<String, Value>,
+    ) -> QueryResult<Option<i32>> {
+        let columns = quoted_columns(row);
+        let mut sql = format!(
+            "INSERT INTO {table} ({columns}) SELECT {columns} FROM json_populate_record(NULL::{table}, $1::json)",
+            table = table.name,
+            columns = columns,
+        );
+
+        if !table.serial_id {
+            diesel::sql_query(sql)
+                .bind::<Text, _>(Value::Object(row.clone()).to_string())
+                .execute(conn)?;
+            return Ok(None);
+        }
+
+        sql.push_str(" RETURNING id");
+        let inserted: InsertedId = diesel::sql_query(sql)
+            .bind::<Text, _>(Value::Object(row.clone()).to_string())
+            .get_result(conn)?;
+
+        Ok(Some(inserted.id))
+    }
+
+    fn update(
+        conn: &mut PgConnection,
+        table: &ArchiveTable,
+        id: i32,
+        values: &Map<String, Value>,
+    ) -> QueryResult<usize> {
+        let columns = quoted_columns(values);
+
+        diesel::sql_query(format!(
+            "UPDATE {table} SET ({columns}) = (SELECT {columns} FROM json_populate_record(NULL::{table}, $1::json)) WHERE id = $2",
+            table = table.name,
+            columns = columns,
+        ))
+        .bind::<Text, _>(Value::Object(values.clone()).to_string())
+        .bind::<Integer, _>(id)
+        .execute(conn)
+    }
+}
+
+/// Prepares exported rows of one table for inserting.
+struct RowRestorer<'a> {
+    table: &'static ArchiveTable,
+    columns: &'a HashSet<String>,
+    ids: &'a HashMap<&'static str, HashMap<i64, i64>>,
+    media_urls: &'a HashMap<String, String>,
+}
+
+impl RowRestorer<'_> {
+    fn prepare(&self, mut row: Map<String, Value>, warnings: &mut Vec<String>) -> PreparedRow {
+        let table = self.table;
+        let old_id = if table.serial_id {
+            row.remove("id").and_then(|id| id.as_i64())
+        } else {
+            None
+        };
+        let label = match old_id {
+            Some(id) => format!("{} {}", table.name, id),
+            None => table.name.to_string(),
+        };
+
+        let unknown: Vec<String> = row
+            .keys()
+            .filter(|column| !self.columns.contains(*column))
+            .cloned()
+            .collect();
+        for column in unknown {
+            warnings.push(format!("{}: no column {} here, dropped", label, column));
+            row.remove(&column);
+        }
+        for column in table.skip {
+            row.remove(*column);
+        }
+        if table.name == "users" && !row.contains_key("password") {
+            row.insert(
+                "password".to_string(),
+                Value::String(UNUSABLE_PASSWORD.to_string()),
+            );
+        }
+
+        for (column, target) in table.references {
+            if let Some(value) = row.get_mut(*column) {
+                if !remap(value, self.ids.get(target)) {
+                    warnings.push(format!(
+                        "{}: {} points at a missing row, cleared",
+                        label, column
+                    ));
+                }
+            }
+        }
+        for column in table.media_urls {
+            if let Some(value) = row.get_mut(*column) {
+                rewrite_url(value, self.media_urls);
+            }
+        }
+        for column in table.media_text {
+            if let Some(Value::String(text)) = row.get_mut(*column) {
+                for (old, new) in self.media_urls {
+                    if text.contains(old.as_str()) {
+                        *text = text.replace(old.as_str(), new);
+                    }
+                }
+            }
+        }
+
+        // Deferred references to rows that are already in are set right away
+        let mut deferred = Map::new();
+        for (column, target) in table.deferred {
+            let Some(value) = row.get(*column).filter(|value| !value.is_null()) else {
+                continue;
+            };
+            let mut remapped = value.clone();
+            if !remap(&mut remapped, self.ids.get(target)) {
+                deferred.insert(column.to_string(), value.clone());
+                row.remove(*column);
+            } else {
+                row.insert(column.to_string(), remapped);
+            }
+        }
+        if deferred.is_empty() {
+            return PreparedRow {
+                old_id,
+                row,
+                deferred: None,
+            };
+        }
+        // Set together with the deferred columns, so the update doesn't touch it
+        if let Some(updated_at) = row.remove("updated_at") {
+            deferred.insert("updated_at".to_string(), updated_at);
+        }
+
+        PreparedRow {
+            old_id,
+            row,
+            deferred: Some(deferred),
+        }
+    }
+}
+
+/// Replaces old ids in `value`, an id or an array of them, with new ones. Ids without a new
+/// counterpart are dropped; returns false if there were any.
+fn remap(value: &mut Value, ids: Option<&HashMap<i64, i64>>) -> bool {
+    let new_id = |id: &Value| {
+        id.as_i64()
+            .and_then(|id| ids.and_then(|ids| ids.get(&id)))
+            .map(|id| Value::from(*id))
+    };
+
+    match value {
+        Value::Null => true,
+        Value::Array(values) => {
+            let count = values.len();
+            *values = values.iter().filter_map(new_id).collect();
+            values.len() == count
+        }
+        _ => match new_id(value) {
+            Some(id) => {
+                *value = id;
+                true
+            }
+            None => {
+                *value = Value::Null;
+                false
+            }
+        },
+    }
+}
+
+/// Rewrites a URL, or the `url` fields of a list of variants.
+fn rewrite_url(value: &mut Value, media_urls: &HashMap<String, String>) {
+    match value {
+        Value::String(url) => {
+            if let Some(new_url) = media_urls.get(url.as_str()) {
+                *url = new_url.clone();
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| rewrite_url(value, media_urls)),
+        Value::Object(object) => {
+            if let Some(url) = object.get_mut("url") {
+                rewrite_url(url, media_urls);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn quoted_columns(row: &Map<String, Value>) -> String {
+    row.keys()
+        .map(|column| format!("\"{}\"", column))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
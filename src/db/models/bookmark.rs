@@ -0,0 +1,475 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, Utc};
+use deadpool_diesel::postgres::Pool;
+use diesel::{dsl, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    category::Category,
+    post::{is_live, Post, PostWithRelations},
+    user::User,
+};
+use crate::db::{errors::DBError, schema, utils::execute_db_operation};
+
+/// Collection that bookmarks go to when none is picked.
+pub const DEFAULT_COLLECTION_NAME: &str = "Reading list";
+
+#[derive(Queryable, Identifiable, Selectable, Debug, Clone, Serialize, PartialEq)]
+#[diesel(table_name = schema::bookmark_collections)]
+pub struct BookmarkCollection {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Deserialize, Debug)]
+#[diesel(table_name = schema::bookmark_collections)]
+pub struct NewBookmarkCollection {
+    pub user_id: i32,
+    pub name: String,
+}
+
+#[derive(AsChangeset, Deserialize, Debug)]
+#[diesel(table_name = schema::bookmark_collections)]
+pub struct UpdateBookmarkCollection {
+    pub name: Option<String>,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BookmarkCollectionWithCount {
+    #[serde(flatten)]
+    pub collection: BookmarkCollection,
+    pub bookmarks_count: i64,
+}
+
+#[derive(Queryable, Selectable, Debug, Serialize, PartialEq)]
+#[diesel(table_name = schema::bookmarks)]
+pub struct Bookmark {
+    pub collection_id: i32,
+    pub post_id: i32,
+    pub position: i32,
+    pub read_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BookmarkedPost {
+    #[serde(flatten)]
+    pub post: PostWithRelations,
+    pub collection_id: i32,
+    pub position: i32,
+    pub read_at: Option<NaiveDateTime>,
+    pub bookmarked_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct BookmarkQuery {
+    pub page_no: Option<i64>,
+    pub collection_id: Option<i32>,
+    pub is_read: Option<bool>,
+}
+
+impl BookmarkCollection {
+    pub async fn create(
+        pool: &Pool,
+        new_collection: NewBookmarkCollection,
+    ) -> Result<Self, DBError> {
+        use crate::db::schema::bookmark_collections::dsl::*;
+
+        execute_db_operation(pool, move |conn| {
+            diesel::insert_into(bookmark_collections)
+                .values(&new_collection)
+                .returning(Self::as_returning())
+                .get_result(conn)
+        })
+        .await
+    }
+
+    pub async fn update(
+        pool: &Pool,
+        collection_id: i32,
+        user: User,
+        update_collection: UpdateBookmarkCollection,
+    ) -> Result<Option<Self>, DBError> {
+        use crate::db::schema::bookmark_collections::dsl::*;
+
+        execute_db_operation(pool, move |conn| {
+            diesel::update(
+                bookmark_collections.filter(id.eq(collection_id).and(user_id.eq(user.id))),
+            )
+            .set(&update_collection)
+            .returning(Self::as_returning())
+            .get_result(conn)
+            .optional()
+        })
+        .await
+    }
+
+    pub async fn delete(pool: &Pool, user: User, collection_id: i32) -> Result<usize, DBError> {
+        use crate::db::schema::bookmark_collections::dsl::*;
+
+        execute_db_operation(pool, move |conn| {
+            diesel::delete(
+                bookmark_collections.filter(id.eq(collection_id).and(user_id.eq(user.id))),
+            )
+            .execute(conn)
+        })
+        .await
+    }
+
+    /// The user's collections, each with the number of live posts it holds.
+    pub async fn list(
+        pool: &Pool,
+        user: User,
+    ) -> Result<Vec<BookmarkCollectionWithCount>, DBError> {
+        use crate::db::schema::{bookmark_collections, bookmarks, posts};
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            let collections: Vec<Self> = bookmark_collections::table
+                .filter(bookmark_collections::user_id.eq(user.id))
+                .order(bookmark_collections::name.asc())
+                .select(Self::as_select())
+                .load(conn)?;
+
+            let counts: HashMap<i32, i64> = bookmarks::table
+                .inner_join(posts::table)
+                .filter(bookmarks::collection_id.eq_any(collections.iter().map(|c| c.id)))
+                .filter(is_live(now))
+                .group_by(bookmarks::collection_id)
+                .select((bookmarks::collection_id, diesel::dsl::count_star()))
+                .load::<(i32, i64)>(conn)?
+                .into_iter()
+                .collect();
+
+            Ok(collections
+                .into_iter()
+                .map(|collection| BookmarkCollectionWithCount {
+                    bookmarks_count: counts.get(&collection.id).copied().unwrap_or(0),
+                    collection,
+                })
+                .collect())
+        })
+        .await
+    }
+
+    /// The user's collection with the given id, or their default one (created on first use).
+    fn find_or_default(
+        conn: &mut PgConnection,
+        user_id: i32,
+        collection_id: Option<i32>,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        use crate::db::schema::bookmark_collections;
+
+        let Some(collection_id) = collection_id else {
+            diesel::insert_into(bookmark_collections::table)
+                .values(NewBookmarkCollection {
+                    user_id,
+                    name: DEFAULT_COLLECTION_NAME.to_string(),
+                })
+                .on_conflict((bookmark_collections::user_id, bookmark_collections::name))
+                .do_nothing()
+                .execute(conn)?;
+
+            return bookmark_collections::table
+                .filter(bookmark_collections::user_id.eq(user_id))
+                .filter(bookmark_collections::name.eq(DEFAULT_COLLECTION_NAME))
+                .select(Self::as_select())
+                .first(conn)
+                .optional();
+        };
+
+        bookmark_collections::table
+            .filter(bookmark_collections::id.eq(collection_id))
+            .filter(bookmark_collections::user_id.eq(user_id))
+            .select(Self::as_select())
+            .first(conn)
+            .optional()
+    }
+}
+
+impl Bookmark {
+    /// Appends a live post to one of the user's collections. Bookmarking a post that is
+    /// already in the collection returns the existing bookmark.
+    pub async fn add(
+        pool: &Pool,
+        user: User,
+        collection_id: Option<i32>,
+        post_id: i32,
+    ) -> Result<Option<Self>, DBError> {
+        use crate::db::schema::{bookmark_collections, bookmarks};
+
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                let Some(collection) =
+                    BookmarkCollection::find_or_default(conn, user.id, collection_id)?
+                else {
+                    return Ok(None);
+                };
+                if !Post::exists_live(conn, post_id)? {
+                    return Ok(None);
+                }
+
+                // Concurrent adds to the collection wait here, so each takes the next position
+                bookmark_collections::table
+                    .find(collection.id)
+                    .select(bookmark_collections::id)
+                    .for_update()
+                    .first::<i32>(conn)?;
+                let last: Option<i32> = bookmarks::table
+                    .filter(bookmarks::collection_id.eq(collection.id))
+                    .select(dsl::max(bookmarks::position))
+                    .first(conn)?;
+
+                diesel::insert_into(bookmarks::table)
+                    .values((
+                        bookmarks::collection_id.eq(collection.id),
+                        bookmarks::post_id.eq(post_id),
+                        bookmarks::position.eq(last.unwrap_or(0) + 1),
+                    ))
+                    .on_conflict((bookmarks::collection_id, bookmarks::post_id))
+                    .do_nothing()
+                    .execute(conn)?;
+
+                Self::find(conn, collection.id, post_id)
+            })
+        })
+        .await
+    }
+
+    /// Removes a post from one of the user's collections and closes the gap it leaves.
+    pub async fn remove(
+        pool: &Pool,
+        user: User,
+        collection_id: i32,
+        post_id: i32,
+    ) -> Result<usize, DBError> {
+        use crate::db::schema::bookmarks;
+
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                if BookmarkCollection::find_or_default(conn, user.id, Some(collection_id))?
+                    .is_none()
+                {
+                    return Ok(0);
+                }
+
+                let removed: Option<i32> = diesel::delete(
+                    bookmarks::table.filter(
+                        bookmarks::collection_id
+                            .eq(collection_id)
+                            .and(bookmarks::post_id.eq(post_id)),
+                    ),
+                )
+                .returning(bookmarks::position)
+                .get_result(conn)
+                .optional()?;
+
+                let Some(removed) = removed else {
+                    return Ok(0);
+                };
+
+                diesel::update(
+                    bookmarks::table.filter(
+                        bookmarks::collection_id
+                            .eq(collection_id)
+                            .and(bookmarks::position.gt(removed)),
+                    ),
+                )
+                .set(bookmarks::position.eq(bookmarks::position - 1))
+                .execute(conn)?;
+
+                Ok(1)
+            })
+        })
+        .await
+    }
+
+    pub async fn mark_read(
+        pool: &Pool,
+        user: User,
+        collection_id: i32,
+        post_id: i32,
+        is_read: bool,
+    ) -> Result<Option<Self>, DBError> {
+        use crate::db::schema::{bookmark_collections, bookmarks};
+
+        let read_at = is_read.then(|| Utc::now().naive_utc());
+
+        execute_db_operation(pool, move |conn| {
+            let owned_collection = bookmark_collections::table
+                .filter(bookmark_collections::id.eq(collection_id))
+                .filter(bookmark_collections::user_id.eq(user.id))
+                .select(bookmark_collections::id);
+
+            diesel::update(
+                bookmarks::table.filter(
+                    bookmarks::collection_id
+                        .eq_any(owned_collection)
+                        .and(bookmarks::post_id.eq(post_id)),
+                ),
+            )
+            .set(bookmarks::read_at.eq(read_at))
+            .returning(Self::as_returning())
+            .get_result(conn)
+            .optional()
+        })
+        .await
+    }
+
+    /// Moves the listed posts to the front of the collection in the given order; the others
+    /// keep their relative order after them and ids that aren't bookmarked are ignored.
+    pub async fn reorder(
+        pool: &Pool,
+        user: User,
+        collection_id: i32,
+        post_ids: Vec<i32>,
+    ) -> Result<Option<Vec<Self>>, DBError> {
+        use crate::db::schema::bookmarks;
+
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                if BookmarkCollection::find_or_default(conn, user.id, Some(collection_id))?
+                    .is_none()
+                {
+                    return Ok(None);
+                }
+
+                let current: Vec<i32> = bookmarks::table
+                    .filter(bookmarks::collection_id.eq(collection_id))
+                    .order(bookmarks::position.asc())
+                    .select(bookmarks::post_id)
+                    .load(conn)?;
+
+                let mut ordered: Vec<i32> = Vec::with_capacity(current.len());
+                for post_id in post_ids.into_iter().chain(current.iter().copied()) {
+                    if current.contains(&post_id) && !ordered.contains(&post_id) {
+                        ordered.push(post_id);
+                    }
+                }
+
+                for (index, post_id) in ordered.into_iter().enumerate() {
+                    diesel::update(
+                        bookmarks::table.filter(
+                            bookmarks::collection_id
+                                .eq(collection_id)
+                                .and(bookmarks::post_id.eq(post_id)),
+                        ),
+                    )
+                    .set(bookmarks::position.eq(index as i32 + 1))
+                    .execute(conn)?;
+                }
+
+                bookmarks::table
+                    .filter(bookmarks::collection_id.eq(collection_id))
+                    .order(bookmarks::position.asc())
+                    .select(Self::as_select())
+                    .load(conn)
+                    .map(Some)
+            })
+        })
+        .await
+    }
+
+    /// The user's bookmarked posts, in collection order when listing a single collection and
+    /// newest first otherwise. Posts that are no longer live are left out.
+    pub async fn list(
+        pool: &Pool,
+        user: User,
+        query: BookmarkQuery,
+    ) -> Result<(Vec<BookmarkedPost>, i64), DBError> {
+        use crate::db::schema::{bookmark_collections, bookmarks, categories, posts, users};
+
+        let now = Utc::now().naive_utc();
+        let page = query.page_no.unwrap_or(1);
+
+        execute_db_operation(pool, move |conn| {
+            let filtered = || {
+                let mut query_builder = bookmarks::table
+                    .inner_join(bookmark_collections::table)
+                    .inner_join(
+                        posts::table
+                            .inner_join(users::table)
+                            .left_join(categories::table),
+                    )
+                    .filter(bookmark_collections::user_id.eq(user.id))
+                    .filter(is_live(now))
+                    .into_boxed();
+
+                if let Some(collection_id) = query.collection_id {
+                    query_builder =
+                        query_builder.filter(bookmarks::collection_id.eq(collection_id));
+                }
+                query_builder = match query.is_read {
+                    Some(true) => query_builder.filter(bookmarks::read_at.is_not_null()),
+                    Some(false) => query_builder.filter(bookmarks::read_at.is_null()),
+                    None => query_builder,
+                };
+
+                query_builder
+            };
+
+            let total: i64 = filtered().count().get_result(conn)?;
+
+            let mut query_builder = filtered();
+            query_builder = if query.collection_id.is_some() {
+                query_builder.order(bookmarks::position.asc())
+            } else {
+                query_builder.order((bookmarks::created_at.desc(), bookmarks::position.asc()))
+            };
+
+            let rows: Vec<(Bookmark, Post, Option<Category>, User)> = query_builder
+                .limit(Post::PER_PAGE)
+                .offset((page - 1) * Post::PER_PAGE)
+                .select((
+                    Bookmark::as_select(),
+                    Post::as_select(),
+                    Option::<Category>::as_select(),
+                    User::as_select(),
+                ))
+                .load(conn)?;
+
+            let mut marks: Vec<Bookmark> = Vec::with_capacity(rows.len());
+            let mut results: Vec<(Post, Option<Category>, User)> = Vec::with_capacity(rows.len());
+            for (bookmark, post, category, author) in rows {
+                marks.push(bookmark);
+                results.push((post, category, author));
+            }
+
+            let bookmarked = Post::with_relations(conn, results, Some(user.id))?
+                .into_iter()
+                .zip(marks)
+                .map(|(post, bookmark)| BookmarkedPost {
+                    post,
+                    collection_id: bookmark.collection_id,
+                    position: bookmark.position,
+                    read_at: bookmark.read_at,
+                    bookmarked_at: bookmark.created_at,
+                })
+                .collect();
+
+            Ok((bookmarked, total))
+        })
+        .await
+    }
+
+    fn find(
+        conn: &mut PgConnection,
+        collection_id: i32,
+        post_id: i32,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        use crate::db::schema::bookmarks;
+
+        bookmarks::table
+            .filter(bookmarks::collection_id.eq(collection_id))
+            .filter(bookmarks::post_id.eq(post_id))
+            .select(Self::as_select())
+            .first(conn)
+            .optional()
+    }
+}
@@ -1,6 +1,8 @@
 #![allow(unused)]
 #![allow(clippy::all)]
 
+use std::collections::HashSet;
+
 use axum::{http::StatusCode, Json};
 use chrono::{Duration, NaiveDateTime, Utc};
 use deadpool_diesel::postgres::Pool;
@@ -9,11 +11,13 @@ use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
 use tokio::task;
 
+use super::media::Media;
 use crate::db::{
     errors::DBError,
     schema,
-    utils::{combine_errors, execute_db_operation},
+    utils::{combine_errors, execute_db_operation, execute_db_transaction, VersionedUpdate},
 };
+use crate::services::slug;
 
 #[derive(Queryable, Identifiable, Selectable, Debug, Clone, Serialize, PartialEq)]
 #[diesel(table_name = schema::categories)]
@@ -27,6 +31,13 @@ pub struct Category {
     pub logo_image: Option<String>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_by: Option<i32>,
+    pub version: i32,
+    pub cover_media_id: Option<i32>,
+    pub logo_media_id: Option<i32>,
 }
 
 #[derive(Insertable, Deserialize, Debug)]
@@ -38,6 +49,8 @@ pub struct NewCategory {
     pub description: Option<String>,
     pub cover_image: Option<String>,
     pub logo_image: Option<String>,
+    pub cover_media_id: Option<i32>,
+    pub logo_media_id: Option<i32>,
 }
 
 #[derive(AsChangeset, Deserialize, Debug)]
@@ -50,21 +63,65 @@ pub struct UpdateCategory {
     pub cover_image: Option<Option<String>>,
     pub logo_image: Option<Option<String>>,
     pub updated_at: NaiveDateTime,
+    pub cover_media_id: Option<Option<i32>>,
+    pub logo_media_id: Option<Option<i32>>,
 }
 
 impl Category {
     pub async fn create(pool: &Pool, new_category: NewCategory) -> Result<Self, DBError> {
         use crate::db::schema::categories::dsl::*;
 
-        execute_db_operation(pool, move |conn| {
-            diesel::insert_into(categories)
-                .values(&new_category)
-                .returning(Self::as_returning())
-                .get_result(conn)
+        execute_db_transaction(pool, move |conn| {
+            for media_id in [new_category.cover_media_id, new_category.logo_media_id]
+                .into_iter()
+                .flatten()
+            {
+                // Categories are only edited by admins, who may use any upload
+                Media::check_attachable(conn, media_id, None)?;
+            }
+
+            Ok(Self::insert(conn, new_category)?)
         })
         .await
     }
 
+    pub fn insert(
+        conn: &mut PgConnection,
+        mut new_category: NewCategory,
+    ) -> Result<Self, diesel::result::Error> {
+        use crate::db::schema::categories::dsl::*;
+
+        if new_category.slug.is_empty() {
+            new_category.slug = Self::unique_slug(conn, &new_category.name)?;
+        }
+        if let Some(media_id) = new_category.cover_media_id {
+            new_category.cover_image = Media::url_of(conn, media_id)?;
+        }
+        if let Some(media_id) = new_category.logo_media_id {
+            new_category.logo_image = Media::url_of(conn, media_id)?;
+        }
+
+        diesel::insert_into(categories)
+            .values(&new_category)
+            .returning(Self::as_returning())
+            .get_result(conn)
+    }
+
+    /// A slug derived from `name` that no other category uses.
+    fn unique_slug(conn: &mut PgConnection, name: &str) -> Result<String, diesel::result::Error> {
+        use crate::db::schema::categories;
+
+        let base = slug::slugify(name, "category");
+        let taken: HashSet<String> = categories::table
+            .filter(categories::slug.like(format!("{}%", base)))
+            .select(categories::slug)
+            .load::<String>(conn)?
+            .into_iter()
+            .collect();
+
+        Ok(slug::unique(&base, &taken))
+    }
+
     pub async fn get_category_by_id(
         pool: &Pool,
         category_id: i32,
@@ -74,6 +131,7 @@ impl Category {
         execute_db_operation(pool, move |conn| {
             categories
                 .filter(id.eq(category_id))
+                .filter(deleted_at.is_null())
                 .first::<Category>(conn)
                 .optional()
         })
@@ -87,7 +145,7 @@ impl Category {
         use crate::db::schema::categories::dsl::*;
 
         execute_db_operation(pool, move |conn| {
-            let mut query = categories.into_boxed();
+            let mut query = categories.filter(deleted_at.is_null()).into_boxed();
 
             if let Some(parent_id_filter) = parent_category_id {
                 query = query.filter(parent_id.eq(parent_id_filter));
@@ -98,16 +156,108 @@ impl Category {
         .await
     }
 
+    /// Applies an edit if the category is still at one of `expected_versions` (any version
+    /// when `None`), bumping its version. Returns the category as it is now on a version
+    /// conflict.
     pub async fn update(
         pool: &Pool,
         category_id: i32,
         update_category: UpdateCategory,
-    ) -> Result<Option<Self>, DBError> {
+        expected_versions: Option<Vec<i32>>,
+    ) -> Result<Option<VersionedUpdate<Self>>, DBError> {
+        use crate::db::schema::categories::dsl::*;
+
+        execute_db_transaction(pool, move |conn| {
+            let mut update_category = update_category;
+            for media_id in [
+                update_category.cover_media_id,
+                update_category.logo_media_id,
+            ]
+            .into_iter()
+            .flatten()
+            .flatten()
+            {
+                Media::check_attachable(conn, media_id, None)?;
+            }
+            Media::sync_url(
+                conn,
+                update_category.cover_media_id,
+                &mut update_category.cover_image,
+            )?;
+            Media::sync_url(
+                conn,
+                update_category.logo_media_id,
+                &mut update_category.logo_image,
+            )?;
+
+            let mut query_builder = diesel::update(categories)
+                .filter(id.eq(category_id))
+                .filter(deleted_at.is_null())
+                .into_boxed();
+
+            if let Some(expected_versions) = expected_versions {
+                query_builder = query_builder.filter(version.eq_any(expected_versions));
+            }
+
+            let category: Option<Self> = query_builder
+                .set((&update_category, version.eq(version + 1)))
+                .returning(Self::as_returning())
+                .get_result(conn)
+                .optional()?;
+
+            match category {
+                Some(category) => Ok(Some(VersionedUpdate::Updated(category))),
+                None => Ok(categories
+                    .filter(id.eq(category_id))
+                    .filter(deleted_at.is_null())
+                    .first::<Self>(conn)
+                    .optional()?
+                    .map(VersionedUpdate::Conflict)),
+            }
+        })
+        .await
+    }
+
+    /// Moves a category to the trash. Its posts and subcategories keep pointing at it until it
+    /// is purged, so restoring it puts everything back in place.
+    pub async fn delete(pool: &Pool, category_id: i32, user_id: i32) -> Result<usize, DBError> {
+        use crate::db::schema::categories::dsl::*;
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            diesel::update(categories.filter(id.eq(category_id).and(deleted_at.is_null())))
+                .set((deleted_at.eq(now), deleted_by.eq(user_id)))
+                .execute(conn)
+        })
+        .await
+    }
+
+    /// Categories in the trash, most recently deleted first.
+    pub async fn find_deleted(pool: &Pool) -> Result<Vec<Self>, DBError> {
         use crate::db::schema::categories::dsl::*;
 
         execute_db_operation(pool, move |conn| {
-            diesel::update(categories.filter(id.eq(category_id)))
-                .set(&update_category)
+            categories
+                .filter(deleted_at.is_not_null())
+                .order((deleted_at.desc(), id.desc()))
+                .load::<Self>(conn)
+        })
+        .await
+    }
+
+    pub async fn restore(pool: &Pool, category_id: i32) -> Result<Option<Self>, DBError> {
+        use crate::db::schema::categories::dsl::*;
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            diesel::update(categories.filter(id.eq(category_id).and(deleted_at.is_not_null())))
+                .set((
+                    deleted_at.eq(None::<NaiveDateTime>),
+                    deleted_by.eq(None::<i32>),
+                    updated_at.eq(now),
+                ))
                 .returning(Self::as_returning())
                 .get_result(conn)
                 .optional()
@@ -115,17 +265,39 @@ impl Category {
         .await
     }
 
-    pub async fn delete(pool: &Pool, category_id: i32) -> Result<usize, DBError> {
-        use crate::db::schema::categories::dsl::*;
+    /// Permanently deletes the categories trashed before `cutoff`, detaching their posts and
+    /// subcategories first.
+    pub async fn purge_deleted(pool: &Pool, cutoff: NaiveDateTime) -> Result<usize, DBError> {
+        use crate::db::schema::{categories, posts};
 
         execute_db_operation(pool, move |conn| {
-            diesel::delete(categories.filter(id.eq(category_id))).execute(conn)
+            conn.transaction(|conn| {
+                let purged_ids: Vec<i32> = categories::table
+                    .filter(categories::deleted_at.lt(cutoff))
+                    .select(categories::id)
+                    .load(conn)?;
+                if purged_ids.is_empty() {
+                    return Ok(0);
+                }
+
+                diesel::update(categories::table.filter(categories::parent_id.eq_any(&purged_ids)))
+                    .set(categories::parent_id.eq(None::<i32>))
+                    .execute(conn)?;
+                diesel::update(posts::table.filter(posts::category_id.eq_any(&purged_ids)))
+                    .set(posts::category_id.eq(None::<i32>))
+                    .execute(conn)?;
+                diesel::delete(categories::table.filter(categories::id.eq_any(&purged_ids)))
+                    .execute(conn)
+            })
         })
         .await
     }
 
     pub async fn find_all(pool: &Pool) -> Result<Vec<Self>, DBError> {
         use crate::db::schema::categories::dsl::*;
-        execute_db_operation(pool, move |conn| categories.load::<Self>(conn)).await
+        execute_db_operation(pool, move |conn| {
+            categories.filter(deleted_at.is_null()).load::<Self>(conn)
+        })
+        .await
     }
 }
@@ -0,0 +1,538 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{NaiveDateTime, Utc};
+use deadpool_diesel::postgres::Pool;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use super::{
+    category::{Category, NewCategory},
+    post::{NewPost, Post, PostStatus},
+    post_comment::NewPostComment,
+    tag::{NewTag, Tag},
+    user::{NewUser, UserRole},
+};
+use crate::{
+    db::{errors::DBError, schema, utils::execute_db_operation},
+    services::{
+        import::{ImportBatch, ImportedCategory, ImportedComment, ImportedPost},
+        search, slug,
+    },
+};
+
+/// What an import created for an item of an export.
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone, Serialize, PartialEq)]
+#[diesel(table_name = schema::import_records)]
+pub struct ImportRecord {
+    pub id: i32,
+    pub source: String,
+    pub external_id: String,
+    pub post_id: Option<i32>,
+    pub comment_id: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = schema::import_records)]
+struct NewImportRecord<'a> {
+    source: &'a str,
+    external_id: &'a str,
+    post_id: Option<i32>,
+    comment_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportAction {
+    Create,
+    /// Imported by an earlier run.
+    Skip,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportedPostReport {
+    pub external_id: String,
+    pub title: String,
+    pub action: ImportAction,
+    /// Left out on dry runs, where nothing is kept.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_id: Option<i32>,
+    pub comments_created: usize,
+}
+
+/// What an import did, or would do on a dry run.
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub source: String,
+    pub posts_created: usize,
+    pub posts_skipped: usize,
+    pub comments_created: usize,
+    pub comments_skipped: usize,
+    pub categories_created: Vec<String>,
+    pub tags_created: Vec<String>,
+    pub users_created: Vec<String>,
+    pub posts: Vec<ImportedPostReport>,
+    pub warnings: Vec<String>,
+}
+
+impl ImportRecord {
+    /// Imports a batch in a single transaction. Authors and commenters are matched to users by
+    /// email, and to authors also by name; unknown ones with an email get an unverified
+    /// account without a password, the rest are credited to `default_author_id`. Categories
+    /// and tags are matched by slug or name and created when missing. Posts and comments
+    /// imported by an earlier run of the same source are skipped. A dry run does all of it and
+    /// rolls back, so the report is exact.
+    pub async fn import(
+        pool: &Pool,
+        batch: ImportBatch,
+        default_author_id: i32,
+        dry_run: bool,
+    ) -> Result<ImportReport, DBError> {
+        execute_db_operation(pool, move |conn| {
+            let mut importer = Importer {
+                batch: &batch,
+                default_author_id,
+                users: HashMap::new(),
+                categories: HashMap::new(),
+                tags: HashMap::new(),
+                report: ImportReport {
+                    dry_run,
+                    source: batch.source.clone(),
+                    warnings: batch.warnings.clone(),
+                    ..Default::default()
+                },
+            };
+
+            let result = conn.transaction(|conn| {
+                for post in &batch.posts {
+                    importer.import_post(conn, post)?;
+                }
+
+                if dry_run {
+                    Err(diesel::result::Error::RollbackTransaction)
+                } else {
+                    Ok(())
+                }
+            });
+
+            let mut report = importer.report;
+            match result {
+                Ok(()) => Ok(report),
+                Err(diesel::result::Error::RollbackTransaction) if dry_run => {
+                    for post in report.posts.iter_mut() {
+                        post.post_id = None;
+                    }
+                    Ok(report)
+                }
+                Err(err) => Err(err),
+            }
+        })
+        .await
+    }
+}
+
+struct Importer<'a> {
+    batch: &'a ImportBatch,
+    default_author_id: i32,
+    /// Users by author key or email.
+    users: HashMap<String, Option<i32>>,
+    /// Categories and tags by slug.
+    categories: HashMap<String, i32>,
+    tags: HashMap<String, i32>,
+    report: ImportReport,
+}
+
+impl Importer<'_> {
+    fn import_post(&mut self, conn: &mut PgConnection, post: &ImportedPost) -> QueryResult<()> {
+        let source = self.batch.source.as_str();
+
+        if let Some(record) = Self::recorded(conn, source, &post.external_id)? {
+            // New comments on a post imported before still come in
+            let comments_created = match record.post_id {
+                Some(post_id) => self.import_comments(conn, post_id, &post.comments)?,
+                None => 0,
+            };
+            self.report.posts_skipped += 1;
+            self.report.posts.push(ImportedPostReport {
+                external_id: post.external_id.clone(),
+                title: post.title.clone(),
+                action: ImportAction::Skip,
+                post_id: record.post_id,
+                comments_created,
+            });
+            return Ok(());
+        }
+
+        let author_id = match self.author_id(conn, post.author.as_deref())? {
+            Some(author_id) => author_id,
+            None => {
+                if let Some(author) = &post.author {
+                    self.report.warnings.push(format!(
+                        "{}: author {:?} not found, credited to the importing user",
+                        post.external_id, author
+                    ));
+                }
+                self.default_author_id
+            }
+        };
+        let category_id = match &post.category {
+            Some(reference) => Some(self.category_id(conn, reference, &mut HashSet::new())?),
+            None => None,
+        };
+        let mut tag_ids = vec![];
+        for name in &post.tags {
+            let tag_id = self.tag_id(conn, name)?;
+            if !tag_ids.contains(&tag_id) {
+                tag_ids.push(tag_id);
+            }
+        }
+
+        let post_slug = match &post.slug {
+            Some(wanted) => {
+                let wanted = slug::slugify(wanted, "post");
+                if Post::slug_taken(conn, &wanted)? {
+                    self.report.warnings.push(format!(
+                        "{}: slug {:?} is taken, a new one is generated",
+                        post.external_id, wanted
+                    ));
+                    String::new()
+                } else {
+                    wanted
+                }
+            }
+            None => String::new(),
+        };
+
+        let now = Utc::now().naive_utc();
+        let (status, published_at) = match (post.status, post.published_at) {
+            (PostStatus::Published, date) => (PostStatus::Published, Some(date.unwrap_or(now))),
+            (PostStatus::Scheduled, Some(date)) if date > now => {
+                (PostStatus::Scheduled, Some(date))
+            }
+            (PostStatus::Scheduled, Some(date)) => (PostStatus::Published, Some(date)),
+            (PostStatus::Scheduled, None) => (PostStatus::Draft, None),
+            (status, _) => (status, None),
+        };
+
+        let created = Post::insert(
+            conn,
+            NewPost {
+                title: post.title.chars().take(255).collect(),
+                content: post.content.clone(),
+                author_id,
+                published_at,
+                slug: post_slug,
+                excerpt: post.excerpt.clone(),
+                featured_image_url: None,
+                category_id,
+                view_count: 0,
+                likes_count: 0,
+                tag_ids,
+                content_format: post.content_format,
+                search_language: search::default_language(),
+                status,
+                featured_media_id: None,
+            },
+        )?;
+        // Keep the original dates rather than the time of the import
+        if let Some(date) = post.published_at {
+            use crate::db::schema::posts;
+
+            diesel::update(posts::table.filter(posts::id.eq(created.id)))
+                .set(posts::created_at.eq(date))
+                .execute(conn)?;
+        }
+        Self::record(conn, source, &post.external_id, Some(created.id), None)?;
+
+        let comments_created = self.import_comments(conn, created.id, &post.comments)?;
+        self.report.posts_created += 1;
+        self.report.posts.push(ImportedPostReport {
+            external_id: post.external_id.clone(),
+            title: post.title.clone(),
+            action: ImportAction::Create,
+            post_id: Some(created.id),
+            comments_created,
+        });
+
+        Ok(())
+    }
+
+    fn import_comments(
+        &mut self,
+        conn: &mut PgConnection,
+        post_id: i32,
+        comments: &[ImportedComment],
+    ) -> QueryResult<usize> {
+        use crate::db::schema::post_comments;
+
+        let source = self.batch.source.as_str();
+        let mut created = 0;
+
+        for comment in comments {
+            if Self::recorded(conn, source, &comment.external_id)?.is_some() {
+                self.report.comments_skipped += 1;
+                continue;
+            }
+            let Some(email) = &comment.author_email else {
+                self.report.warnings.push(format!(
+                    "{}: skipped, the commenter has no email",
+                    comment.external_id
+                ));
+                continue;
+            };
+            let user_id = self.user_id(conn, email, &comment.author_name, UserRole::User)?;
+
+            let date = comment.created_at.unwrap_or_else(|| Utc::now().naive_utc());
+            let comment_id: i32 = diesel::insert_into(post_comments::table)
+                .values((
+                    &NewPostComment {
+                        post_id,
+                        user_id,
+                        content: comment.content.clone(),
+                    },
+                    post_comments::created_at.eq(date),
+                    post_comments::updated_at.eq(date),
+                ))
+                .returning(post_comments::id)
+                .get_result(conn)?;
+            Self::record(conn, source, &comment.external_id, None, Some(comment_id))?;
+
+            created += 1;
+        }
+
+        self.report.comments_created += created;
+        Ok(created)
+    }
+
+    /// The user an author reference points at, if one exists or can be created.
+    fn author_id(
+        &mut self,
+        conn: &mut PgConnection,
+        reference: Option<&str>,
+    ) -> QueryResult<Option<i32>> {
+        use crate::db::schema::users;
+
+        let Some(reference) = reference else {
+            return Ok(None);
+        };
+        if let Some(user_id) = self.users.get(reference) {
+            return Ok(*user_id);
+        }
+
+        let author = self
+            .batch
+            .authors
+            .iter()
+            .find(|author| author.key == reference);
+        let (name, email) = match author {
+            Some(author) => (author.name.clone(), author.email.clone()),
+            None if reference.contains('@') => (reference.to_string(), Some(reference.to_string())),
+            None => (reference.to_string(), None),
+        };
+
+        let user_id = match email {
+            Some(email) => Some(self.user_id(conn, &email, &name, UserRole::Author)?),
+            None => users::table
+                .filter(users::name.ilike(literal_pattern(&name)))
+                .select(users::id)
+                .order(users::id.asc())
+                .first(conn)
+                .optional()?,
+        };
+
+        self.users.insert(reference.to_string(), user_id);
+        Ok(user_id)
+    }
+
+    /// The user with `email`, created when there is none.
+    fn user_id(
+        &mut self,
+        conn: &mut PgConnection,
+        email: &str,
+        name: &str,
+        role: UserRole,
+    ) -> QueryResult<i32> {
+        use crate::db::schema::users;
+
+        let email = email.trim().to_lowercase();
+        if let Some(Some(user_id)) = self.users.get(&email) {
+            return Ok(*user_id);
+        }
+
+        let existing: Option<i32> = users::table
+            .filter(users::email.ilike(literal_pattern(&email)))
+            .select(users::id)
+            .first(conn)
+            .optional()?;
+        let user_id = match existing {
+            Some(user_id) => user_id,
+            None => {
+                let user_id = diesel::insert_into(users::table)
+                    .values(NewUser {
+                        name: name.to_string(),
+                        email: email.clone(),
+                        // Not a valid hash, so the account can't log in until the password
+                        // is reset
+                        password: "!".to_string(),
+                        role,
+                    })
+                    .returning(users::id)
+                    .get_result(conn)?;
+                self.report.users_created.push(email.clone());
+                user_id
+            }
+        };
+
+        self.users.insert(email, Some(user_id));
+        Ok(user_id)
+    }
+
+    /// The category a post refers to by slug or name, with its parents, created as needed.
+    fn category_id(
+        &mut self,
+        conn: &mut PgConnection,
+        reference: &str,
+        seen: &mut HashSet<String>,
+    ) -> QueryResult<i32> {
+        use crate::db::schema::categories;
+
+        let category = self
+            .batch
+            .categories
+            .iter()
+            .find(|category| category.slug == reference || category.name == reference)
+            .cloned()
+            .unwrap_or_else(|| ImportedCategory {
+                slug: slug::slugify(reference, "category"),
+                name: reference.to_string(),
+                parent_slug: None,
+            });
+        if let Some(category_id) = self.categories.get(&category.slug) {
+            return Ok(*category_id);
+        }
+
+        let existing: Option<i32> = categories::table
+            .filter(
+                categories::slug
+                    .eq(&category.slug)
+                    .or(categories::name.ilike(literal_pattern(&category.name))),
+            )
+            .select(categories::id)
+            .order(categories::id.asc())
+            .first(conn)
+            .optional()?;
+        let category_id = match existing {
+            Some(category_id) => category_id,
+            None => {
+                seen.insert(category.slug.clone());
+                let parent_id = match &category.parent_slug {
+                    // A parent loop in the export only loses the link that closes it
+                    Some(parent) if !seen.contains(parent) => {
+                        Some(self.category_id(conn, parent, seen)?)
+                    }
+                    _ => None,
+                };
+
+                let created = Category::insert(
+                    conn,
+                    NewCategory {
+                        name: category.name.clone(),
+                        slug: category.slug.clone(),
+                        parent_id,
+                        description: None,
+                        cover_image: None,
+                        logo_image: None,
+                        cover_media_id: None,
+                        logo_media_id: None,
+                    },
+                )?;
+                self.report.categories_created.push(category.name);
+                created.id
+            }
+        };
+
+        self.categories.insert(category.slug, category_id);
+        Ok(category_id)
+    }
+
+    fn tag_id(&mut self, conn: &mut PgConnection, name: &str) -> QueryResult<i32> {
+        use crate::db::schema::tags;
+
+        let tag_slug = slug::slugify(name, "tag");
+        if let Some(tag_id) = self.tags.get(&tag_slug) {
+            return Ok(*tag_id);
+        }
+
+        let existing: Option<i32> = tags::table
+            .filter(
+                tags::slug
+                    .eq(&tag_slug)
+                    .or(tags::name.ilike(literal_pattern(name))),
+            )
+            .select(tags::id)
+            .order(tags::id.asc())
+            .first(conn)
+            .optional()?;
+        let tag_id = match existing {
+            Some(tag_id) => tag_id,
+            None => {
+                let created = Tag::insert(
+                    conn,
+                    NewTag {
+                        name: name.to_string(),
+                        slug: tag_slug.clone(),
+                        description: None,
+                    },
+                )?;
+                self.report.tags_created.push(name.to_string());
+                created.id
+            }
+        };
+
+        self.tags.insert(tag_slug, tag_id);
+        Ok(tag_id)
+    }
+
+    /// What an earlier import of the same source created for `external_id`.
+    fn recorded(
+        conn: &mut PgConnection,
+        source: &str,
+        external_id: &str,
+    ) -> QueryResult<Option<ImportRecord>> {
+        use crate::db::schema::import_records;
+
+        import_records::table
+            .filter(import_records::source.eq(source))
+            .filter(import_records::external_id.eq(external_id))
+            .select(ImportRecord::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    fn record(
+        conn: &mut PgConnection,
+        source: &str,
+        external_id: &str,
+        post_id: Option<i32>,
+        comment_id: Option<i32>,
+    ) -> QueryResult<usize> {
+        use crate::db::schema::import_records;
+
+        diesel::insert_into(import_records::table)
+            .values(NewImportRecord {
+                source,
+                external_id,
+                post_id,
+                comment_id,
+            })
+            .execute(conn)
+    }
+}
+
+/// A `LIKE` pattern matching `text` literally, for case-insensitive equality with `ilike`.
+fn literal_pattern(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
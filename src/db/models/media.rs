@@ -0,0 +1,329 @@
+use chrono::{NaiveDateTime, Utc};
+use deadpool_diesel::postgres::Pool;
+use diesel::{
+    deserialize::{self, FromSql},
+    pg::{Pg, PgValue},
+    prelude::*,
+    serialize::{self, Output, ToSql},
+    sql_types::Jsonb,
+    AsExpression, FromSqlRow,
+};
+use serde::{Deserialize, Serialize};
+
+use super::user::User;
+use crate::db::{errors::DBError, schema, utils::execute_db_operation};
+
+/// A resized WebP copy of an upload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaVariant {
+    pub width: i32,
+    pub height: i32,
+    pub storage_key: String,
+    pub url: String,
+}
+
+/// Variants of an upload, smallest first, stored as JSONB.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Jsonb)]
+#[serde(transparent)]
+pub struct MediaVariants(pub Vec<MediaVariant>);
+
+impl FromSql<Jsonb, Pg> for MediaVariants {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let value = <serde_json::Value as FromSql<Jsonb, Pg>>::from_sql(bytes)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl ToSql<Jsonb, Pg> for MediaVariants {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let value = serde_json::to_value(self)?;
+        <serde_json::Value as ToSql<Jsonb, Pg>>::to_sql(&value, &mut out.reborrow())
+    }
+}
+
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone, Serialize, PartialEq)]
+#[diesel(table_name = schema::media)]
+pub struct Media {
+    pub id: i32,
+    pub owner_id: Option<i32>,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    pub url: String,
+    pub original_name: Option<String>,
+    pub content_type: String,
+    pub byte_size: i32,
+    pub width: i32,
+    pub height: i32,
+    pub alt_text: Option<String>,
+    pub variants: MediaVariants,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = schema::media)]
+pub struct NewMedia {
+    pub owner_id: Option<i32>,
+    pub storage_key: String,
+    pub url: String,
+    pub original_name: Option<String>,
+    pub content_type: String,
+    pub byte_size: i32,
+    pub width: i32,
+    pub height: i32,
+    pub alt_text: Option<String>,
+    pub variants: MediaVariants,
+}
+
+/// What refers to a media item.
+#[derive(Debug, Default, Serialize)]
+pub struct MediaUsage {
+    pub post_ids: Vec<i32>,
+    pub category_ids: Vec<i32>,
+    pub user_ids: Vec<i32>,
+}
+
+impl MediaUsage {
+    pub fn is_empty(&self) -> bool {
+        self.post_ids.is_empty() && self.category_ids.is_empty() && self.user_ids.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct MediaWithUsage {
+    #[serde(flatten)]
+    pub media: Media,
+    pub usage: MediaUsage,
+}
+
+/// Outcome of deleting a media item.
+#[derive(Debug)]
+pub enum MediaDeletion {
+    /// The row is gone; its stored files are left to the caller.
+    Deleted(Media),
+    /// Still referenced, so nothing was deleted.
+    InUse(MediaUsage),
+}
+
+impl Media {
+    const PER_PAGE: i64 = 30;
+
+    pub async fn create(pool: &Pool, new_media: NewMedia) -> Result<Self, DBError> {
+        use crate::db::schema::media;
+
+        execute_db_operation(pool, move |conn| {
+            diesel::insert_into(media::table)
+                .values(&new_media)
+                .returning(Self::as_returning())
+                .get_result(conn)
+        })
+        .await
+    }
+
+    /// An upload owned by the user, or any upload for moderators, with what uses it.
+    pub async fn find_by_id(
+        pool: &Pool,
+        user: User,
+        media_id: i32,
+    ) -> Result<Option<MediaWithUsage>, DBError> {
+        use crate::db::schema::media;
+
+        execute_db_operation(pool, move |conn| {
+            let mut query_builder = media::table
+                .filter(media::id.eq(media_id))
+                .select(Self::as_select())
+                .into_boxed();
+
+            if !user.is_mod() {
+                query_builder = query_builder.filter(media::owner_id.eq(user.id));
+            }
+
+            let Some(media) = query_builder.first(conn).optional()? else {
+                return Ok(None);
+            };
+
+            let usage = Self::usage(conn, media.id)?;
+            Ok(Some(MediaWithUsage { media, usage }))
+        })
+        .await
+    }
+
+    /// The library newest first. Moderators see every upload, everyone else their own.
+    pub async fn list(pool: &Pool, user: User, page: i64) -> Result<Vec<Self>, DBError> {
+        use crate::db::schema::media;
+
+        execute_db_operation(pool, move |conn| {
+            let mut query_builder = media::table.select(Self::as_select()).into_boxed();
+
+            if !user.is_mod() {
+                query_builder = query_builder.filter(media::owner_id.eq(user.id));
+            }
+
+            query_builder
+                .order((media::created_at.desc(), media::id.desc()))
+                .limit(Self::PER_PAGE)
+                .offset((page.max(1) - 1) * Self::PER_PAGE)
+                .load(conn)
+        })
+        .await
+    }
+
+    /// Sets the alt text of an upload owned by the user, or of any upload for moderators.
+    pub async fn update_alt_text(
+        pool: &Pool,
+        user: User,
+        media_id: i32,
+        alt_text: Option<String>,
+    ) -> Result<Option<Self>, DBError> {
+        use crate::db::schema::media;
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            let mut query_builder = diesel::update(media::table)
+                .filter(media::id.eq(media_id))
+                .into_boxed();
+
+            if !user.is_mod() {
+                query_builder = query_builder.filter(media::owner_id.eq(user.id));
+            }
+
+            query_builder
+                .set((media::alt_text.eq(alt_text), media::updated_at.eq(now)))
+                .returning(Self::as_returning())
+                .get_result(conn)
+                .optional()
+        })
+        .await
+    }
+
+    /// Deletes an upload owned by the user, or any upload for moderators, unless something
+    /// still uses it.
+    pub async fn delete(
+        pool: &Pool,
+        user: User,
+        media_id: i32,
+    ) -> Result<Option<MediaDeletion>, DBError> {
+        use crate::db::schema::media;
+
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                let media: Option<Self> = media::table
+                    .filter(media::id.eq(media_id))
+                    .select(Self::as_select())
+                    .for_update()
+                    .first(conn)
+                    .optional()?;
+                let Some(media) =
+                    media.filter(|media| user.is_mod() || media.owner_id == Some(user.id))
+                else {
+                    return Ok(None);
+                };
+
+                let usage = Self::usage(conn, media.id)?;
+                if !usage.is_empty() {
+                    return Ok(Some(MediaDeletion::InUse(usage)));
+                }
+
+                diesel::delete(media::table.filter(media::id.eq(media.id))).execute(conn)?;
+                Ok(Some(MediaDeletion::Deleted(media)))
+            })
+        })
+        .await
+    }
+
+    /// Keys of the upload and its variants in storage.
+    pub fn storage_keys(&self) -> Vec<String> {
+        std::iter::once(self.storage_key.clone())
+            .chain(
+                self.variants
+                    .0
+                    .iter()
+                    .map(|variant| variant.storage_key.clone()),
+            )
+            .collect()
+    }
+
+    fn usage(conn: &mut PgConnection, media_id: i32) -> QueryResult<MediaUsage> {
+        use crate::db::schema::{categories, posts, users};
+
+        Ok(MediaUsage {
+            post_ids: posts::table
+                .filter(posts::featured_media_id.eq(media_id))
+                .select(posts::id)
+                .order(posts::id.asc())
+                .load(conn)?,
+            category_ids: categories::table
+                .filter(
+                    categories::cover_media_id
+                        .eq(media_id)
+                        .or(categories::logo_media_id.eq(media_id)),
+                )
+                .select(categories::id)
+                .order(categories::id.asc())
+                .load(conn)?,
+            user_ids: users::table
+                .filter(users::avatar_media_id.eq(media_id))
+                .select(users::id)
+                .order(users::id.asc())
+                .load(conn)?,
+        })
+    }
+
+    /// Makes sure `user` may attach the media item to something: it has to exist and belong
+    /// to them, unless they are a moderator. `None` skips the ownership check for admin-only
+    /// edits. The row stays locked so the upload can't be deleted before it is referenced.
+    pub fn check_attachable(
+        conn: &mut PgConnection,
+        media_id: i32,
+        user: Option<&User>,
+    ) -> Result<(), DBError> {
+        use crate::db::schema::media;
+
+        let owner_id: Option<Option<i32>> = media::table
+            .filter(media::id.eq(media_id))
+            .select(media::owner_id)
+            .for_share()
+            .first(conn)
+            .optional()?;
+        let Some(owner_id) = owner_id else {
+            return Err(DBError::MediaNotFound(media_id));
+        };
+
+        match user {
+            Some(user) if !user.is_mod() && owner_id != Some(user.id) => {
+                Err(DBError::MediaNotOwned(media_id))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Public URL of the media item, if it exists.
+    pub fn url_of(conn: &mut PgConnection, media_id: i32) -> QueryResult<Option<String>> {
+        use crate::db::schema::media;
+
+        media::table
+            .filter(media::id.eq(media_id))
+            .select(media::url)
+            .first(conn)
+            .optional()
+    }
+
+    /// Keeps a URL column in step with the media reference next to it in a changeset: picking
+    /// a media item points the URL at it, and dropping the reference clears the URL unless the
+    /// same edit sets a new one.
+    pub fn sync_url(
+        conn: &mut PgConnection,
+        media_id: Option<Option<i32>>,
+        url: &mut Option<Option<String>>,
+    ) -> QueryResult<()> {
+        match media_id {
+            Some(Some(media_id)) => *url = Some(Self::url_of(conn, media_id)?),
+            Some(None) if url.is_none() => *url = Some(None),
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
@@ -1,8 +1,24 @@
+pub mod archive;
+pub mod bookmark;
 pub mod category;
 pub mod email_verification;
 pub mod forgot_password;
+pub mod import_record;
+pub mod media;
+pub mod pagination;
 pub mod post;
+pub mod post_analytics;
 pub mod post_comment;
+pub mod post_contributor;
+pub mod post_like;
+pub mod post_note;
+pub mod post_preview_token;
+pub mod post_ranking;
+pub mod post_reaction;
+pub mod post_status_transition;
 pub mod post_view;
+pub mod search_suggestion;
+pub mod series;
+pub mod sitemap;
 pub mod tag;
 pub mod user;
@@ -1,8 +1,10 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::query_builder::*;
 use diesel::query_dsl::methods::LoadQuery;
 use diesel::sql_types::BigInt;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 pub trait Paginate: Sized {
     fn paginate(self, page: i64) -> Paginated<Self>;
@@ -71,3 +73,111 @@ where
         Ok(())
     }
 }
+
+/// Where a keyset-paginated page starts: right after (or, going back, right before) the row
+/// with this sort key and id. Clients get it as an opaque string.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cursor<K> {
+    /// The listing the cursor was made for, so it isn't used to page through another one.
+    pub listing: String,
+    pub key: K,
+    pub id: i32,
+    #[serde(default)]
+    pub backward: bool,
+}
+
+impl<K: Serialize + DeserializeOwned> Cursor<K> {
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("cursors serialize to JSON");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let json = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+/// Which page of a listing to load.
+#[derive(Debug, Clone)]
+pub enum PageRequest<K> {
+    /// By number, with `LIMIT/OFFSET` and a total count.
+    Number(i64),
+    /// By cursor, or the first page when there is none yet.
+    Cursor(Option<Cursor<K>>),
+}
+
+impl<K: Serialize + DeserializeOwned> PageRequest<K> {
+    /// Cursor mode when a cursor is given, an empty one meaning the first page; page numbers
+    /// otherwise. `None` if the cursor can't be decoded or belongs to another listing.
+    pub fn from_params(page: Option<i64>, cursor: Option<&str>, listing: &str) -> Option<Self> {
+        match cursor {
+            None => Some(PageRequest::Number(page.unwrap_or(1).max(1))),
+            Some("") => Some(PageRequest::Cursor(None)),
+            Some(cursor) => Cursor::decode(cursor)
+                .filter(|cursor| cursor.listing == listing)
+                .map(|cursor| PageRequest::Cursor(Some(cursor))),
+        }
+    }
+
+    /// Whether rows are loaded in reverse order, to page back from a cursor.
+    pub fn is_backward(&self) -> bool {
+        matches!(self, PageRequest::Cursor(Some(cursor)) if cursor.backward)
+    }
+}
+
+/// A page of a listing with the cursors of the pages before and after it. `total` is only
+/// counted in page-number mode.
+#[derive(Debug, Serialize)]
+pub struct KeysetPage<T> {
+    pub data: Vec<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+impl<T> KeysetPage<T> {
+    /// Builds the page of `listing` from up to `per_page + 1` rows loaded in the request's
+    /// order; the extra row only tells whether there is more.
+    pub fn new<K: Serialize + DeserializeOwned>(
+        mut rows: Vec<T>,
+        per_page: i64,
+        request: &PageRequest<K>,
+        listing: &str,
+        cursor_of: impl Fn(&T) -> (K, i32),
+    ) -> Self {
+        let has_more = rows.len() as i64 > per_page;
+        rows.truncate(per_page.max(0) as usize);
+        if request.is_backward() {
+            rows.reverse();
+        }
+
+        let (has_next, has_prev) = match request {
+            PageRequest::Number(page) => (has_more, *page > 1),
+            PageRequest::Cursor(None) => (has_more, false),
+            PageRequest::Cursor(Some(cursor)) if cursor.backward => (true, has_more),
+            PageRequest::Cursor(Some(_)) => (has_more, true),
+        };
+        let cursor = |row: Option<&T>, backward: bool| {
+            row.map(|row| {
+                let (key, id) = cursor_of(row);
+                Cursor {
+                    listing: listing.to_string(),
+                    key,
+                    id,
+                    backward,
+                }
+                .encode()
+            })
+        };
+
+        KeysetPage {
+            next_cursor: cursor(rows.last().filter(|_| has_next), false),
+            prev_cursor: cursor(rows.first().filter(|_| has_prev), true),
+            data: rows,
+            total: None,
+        }
+    }
+}
+
@@ -1,25 +1,140 @@
 #![allow(unused)]
 #![allow(clippy::all)]
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use super::{category::Category, tag::Tag, user::User};
+use super::{
+    category::Category,
+    media::Media,
+    pagination::{Cursor, KeysetPage, PageRequest},
+    post_contributor::{credited_post_ids, editable_post_ids, ContributorRole, PostContributor},
+    post_like::PostLike,
+    post_preview_token::PostPreviewToken,
+    post_reaction::{PostReaction, ReactionCount},
+    post_status_transition::PostStatusTransition,
+    series::{Series, SeriesNavigation},
+    tag::Tag,
+    user::User,
+};
 use axum::{http::StatusCode, Json};
 use chrono::{Duration, NaiveDateTime, Utc};
 use deadpool_diesel::postgres::Pool;
 use diesel::query_dsl::methods::FindDsl;
 use diesel::QueryDsl;
-use diesel::{debug_query, prelude::*};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Jsonb;
+use diesel::{debug_query, dsl, prelude::*};
 use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
 use tokio::task;
 
-use crate::db::models::post_view::PostView;
 use crate::db::{
     errors::DBError,
     schema,
-    utils::{combine_errors, execute_db_operation},
+    utils::{combine_errors, execute_db_operation, execute_db_transaction, VersionedUpdate},
 };
+use crate::services::{content_renderer, preview, search, slug};
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, diesel_derive_enum::DbEnum,
+)]
+#[ExistingTypePath = "crate::db::schema::sql_types::ContentFormat"]
+#[serde(rename_all = "snake_case")]
+pub enum ContentFormat {
+    #[default]
+    Markdown,
+    Html,
+    Plain,
+}
+
+/// Where a post is in the editorial workflow.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    diesel_derive_enum::DbEnum,
+)]
+#[ExistingTypePath = "crate::db::schema::sql_types::PostStatus"]
+#[serde(rename_all = "snake_case")]
+pub enum PostStatus {
+    #[default]
+    Draft,
+    InReview,
+    ChangesRequested,
+    Approved,
+    Scheduled,
+    Published,
+    Archived,
+}
+
+/// What a user needs to be allowed to move a post between two statuses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkflowPermission {
+    /// The owning author, an editing contributor or a moderator.
+    Edit,
+    /// A moderator, or an editor or reviewer credited on someone else's post.
+    Review,
+    /// Someone who may edit the post and publish without review.
+    PublishDirectly,
+}
+
+impl PostStatus {
+    /// What it takes to move a post from this status to `next`, or `None` if the workflow
+    /// has no such step.
+    pub fn permission_to(self, next: PostStatus) -> Option<WorkflowPermission> {
+        use PostStatus::*;
+        use WorkflowPermission::*;
+
+        match (self, next) {
+            (Draft | ChangesRequested, InReview) => Some(Edit),
+            (InReview, Approved | ChangesRequested) => Some(Review),
+            (InReview | ChangesRequested | Approved | Scheduled | Published | Archived, Draft) => {
+                Some(Edit)
+            }
+            (Approved | Scheduled, Published | Scheduled) => Some(Edit),
+            (Scheduled, Approved) => Some(Edit),
+            (Draft | InReview | ChangesRequested, Published | Scheduled) => Some(PublishDirectly),
+            (Published, Archived) | (Archived, Published) => Some(Edit),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostHeading {
+    pub level: u8,
+    pub text: String,
+    pub anchor: String,
+}
+
+/// Heading outline of a post, stored as JSONB.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Jsonb)]
+#[serde(transparent)]
+pub struct PostOutline(pub Vec<PostHeading>);
+
+impl FromSql<Jsonb, Pg> for PostOutline {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let value = <serde_json::Value as FromSql<Jsonb, Pg>>::from_sql(bytes)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl ToSql<Jsonb, Pg> for PostOutline {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let value = serde_json::to_value(self)?;
+        <serde_json::Value as ToSql<Jsonb, Pg>>::to_sql(&value, &mut out.reborrow())
+    }
+}
 
 #[derive(Queryable, Clone, Debug, Selectable, Identifiable, Serialize, PartialEq)]
 #[diesel(table_name = schema::posts)]
@@ -31,7 +146,6 @@ pub struct Post {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub published_at: Option<NaiveDateTime>,
-    pub is_published: bool,
     pub slug: String,
     pub excerpt: Option<String>,
     pub featured_image_url: Option<String>,
@@ -39,6 +153,20 @@ pub struct Post {
     pub view_count: i32,
     pub likes_count: i32,
     pub tag_ids: Vec<i32>,
+    pub content_format: ContentFormat,
+    pub rendered_html: Option<String>,
+    pub word_count: i32,
+    pub reading_time: i32,
+    pub toc: PostOutline,
+    pub excerpt_generated: bool,
+    pub search_language: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_by: Option<i32>,
+    pub version: i32,
+    pub status: PostStatus,
+    pub featured_media_id: Option<i32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -68,6 +196,14 @@ pub struct PostAuthor {
     pub avatar: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct PostCredit {
+    pub id: i32,
+    pub name: String,
+    pub avatar: Option<String>,
+    pub role: ContributorRole,
+}
+
 #[derive(Debug, Serialize)]
 pub struct PostWithRelations {
     #[serde(flatten)]
@@ -75,6 +211,13 @@ pub struct PostWithRelations {
     pub category: Option<PostCategory>,
     pub tags: Vec<PostTag>,
     pub author: PostAuthor,
+    pub contributors: Vec<PostCredit>,
+    pub reactions: Vec<ReactionCount>,
+    /// Only set when the post is loaded for a signed-in user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub liked_by_me: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series: Option<SeriesNavigation>,
 }
 
 #[derive(Insertable, Deserialize, Debug)]
@@ -84,7 +227,6 @@ pub struct NewPost {
     pub content: String,
     pub author_id: i32,
     pub published_at: Option<NaiveDateTime>,
-    pub is_published: bool,
     pub slug: String,
     pub excerpt: Option<String>,
     pub featured_image_url: Option<String>,
@@ -92,6 +234,10 @@ pub struct NewPost {
     pub view_count: i32,
     pub likes_count: i32,
     pub tag_ids: Vec<i32>,
+    pub content_format: ContentFormat,
+    pub search_language: String,
+    pub status: PostStatus,
+    pub featured_media_id: Option<i32>,
 }
 
 #[derive(AsChangeset, Deserialize, Debug)]
@@ -100,9 +246,7 @@ pub struct UpdatePost {
     pub title: Option<String>,
     pub content: Option<String>,
     pub author_id: Option<i32>,
-    pub published_at: Option<Option<NaiveDateTime>>,
     pub updated_at: NaiveDateTime,
-    pub is_published: Option<bool>,
     pub slug: Option<String>,
     pub excerpt: Option<Option<String>>,
     pub featured_image_url: Option<Option<String>>,
@@ -110,6 +254,74 @@ pub struct UpdatePost {
     pub view_count: Option<i32>,
     pub likes_count: Option<i32>,
     pub tag_ids: Option<Vec<i32>>,
+    pub content_format: Option<ContentFormat>,
+    pub excerpt_generated: Option<bool>,
+    pub search_language: Option<String>,
+    pub featured_media_id: Option<Option<i32>>,
+}
+
+/// Everything derived from rendering a post's content.
+#[derive(AsChangeset, Debug)]
+#[diesel(table_name = schema::posts)]
+pub struct RenderedPost {
+    pub rendered_html: String,
+    pub word_count: i32,
+    pub reading_time: i32,
+    pub toc: PostOutline,
+    pub excerpt: Option<String>,
+    pub excerpt_generated: Option<bool>,
+}
+
+impl RenderedPost {
+    /// Renders `post`, generating its excerpt when the author left it empty.
+    pub fn from_post(post: &Post) -> Self {
+        let rendered = content_renderer::render(&post.content, post.content_format);
+        let generate_excerpt = post.excerpt_generated
+            || post
+                .excerpt
+                .as_deref()
+                .map_or(true, |excerpt| excerpt.trim().is_empty());
+
+        RenderedPost {
+            word_count: rendered.word_count as i32,
+            reading_time: rendered.reading_time() as i32,
+            rendered_html: rendered.html,
+            toc: PostOutline(rendered.outline),
+            excerpt: if generate_excerpt {
+                rendered.excerpt
+            } else {
+                None
+            },
+            excerpt_generated: generate_excerpt.then_some(true),
+        }
+    }
+}
+
+#[derive(AsChangeset, Debug)]
+#[diesel(table_name = schema::posts, treat_none_as_null = true)]
+struct StatusUpdate {
+    status: PostStatus,
+    published_at: Option<NaiveDateTime>,
+    updated_at: NaiveDateTime,
+}
+
+/// A requested move of a post to another workflow status.
+#[derive(Debug)]
+pub struct StatusChangeRequest {
+    pub status: PostStatus,
+    pub comment: Option<String>,
+    /// When a scheduled post goes live.
+    pub publish_at: Option<NaiveDateTime>,
+}
+
+/// Outcome of a workflow status change.
+#[derive(Debug)]
+pub enum StatusChange {
+    Changed(Post),
+    /// The workflow has no step from the post's current status to the requested one.
+    Invalid(Post),
+    /// The step exists but the user may not take it.
+    Forbidden(Post),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -126,28 +338,215 @@ pub enum PostSortBy {
 pub struct PostQuery {
     pub page_no: Option<i64>,
     pub author_id: Option<i32>,
+    pub contributor_id: Option<i32>,
     pub category_id: Option<i32>,
-    pub is_published: Option<bool>,
+    pub status: Option<PostStatus>,
     pub search: Option<String>,
     pub sort_by: Option<PostSortBy>,
     pub sort_order: Option<String>,
     pub tag_ids: Option<Vec<i32>>,
 }
 
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct PostSearchQuery {
+    pub terms: String,
+    pub language: Option<String>,
+    pub page_no: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostSearchResult {
+    #[serde(flatten)]
+    pub post: PostWithRelations,
+    pub rank: f32,
+    /// Title and content fragments with the matches wrapped in `<mark>`, safe to render as HTML.
+    pub title_highlight: String,
+    pub snippet: String,
+}
+
+#[derive(QueryableByName, Debug)]
+struct PostSearchHit {
+    #[diesel(sql_type = diesel::sql_types::Int4)]
+    id: i32,
+    #[diesel(sql_type = diesel::sql_types::Float4)]
+    rank: f32,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    title_highlight: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    snippet: String,
+    #[diesel(sql_type = diesel::sql_types::Int8)]
+    total: i64,
+}
+
+/// Ranks matching posts, then highlights only the requested page.
+///
+/// Binds: language filter, tsquery, viewer is mod, viewer id, now, limit, offset.
+///
+/// The input is parsed once per language posts are indexed in, and every post is matched and
+/// highlighted with its own language.
+/// The snippet is cut from the sanitized HTML with its tags stripped, so the text is already
+/// escaped; the title is escaped here before `<mark>` is added around the matches.
+const SEARCH_SQL: &str = r#"
+WITH search AS (
+    SELECT languages.language, to_tsquery(languages.language::regconfig, $2) AS query
+    FROM (
+        SELECT DISTINCT search_language AS language
+        FROM posts
+        WHERE $1::varchar IS NULL OR search_language = $1
+    ) languages
+),
+hits AS (
+    SELECT posts.id,
+           ts_rank(posts.search_vector, search.query, 1) AS rank,
+           COUNT(*) OVER () AS total
+    FROM posts
+    JOIN search ON search.language = posts.search_language
+    WHERE posts.search_vector @@ search.query
+      AND posts.deleted_at IS NULL
+      AND ($3 OR posts.author_id = $4
+           OR EXISTS (SELECT 1 FROM post_contributors
+                      WHERE post_contributors.post_id = posts.id AND post_contributors.user_id = $4)
+           OR (posts.status = 'published' AND (posts.published_at IS NULL OR posts.published_at <= $5)))
+    ORDER BY rank DESC, posts.published_at DESC NULLS LAST, posts.id DESC
+    LIMIT $6 OFFSET $7
+)
+SELECT hits.id,
+       hits.rank,
+       ts_headline(search.language::regconfig,
+           replace(replace(replace(posts.title, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'),
+           search.query, 'HighlightAll=true, StartSel=<mark>, StopSel=</mark>') AS title_highlight,
+       ts_headline(search.language::regconfig,
+           regexp_replace(coalesce(posts.rendered_html, ''), '<[^>]*>', ' ', 'g'),
+           search.query,
+           'StartSel=<mark>, StopSel=</mark>, MaxWords=35, MinWords=15, MaxFragments=2, FragmentDelimiter=" … "') AS snippet,
+       hits.total
+FROM hits
+JOIN posts ON posts.id = hits.id
+JOIN search ON search.language = posts.search_language
+ORDER BY hits.rank DESC, posts.published_at DESC NULLS LAST, posts.id DESC
+"#;
+
+/// Score of a post related to another one; only these are cached, the posts are loaded fresh.
+#[derive(QueryableByName, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RelatedPostScore {
+    #[diesel(sql_type = diesel::sql_types::Int4)]
+    pub id: i32,
+    #[diesel(sql_type = diesel::sql_types::Float4)]
+    pub score: f32,
+}
+
+/// Which published posts a syndication feed covers.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FeedScope {
+    All,
+    Category(String),
+    Tag(String),
+    Author(i32),
+}
+
+#[derive(Debug)]
+pub struct PostFeed {
+    /// Name of the category, tag or author the feed is scoped to.
+    pub scope_name: Option<String>,
+    pub posts: Vec<PostWithRelations>,
+    /// Latest time a post in the scope was edited, went live or was trashed, so it never
+    /// goes back when a post leaves the feed.
+    pub last_modified: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelatedPost {
+    #[serde(flatten)]
+    pub post: PostWithRelations,
+    pub score: f32,
+}
+
+/// Binds: source post id, now, limit.
+///
+/// Candidates share a tag or the category with the source, or have a similar title. Each
+/// shared tag weighs 3, the same category 2, title trigram similarity up to 2 and recency
+/// up to 1, halving after 30 days.
+const RELATED_SQL: &str = r#"
+WITH source AS (
+    SELECT id, title, tag_ids, category_id FROM posts WHERE id = $1
+)
+SELECT posts.id,
+       (3.0 * cardinality(ARRAY(
+            SELECT unnest(posts.tag_ids) INTERSECT SELECT unnest(source.tag_ids)))
+        + CASE WHEN posts.category_id = source.category_id THEN 2.0 ELSE 0.0 END
+        + 2.0 * similarity(posts.title, source.title)
+        + 1.0 / (1.0 + extract(epoch FROM ($2 - coalesce(posts.published_at, posts.created_at)))
+                 / 2592000.0)
+       )::real AS score
+FROM posts, source
+WHERE posts.id <> source.id
+  AND posts.deleted_at IS NULL
+  AND posts.status = 'published' AND (posts.published_at IS NULL OR posts.published_at <= $2)
+  AND (posts.tag_ids && source.tag_ids
+       OR posts.category_id = source.category_id
+       OR posts.title % source.title)
+ORDER BY score DESC, posts.id DESC
+LIMIT $3
+"#;
+
+/// Published posts whose publish time has been reached and that aren't in the trash.
+#[dsl::auto_type]
+pub fn is_live(now: NaiveDateTime) -> _ {
+    let published: dsl::Eq<schema::posts::status, PostStatus> =
+        schema::posts::status.eq(PostStatus::Published);
+    published
+        .and(
+            schema::posts::published_at
+                .is_null()
+                .or(schema::posts::published_at.le(now)),
+        )
+        .and(schema::posts::deleted_at.is_null())
+}
+
 impl Post {
     pub const PER_PAGE: i64 = 16;
+    /// Listing name in the cursors of `find_published_paginated`.
+    pub const PUBLISHED_LISTING: &'static str = "published_posts";
+
+    /// Whether the post is live, outside of a transaction.
+    pub async fn is_live(pool: &Pool, post_id: i32) -> Result<bool, DBError> {
+        execute_db_operation(pool, move |conn| Self::exists_live(conn, post_id)).await
+    }
+
+    pub fn exists_live(
+        conn: &mut PgConnection,
+        post_id: i32,
+    ) -> Result<bool, diesel::result::Error> {
+        use crate::db::schema::posts;
+
+        let now = Utc::now().naive_utc();
+        diesel::select(diesel::dsl::exists(
+            posts::table
+                .filter(posts::id.eq(post_id))
+                .filter(is_live(now)),
+        ))
+        .get_result(conn)
+    }
+
+    /// Live posts are visible to everyone. Drafts and scheduled posts only to moderators, the
+    /// people credited on them and holders of a valid preview token.
     pub async fn find_by_id_or_slug(
         pool: &Pool,
         post_id: Option<i32>,
         post_slug: Option<String>,
+        viewer: Option<User>,
+        preview_token: Option<String>,
     ) -> Result<Option<PostWithRelations>, DBError> {
         use crate::db::schema::posts::dsl::*;
         use crate::db::schema::{categories, posts, tags, users};
 
+        let now = Utc::now().naive_utc();
+
         execute_db_operation(pool, move |conn| {
             let mut query_builder = posts::table
                 .left_join(categories::table)
                 .inner_join(users::table)
+                .filter(posts::deleted_at.is_null())
                 .into_boxed();
 
             query_builder = match (post_id, post_slug) {
@@ -165,52 +564,66 @@ impl Post {
                 .first(conn)
                 .optional()?;
 
-            if let Some((post, category, author)) = result {
-                // Get all relevant tags
-                let tags_map: HashMap<i32, Tag> = if !post.tag_ids.is_empty() {
-                    tags::table
-                        .filter(tags::dsl::id.eq_any(&post.tag_ids))
-                        .load::<Tag>(conn)?
-                        .into_iter()
-                        .map(|tag| (tag.id, tag))
-                        .collect()
-                } else {
-                    HashMap::new()
-                };
-
-                // Transform the result into PostWithRelations
-                let post_with_relations = PostWithRelations {
-                    post: post.clone(),
-                    category: category.map(|c| PostCategory {
-                        id: c.id,
-                        name: c.name,
-                    }),
-                    tags: post
-                        .tag_ids
-                        .iter()
-                        .filter_map(|&tag_id| {
-                            tags_map.get(&tag_id).map(|tag| PostTag {
-                                id: tag.id,
-                                name: tag.name.clone(),
-                            })
-                        })
-                        .collect(),
-                    author: PostAuthor {
-                        id: author.id,
-                        name: author.name,
-                        email: author.email,
-                        avatar: author.avatar,
-                    },
-                };
+            let Some(result) = result else {
+                return Ok(None);
+            };
+            if !result.0.is_live_at(now)
+                && !Self::can_preview(
+                    conn,
+                    &result.0,
+                    viewer.as_ref(),
+                    preview_token.as_deref(),
+                    now,
+                )?
+            {
+                return Ok(None);
+            }
 
-                Ok(Some(post_with_relations))
-            } else {
-                Ok(None)
+            let viewer_id = viewer.map(|viewer| viewer.id);
+            let mut post = Self::with_relations(conn, vec![result], viewer_id)?.pop();
+            if let Some(post) = post.as_mut() {
+                post.series = Series::navigation(conn, post.post.id)?;
             }
+
+            Ok(post)
         })
         .await
     }
 
+    pub fn is_live_at(&self, now: NaiveDateTime) -> bool {
+        self.status == PostStatus::Published && self.published_at.map_or(true, |date| date <= now)
+    }
+
+    fn can_preview(
+        conn: &mut PgConnection,
+        post: &Post,
+        viewer: Option<&User>,
+        preview_token: Option<&str>,
+        now: NaiveDateTime,
+    ) -> Result<bool, diesel::result::Error> {
+        use crate::db::schema::post_contributors;
+
+        if let Some(viewer) = viewer {
+            if viewer.is_mod() || viewer.id == post.author_id {
+                return Ok(true);
+            }
+            let credited = diesel::select(dsl::exists(
+                post_contributors::table
+                    .filter(post_contributors::post_id.eq(post.id))
+                    .filter(post_contributors::user_id.eq(viewer.id)),
+            ))
+            .get_result(conn)?;
+            if credited {
+                return Ok(true);
+            }
+        }
+
+        match preview_token.and_then(|token| preview::verify(token, post.id, now)) {
+            Some(token_id) => PostPreviewToken::is_active(conn, token_id, post.id, now),
+            None => Ok(false),
+        }
+    }
+
     pub async fn find_posts_with_query(
         pool: &Pool,
         query: PostQuery,
@@ -222,6 +635,7 @@ impl Post {
             let mut query_builder = posts::table
                 .left_join(categories::table)
                 .inner_join(users::table)
+                .filter(posts::deleted_at.is_null())
                 .into_boxed();
 
             // Apply existing filters
@@ -231,21 +645,31 @@ impl Post {
                     query_builder = query_builder.filter(posts::author_id.eq(author_id_filter));
                 }
             } else {
-                query_builder = query_builder.filter(posts::author_id.eq(user.id));
+                query_builder = query_builder.filter(
+                    posts::author_id
+                        .eq(user.id)
+                        .or(posts::id.eq_any(credited_post_ids(user.id))),
+                );
+            }
+
+            if let Some(contributor_id_filter) = query.contributor_id {
+                query_builder =
+                    query_builder.filter(posts::id.eq_any(credited_post_ids(contributor_id_filter)));
             }
 
             if let Some(category_id_filter) = query.category_id {
                 query_builder = query_builder.filter(posts::category_id.eq(category_id_filter));
             }
-            if let Some(is_published_filter) = query.is_published {
-                query_builder = query_builder.filter(posts::is_published.eq(is_published_filter));
+            if let Some(status_filter) = query.status {
+                query_builder = query_builder.filter(posts::status.eq(status_filter));
             }
-            if let Some(search_term) = query.search {
-                let search_pattern = format!("%{}%", search_term.to_lowercase());
+            if let Some(tsquery) = query.search.as_deref().and_then(search::build_tsquery) {
                 query_builder = query_builder.filter(
-                    posts::title
-                        .ilike(search_pattern.clone())
-                        .or(posts::content.ilike(search_pattern)),
+                    dsl::sql::<diesel::sql_types::Bool>(
+                        "posts.search_vector @@ to_tsquery(posts.search_language::regconfig, ",
+                    )
+                    .bind::<diesel::sql_types::Text, _>(tsquery)
+                    .sql(")"),
                 );
             }
 
@@ -287,76 +711,208 @@ impl Post {
                 ))
                 .load(conn)?;
 
-            // Get all relevant tags
-            let all_tag_ids: Vec<i32> = results
-                .iter()
-                .flat_map(|(post, _, _)| post.tag_ids.clone())
-                .collect();
+            Self::with_relations(conn, results, Some(user.id))
+        })
+        .await
+    }
 
-            let tags_map: HashMap<i32, Tag> = if !all_tag_ids.is_empty() {
-                tags::table
-                    .filter(tags::dsl::id.eq_any(all_tag_ids))
-                    .load::<Tag>(conn)?
-                    .into_iter()
-                    .map(|tag| (tag.id, tag))
-                    .collect()
-            } else {
-                HashMap::new()
+    /// Live posts, most recently published first, by page number or by cursor over the publish
+    /// time (the creation time for posts without one) and id. Edits don't move a post, so
+    /// cursors stay put while posts are updated.
+    pub async fn find_published_paginated(
+        pool: &Pool,
+        page: PageRequest<NaiveDateTime>,
+        viewer_id: Option<i32>,
+    ) -> Result<KeysetPage<PostWithRelations>, DBError> {
+        use crate::db::schema::posts::dsl::*;
+        use crate::db::schema::{categories, posts, users};
+
+        use diesel::sql_types::{Bool, Int4, Timestamptz};
+
+        let now = Utc::now().naive_utc();
+        let published =
+            || dsl::sql::<Timestamptz>("COALESCE(posts.published_at, posts.created_at)");
+        let past_cursor = |cursor: &Cursor<NaiveDateTime>, comparison: &str| {
+            dsl::sql::<Bool>("(COALESCE(posts.published_at, posts.created_at), posts.id) ")
+                .sql(comparison)
+                .sql(" (")
+                .bind::<Timestamptz, _>(cursor.key)
+                .sql(", ")
+                .bind::<Int4, _>(cursor.id)
+                .sql(")")
+        };
+
+        execute_db_operation(pool, move |conn| {
+            let total = match page {
+                PageRequest::Number(_) => {
+                    Some(posts.filter(is_live(now)).count().get_result(conn)?)
+                }
+                PageRequest::Cursor(_) => None,
             };
 
-            // Transform the results into PostWithRelations
-            let posts_with_relations = results
+            let mut query_builder = posts::table
+                .left_join(categories::table)
+                .inner_join(users::table)
+                .filter(is_live(now))
+                .select((
+                    Post::as_select(),
+                    Option::<Category>::as_select(),
+                    User::as_select(),
+                ))
+                .limit(Self::PER_PAGE + 1)
+                .into_boxed();
+
+            query_builder = match &page {
+                PageRequest::Number(page_no) => query_builder
+                    .order((published().desc(), id.desc()))
+                    .offset((page_no - 1) * Self::PER_PAGE),
+                PageRequest::Cursor(None) => query_builder.order((published().desc(), id.desc())),
+                PageRequest::Cursor(Some(cursor)) if cursor.backward => query_builder
+                    .filter(past_cursor(cursor, ">"))
+                    .order((published().asc(), id.asc())),
+                PageRequest::Cursor(Some(cursor)) => query_builder
+                    .filter(past_cursor(cursor, "<"))
+                    .order((published().desc(), id.desc())),
+            };
+
+            let results: Vec<(Post, Option<Category>, User)> = query_builder.load(conn)?;
+            let mut page = KeysetPage::new(
+                Self::with_relations(conn, results, viewer_id)?,
+                Self::PER_PAGE,
+                &page,
+                Self::PUBLISHED_LISTING,
+                |post| {
+                    let key = post.post.published_at.unwrap_or(post.post.created_at);
+                    (key, post.post.id)
+                },
+            );
+            page.total = total;
+
+            Ok(page)
+        })
+        .await
+    }
+
+    /// Full-text search ordered by relevance.
+    ///
+    /// Anonymous visitors only find live posts, authors also find the drafts they own or
+    /// contribute to and moderators find everything.
+    pub async fn search(
+        pool: &Pool,
+        query: PostSearchQuery,
+        viewer: Option<User>,
+    ) -> Result<(Vec<PostSearchResult>, i64), DBError> {
+        use crate::db::schema::{categories, posts, users};
+        use diesel::sql_types::{BigInt, Bool, Int4, Nullable, Text, Timestamptz, Varchar};
+
+        let Some(tsquery) = search::build_tsquery(&query.terms) else {
+            return Ok((Vec::new(), 0));
+        };
+        let viewer_is_mod = viewer.as_ref().is_some_and(|user| user.is_mod());
+        let liker_id = viewer.as_ref().map(|user| user.id);
+        let viewer_id = viewer.filter(|user| user.is_author()).map(|user| user.id);
+        let page = query.page_no.unwrap_or(1);
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            let hits: Vec<PostSearchHit> = diesel::sql_query(SEARCH_SQL)
+                .bind::<Nullable<Varchar>, _>(query.language)
+                .bind::<Text, _>(tsquery)
+                .bind::<Bool, _>(viewer_is_mod)
+                .bind::<Nullable<Int4>, _>(viewer_id)
+                .bind::<Timestamptz, _>(now)
+                .bind::<BigInt, _>(Self::PER_PAGE)
+                .bind::<BigInt, _>((page - 1) * Self::PER_PAGE)
+                .load(conn)?;
+
+            let total = hits.first().map_or(0, |hit| hit.total);
+            let hit_ids: Vec<i32> = hits.iter().map(|hit| hit.id).collect();
+
+            let results: Vec<(Post, Option<Category>, User)> = posts::table
+                .left_join(categories::table)
+                .inner_join(users::table)
+                .filter(posts::id.eq_any(hit_ids))
+                .select((
+                    Post::as_select(),
+                    Option::<Category>::as_select(),
+                    User::as_select(),
+                ))
+                .load(conn)?;
+
+            let mut posts_by_id: HashMap<i32, PostWithRelations> =
+                Self::with_relations(conn, results, liker_id)?
+                    .into_iter()
+                    .map(|post| (post.post.id, post))
+                    .collect();
+
+            let search_results = hits
                 .into_iter()
-                .map(|(post, category, author)| PostWithRelations {
-                    post: post.clone(),
-                    category: category.map(|c| PostCategory {
-                        id: c.id,
-                        name: c.name,
-                    }),
-                    tags: post
-                        .tag_ids
-                        .iter()
-                        .filter_map(|&tag_id| {
-                            tags_map.get(&tag_id).map(|tag| PostTag {
-                                id: tag.id,
-                                name: tag.name.clone(),
-                            })
-                        })
-                        .collect(),
-                    author: PostAuthor {
-                        id: author.id,
-                        name: author.name,
-                        email: author.email,
-                        avatar: author.avatar,
-                    },
+                .filter_map(|hit| {
+                    posts_by_id.remove(&hit.id).map(|post| PostSearchResult {
+                        post,
+                        rank: hit.rank,
+                        title_highlight: hit.title_highlight,
+                        snippet: hit.snippet,
+                    })
                 })
                 .collect();
 
-            Ok(posts_with_relations)
+            Ok((search_results, total))
         })
         .await
     }
 
-    pub async fn find_published_paginated(
+    /// Scores the published posts related to a live post, or `None` if there is no such post.
+    pub async fn related_scores(
         pool: &Pool,
-        page: i64,
-    ) -> Result<(Vec<PostWithRelations>, i64), DBError> {
+        post_id: Option<i32>,
+        post_slug: Option<String>,
+        limit: i64,
+    ) -> Result<Option<Vec<RelatedPostScore>>, DBError> {
         use crate::db::schema::posts::dsl::*;
-        use crate::db::schema::{categories, posts, tags, users};
+        use diesel::sql_types::{BigInt, Int4, Timestamptz};
+
+        let now = Utc::now().naive_utc();
 
         execute_db_operation(pool, move |conn| {
-            let total = posts
-                .filter(is_published.eq(true))
-                .count()
-                .get_result(conn)?;
+            let mut query_builder = posts.filter(is_live(now)).select(id).into_boxed();
+
+            query_builder = match (post_id, post_slug) {
+                (Some(post_id), _) => query_builder.filter(id.eq(post_id)),
+                (_, Some(post_slug)) => query_builder.filter(slug.eq(post_slug)),
+                _ => return Ok(None),
+            };
+
+            let Some(source_id) = query_builder.first::<i32>(conn).optional()? else {
+                return Ok(None);
+            };
+
+            diesel::sql_query(RELATED_SQL)
+                .bind::<Int4, _>(source_id)
+                .bind::<Timestamptz, _>(now)
+                .bind::<BigInt, _>(limit)
+                .load(conn)
+                .map(Some)
+        })
+        .await
+    }
 
+    /// Loads the scored posts in score order, skipping any that are no longer live.
+    pub async fn find_related(
+        pool: &Pool,
+        scores: Vec<RelatedPostScore>,
+        viewer_id: Option<i32>,
+    ) -> Result<Vec<RelatedPost>, DBError> {
+        use crate::db::schema::{categories, posts, users};
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
             let results: Vec<(Post, Option<Category>, User)> = posts::table
                 .left_join(categories::table)
                 .inner_join(users::table)
-                .filter(is_published.eq(true))
-                .order(updated_at.desc())
-                .limit(Self::PER_PAGE)
-                .offset((page - 1) * Self::PER_PAGE)
+                .filter(posts::id.eq_any(scores.iter().map(|related| related.id)))
+                .filter(is_live(now))
                 .select((
                     Post::as_select(),
                     Option::<Category>::as_select(),
@@ -364,133 +920,697 @@ impl Post {
                 ))
                 .load(conn)?;
 
-            // Get all relevant tags
-            let all_tag_ids: Vec<i32> = results
-                .iter()
-                .flat_map(|(post, _, _)| post.tag_ids.clone())
-                .collect();
-
-            let tags_map: HashMap<i32, Tag> = if !all_tag_ids.is_empty() {
-                tags::table
-                    .filter(tags::dsl::id.eq_any(all_tag_ids))
-                    .load::<Tag>(conn)?
+            let mut posts_by_id: HashMap<i32, PostWithRelations> =
+                Self::with_relations(conn, results, viewer_id)?
                     .into_iter()
-                    .map(|tag| (tag.id, tag))
-                    .collect()
-            } else {
-                HashMap::new()
-            };
+                    .map(|post| (post.post.id, post))
+                    .collect();
+
+            Ok(scores
+                .into_iter()
+                .filter_map(|related| {
+                    posts_by_id.remove(&related.id).map(|post| RelatedPost {
+                        post,
+                        score: related.score,
+                    })
+                })
+                .collect())
+        })
+        .await
+    }
+
+    /// Attaches the category, tags, author, contributors and reactions to each loaded post,
+    /// keeping their order. With a viewer, also flags the posts they liked.
+    pub fn with_relations(
+        conn: &mut PgConnection,
+        results: Vec<(Post, Option<Category>, User)>,
+        viewer_id: Option<i32>,
+    ) -> Result<Vec<PostWithRelations>, diesel::result::Error> {
+        use crate::db::schema::tags;
+
+        // Get all relevant tags
+        let all_tag_ids: Vec<i32> = results
+            .iter()
+            .flat_map(|(post, _, _)| post.tag_ids.clone())
+            .collect();
 
-            // Transform the results into PostWithRelations
-            let posts_with_relations = results
+        let tags_map: HashMap<i32, Tag> = if !all_tag_ids.is_empty() {
+            tags::table
+                .filter(tags::dsl::id.eq_any(all_tag_ids))
+                .filter(tags::dsl::deleted_at.is_null())
+                .load::<Tag>(conn)?
                 .into_iter()
-                .map(|(post, category, author)| PostWithRelations {
-                    post: post.clone(),
-                    category: category.map(|c| PostCategory {
+                .map(|tag| (tag.id, tag))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let post_ids: Vec<i32> = results.iter().map(|(post, _, _)| post.id).collect();
+        let mut credits = PostContributor::credits(conn, &post_ids)?;
+        let mut reactions = PostReaction::counts(conn, &post_ids, viewer_id)?;
+        let liked = match viewer_id {
+            Some(viewer_id) => Some(PostLike::liked_ids(conn, viewer_id, &post_ids)?),
+            None => None,
+        };
+
+        // Transform the results into PostWithRelations
+        let posts_with_relations = results
+            .into_iter()
+            .map(|(post, category, author)| PostWithRelations {
+                category: category
+                    .filter(|c| c.deleted_at.is_none())
+                    .map(|c| PostCategory {
                         id: c.id,
                         name: c.name,
                     }),
-                    tags: post
-                        .tag_ids
-                        .iter()
-                        .filter_map(|&tag_id| {
-                            tags_map.get(&tag_id).map(|tag| PostTag {
-                                id: tag.id,
-                                name: tag.name.clone(),
-                            })
+                tags: post
+                    .tag_ids
+                    .iter()
+                    .filter_map(|&tag_id| {
+                        tags_map.get(&tag_id).map(|tag| PostTag {
+                            id: tag.id,
+                            name: tag.name.clone(),
                         })
-                        .collect(),
-                    author: PostAuthor {
-                        id: author.id,
-                        name: author.name,
-                        email: author.email,
-                        avatar: author.avatar,
-                    },
-                })
-                .collect();
+                    })
+                    .collect(),
+                author: PostAuthor {
+                    id: author.id,
+                    name: author.name,
+                    email: author.email,
+                    avatar: author.avatar,
+                },
+                contributors: credits.remove(&post.id).unwrap_or_default(),
+                reactions: reactions.remove(&post.id).unwrap_or_default(),
+                liked_by_me: liked.as_ref().map(|liked| liked.contains(&post.id)),
+                series: None,
+                post,
+            })
+            .collect();
 
-            Ok((posts_with_relations, total))
+        Ok(posts_with_relations)
+    }
+
+    /// Creates a post. A featured image has to be one of the author's uploads unless they are
+    /// a moderator.
+    pub async fn create(pool: &Pool, new_post: NewPost) -> Result<Self, DBError> {
+        use crate::db::schema::users;
+
+        execute_db_transaction(pool, move |conn| {
+            if let Some(media_id) = new_post.featured_media_id {
+                let author: User = users::table
+                    .filter(users::id.eq(new_post.author_id))
+                    .select(User::as_select())
+                    .first(conn)?;
+                Media::check_attachable(conn, media_id, Some(&author))?;
+            }
+
+            Ok(Self::insert(conn, new_post)?)
         })
         .await
     }
 
-    pub async fn create(pool: &Pool, new_post: NewPost) -> Result<Self, DBError> {
+    /// Inserts a post along with its author credit, first workflow step and render. Run it in
+    /// a transaction.
+    pub fn insert(
+        conn: &mut PgConnection,
+        mut new_post: NewPost,
+    ) -> Result<Self, diesel::result::Error> {
         use crate::db::schema::posts::dsl::*;
 
+        if new_post.slug.is_empty() {
+            new_post.slug = Self::unique_slug(conn, &new_post.title)?;
+        }
+        if let Some(media_id) = new_post.featured_media_id {
+            new_post.featured_image_url = Media::url_of(conn, media_id)?;
+        }
+
+        let post: Self = diesel::insert_into(posts)
+            .values(&new_post)
+            .get_result(conn)?;
+        PostContributor::insert_author(conn, post.id, post.author_id)?;
+        PostStatusTransition::record(conn, post.id, None, post.status, Some(post.author_id), None)?;
+        Self::store_render(conn, &post)
+    }
+
+    /// Whether a post uses `post_slug` now or has used it before.
+    pub fn slug_taken(
+        conn: &mut PgConnection,
+        post_slug: &str,
+    ) -> Result<bool, diesel::result::Error> {
+        use crate::db::schema::{post_slug_history, posts};
+
+        let in_use = diesel::select(dsl::exists(posts::table.filter(posts::slug.eq(post_slug))))
+            .get_result(conn)?;
+        if in_use {
+            return Ok(true);
+        }
+
+        diesel::select(dsl::exists(
+            post_slug_history::table.filter(post_slug_history::slug.eq(post_slug)),
+        ))
+        .get_result(conn)
+    }
+
+    /// A slug derived from `title` that no post uses now or has used before.
+    fn unique_slug(conn: &mut PgConnection, title: &str) -> Result<String, diesel::result::Error> {
+        use crate::db::schema::{post_slug_history, posts};
+
+        let base = slug::slugify(title, "post");
+        let pattern = format!("{}%", base);
+        let mut taken: HashSet<String> = posts::table
+            .filter(posts::slug.like(&pattern))
+            .select(posts::slug)
+            .load::<String>(conn)?
+            .into_iter()
+            .collect();
+        taken.extend(
+            post_slug_history::table
+                .filter(post_slug_history::slug.like(&pattern))
+                .select(post_slug_history::slug)
+                .load::<String>(conn)?,
+        );
+
+        Ok(slug::unique(&base, &taken))
+    }
+
+    /// The current slug of the live post that was previously published under `old_slug`.
+    /// Unpublished posts are not redirected so their new slug does not leak.
+    pub async fn find_redirected_slug(
+        pool: &Pool,
+        old_slug: String,
+    ) -> Result<Option<String>, DBError> {
+        use crate::db::schema::{post_slug_history, posts};
+
         execute_db_operation(pool, move |conn| {
-            diesel::insert_into(posts)
-                .values(&new_post)
-                .get_result(conn)
+            let now = Utc::now().naive_utc();
+
+            post_slug_history::table
+                .inner_join(posts::table)
+                .filter(post_slug_history::slug.eq(old_slug))
+                .filter(is_live(now))
+                .select(posts::slug)
+                .first(conn)
+                .optional()
         })
         .await
     }
 
+    fn store_render(conn: &mut PgConnection, post: &Self) -> Result<Self, diesel::result::Error> {
+        use crate::db::schema::posts::dsl::*;
+
+        diesel::update(posts.filter(id.eq(post.id)))
+            .set(RenderedPost::from_post(post))
+            .returning(Self::as_returning())
+            .get_result(conn)
+    }
+
+    /// Applies an edit if the post is still at one of `expected_versions` (any version when
+    /// `None`), bumping its version. Returns the post as it is now on a version conflict.
     pub async fn update(
         pool: &Pool,
         post_id: i32,
         user: User,
         update_post: UpdatePost,
-    ) -> Result<Option<Self>, DBError> {
+        expected_versions: Option<Vec<i32>>,
+    ) -> Result<Option<VersionedUpdate<Self>>, DBError> {
         use crate::db::schema::posts::dsl::*;
 
-        execute_db_operation(pool, move |conn| {
+        let needs_render = update_post.content.is_some()
+            || update_post.content_format.is_some()
+            || update_post.excerpt.is_some();
+
+        execute_db_transaction(pool, move |conn| {
+            let mut update_post = update_post;
+            if let Some(Some(media_id)) = update_post.featured_media_id {
+                Media::check_attachable(conn, media_id, Some(&user))?;
+            }
+            Media::sync_url(
+                conn,
+                update_post.featured_media_id,
+                &mut update_post.featured_image_url,
+            )?;
+
+            let mut query_builder = diesel::update(posts)
+                .filter(id.eq(post_id))
+                .filter(deleted_at.is_null())
+                .into_boxed();
+
             if !user.is_mod() {
-                return diesel::update(posts.filter(id.eq(post_id).and(author_id.eq(user.id))))
-                    .set(&update_post)
-                    .returning(Self::as_returning())
-                    .get_result(conn)
-                    .optional();
+                query_builder = query_builder.filter(
+                    author_id
+                        .eq(user.id)
+                        .or(id.eq_any(editable_post_ids(user.id))),
+                );
+            }
+            if let Some(expected_versions) = expected_versions {
+                query_builder = query_builder.filter(version.eq_any(expected_versions));
+            }
+
+            let post: Option<Self> = query_builder
+                .set((&update_post, version.eq(version + 1)))
+                .returning(Self::as_returning())
+                .get_result(conn)
+                .optional()?;
+
+            let Some(post) = post else {
+                // Either the post is gone or someone else saved it first
+                return Ok(
+                    Self::find_editable(conn, post_id, &user)?.map(VersionedUpdate::Conflict)
+                );
+            };
+
+            // The cached HTML and stats are stale as soon as the source or its format changes,
+            // and a cleared excerpt has to be generated again
+            let post = if needs_render {
+                Self::store_render(conn, &post)?
             } else {
-                return diesel::update(posts.filter(id.eq(post_id)))
-                    .set(&update_post)
-                    .returning(Self::as_returning())
-                    .get_result(conn)
-                    .optional();
+                post
+            };
+
+            Ok(Some(VersionedUpdate::Updated(post)))
+        })
+        .await
+    }
+
+    /// The post if the user may edit it, as the owner, an editing contributor or a moderator.
+    pub fn find_editable(
+        conn: &mut PgConnection,
+        post_id: i32,
+        user: &User,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        use crate::db::schema::posts::dsl::*;
+
+        let mut query_builder = posts
+            .filter(id.eq(post_id))
+            .filter(deleted_at.is_null())
+            .select(Self::as_select())
+            .into_boxed();
+
+        if !user.is_mod() {
+            query_builder = query_builder.filter(
+                author_id
+                    .eq(user.id)
+                    .or(id.eq_any(editable_post_ids(user.id))),
+            );
+        }
+
+        query_builder.first(conn).optional()
+    }
+
+    /// Renders posts whose HTML cache is empty, e.g. rows that predate the cache.
+    pub async fn render_pending(pool: &Pool, limit: i64) -> Result<usize, DBError> {
+        use crate::db::schema::posts::dsl::*;
+
+        execute_db_operation(pool, move |conn| {
+            let pending: Vec<Self> = posts
+                .filter(rendered_html.is_null())
+                .select(Self::as_select())
+                .limit(limit)
+                .load(conn)?;
+
+            for post in pending.iter() {
+                Self::store_render(conn, post)?;
             }
+
+            Ok(pending.len())
+        })
+        .await
+    }
+
+    pub async fn schedule(
+        pool: &Pool,
+        post_id: i32,
+        user: User,
+        publish_at: NaiveDateTime,
+        can_publish_directly: bool,
+    ) -> Result<Option<StatusChange>, DBError> {
+        let request = StatusChangeRequest {
+            status: PostStatus::Scheduled,
+            comment: None,
+            publish_at: Some(publish_at),
+        };
+
+        Self::change_status(pool, post_id, user, request, can_publish_directly).await
+    }
+
+    /// Cancels a pending publication. The post stays approved.
+    pub async fn unschedule(
+        pool: &Pool,
+        post_id: i32,
+        user: User,
+    ) -> Result<Option<StatusChange>, DBError> {
+        let request = StatusChangeRequest {
+            status: PostStatus::Approved,
+            comment: None,
+            publish_at: None,
+        };
+
+        Self::change_status(pool, post_id, user, request, false).await
+    }
+
+    /// Moves a post along the editorial workflow and records the step. `None` if the post
+    /// doesn't exist or the user takes no part in its workflow.
+    pub async fn change_status(
+        pool: &Pool,
+        post_id: i32,
+        user: User,
+        request: StatusChangeRequest,
+        can_publish_directly: bool,
+    ) -> Result<Option<StatusChange>, DBError> {
+        use crate::db::schema::posts::dsl::*;
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                let Some(post) = Self::find_in_workflow(conn, post_id, &user)? else {
+                    return Ok(None);
+                };
+                let Some(permission) = post.status.permission_to(request.status) else {
+                    return Ok(Some(StatusChange::Invalid(post)));
+                };
+
+                let role = PostContributor::role_of(conn, post.id, user.id)?;
+                let can_edit = user.is_mod()
+                    || post.author_id == user.id
+                    || role.is_some_and(|role| ContributorRole::EDITORS.contains(&role));
+                let allowed = match permission {
+                    WorkflowPermission::Edit => can_edit,
+                    WorkflowPermission::Review => {
+                        user.is_mod()
+                            || (post.author_id != user.id
+                                && role
+                                    .is_some_and(|role| ContributorRole::REVIEWERS.contains(&role)))
+                    }
+                    WorkflowPermission::PublishDirectly => can_edit && can_publish_directly,
+                };
+                if !allowed {
+                    return Ok(Some(StatusChange::Forbidden(post)));
+                }
+
+                let changes = StatusUpdate {
+                    status: request.status,
+                    published_at: match request.status {
+                        PostStatus::Scheduled => request.publish_at,
+                        // Publishing early, or again after an archive, keeps a past date
+                        PostStatus::Published => {
+                            Some(post.published_at.filter(|date| *date <= now).unwrap_or(now))
+                        }
+                        // A post taken off the schedule has no publish date anymore
+                        _ if post.status == PostStatus::Scheduled => None,
+                        _ => post.published_at,
+                    },
+                    updated_at: now,
+                };
+
+                let updated: Self = diesel::update(posts.filter(id.eq(post.id)))
+                    .set((&changes, version.eq(version + 1)))
+                    .returning(Self::as_returning())
+                    .get_result(conn)?;
+                PostStatusTransition::record(
+                    conn,
+                    post.id,
+                    Some(post.status),
+                    updated.status,
+                    Some(user.id),
+                    request.comment,
+                )?;
+
+                Ok(Some(StatusChange::Changed(updated)))
+            })
         })
         .await
     }
 
+    /// The post if the user takes part in its workflow: moderators and everyone credited on
+    /// it, reviewers included.
+    pub fn find_in_workflow(
+        conn: &mut PgConnection,
+        post_id: i32,
+        user: &User,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        use crate::db::schema::posts::dsl::*;
+
+        let mut query_builder = posts
+            .filter(id.eq(post_id))
+            .filter(deleted_at.is_null())
+            .select(Self::as_select())
+            .into_boxed();
+
+        if !user.is_mod() {
+            query_builder = query_builder.filter(
+                author_id
+                    .eq(user.id)
+                    .or(id.eq_any(credited_post_ids(user.id))),
+            );
+        }
+
+        query_builder.first(conn).optional()
+    }
+
+    /// Publishes every scheduled post whose `published_at` has been reached.
+    pub async fn publish_scheduled(pool: &Pool) -> Result<usize, DBError> {
+        use crate::db::schema::posts::dsl::*;
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                let published: Vec<i32> = diesel::update(
+                    posts.filter(
+                        status
+                            .eq(PostStatus::Scheduled)
+                            .and(published_at.le(now))
+                            .and(deleted_at.is_null()),
+                    ),
+                )
+                .set((
+                    status.eq(PostStatus::Published),
+                    updated_at.eq(now),
+                    version.eq(version + 1),
+                ))
+                .returning(id)
+                .get_results(conn)?;
+
+                for post_id in published.iter() {
+                    PostStatusTransition::record(
+                        conn,
+                        *post_id,
+                        Some(PostStatus::Scheduled),
+                        PostStatus::Published,
+                        None,
+                        None,
+                    )?;
+                }
+
+                Ok(published.len())
+            })
+        })
+        .await
+    }
+
+    /// Moves a post to the trash. Authors can only trash the posts they own.
     pub async fn delete(pool: &Pool, user: User, post_id: i32) -> Result<usize, DBError> {
         use crate::db::schema::posts::dsl::*;
 
+        let now = Utc::now().naive_utc();
+
         execute_db_operation(pool, move |conn| {
+            let mut query_builder = diesel::update(posts)
+                .filter(id.eq(post_id))
+                .filter(deleted_at.is_null())
+                .into_boxed();
+
             if !user.is_mod() {
-                diesel::delete(posts.filter(id.eq(post_id).and(author_id.eq(user.id))))
-                    .execute(conn)
-            } else {
-                diesel::delete(posts.filter(id.eq(post_id))).execute(conn)
+                query_builder = query_builder.filter(author_id.eq(user.id));
             }
+
+            query_builder
+                .set((deleted_at.eq(now), deleted_by.eq(user.id)))
+                .execute(conn)
         })
         .await
     }
 
-    pub async fn increment_view_count(
+    /// Posts in the trash, most recently deleted first. Authors only see the ones they own.
+    pub async fn find_deleted(
         pool: &Pool,
-        q_post_id: i32,
-        q_user_id: Option<i32>,
-    ) -> Result<(), DBError> {
+        user: User,
+        page: i64,
+    ) -> Result<(Vec<Self>, i64), DBError> {
         use crate::db::schema::posts::dsl::*;
 
         execute_db_operation(pool, move |conn| {
-            conn.transaction(|conn| {
-                PostView::create_query(conn, q_post_id, q_user_id)?;
-                diesel::update(posts.filter(id.eq_all(q_post_id)))
-                    .set(view_count.eq(view_count + 1))
-                    .execute(conn)
-            })
+            let mut count_query = posts.filter(deleted_at.is_not_null()).into_boxed();
+            let mut query_builder = posts
+                .filter(deleted_at.is_not_null())
+                .select(Self::as_select())
+                .into_boxed();
+
+            if !user.is_mod() {
+                count_query = count_query.filter(author_id.eq(user.id));
+                query_builder = query_builder.filter(author_id.eq(user.id));
+            }
+
+            let total = count_query.count().get_result(conn)?;
+            let items = query_builder
+                .order((deleted_at.desc(), id.desc()))
+                .limit(Self::PER_PAGE)
+                .offset((page - 1) * Self::PER_PAGE)
+                .load(conn)?;
+
+            Ok((items, total))
+        })
+        .await
+    }
+
+    /// Takes a post back out of the trash.
+    pub async fn restore(pool: &Pool, user: User, post_id: i32) -> Result<Option<Self>, DBError> {
+        use crate::db::schema::posts::dsl::*;
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            let mut query_builder = diesel::update(posts)
+                .filter(id.eq(post_id))
+                .filter(deleted_at.is_not_null())
+                .into_boxed();
+
+            if !user.is_mod() {
+                query_builder = query_builder.filter(author_id.eq(user.id));
+            }
+
+            query_builder
+                .set((
+                    deleted_at.eq(None::<NaiveDateTime>),
+                    deleted_by.eq(None::<i32>),
+                    updated_at.eq(now),
+                ))
+                .returning(Self::as_returning())
+                .get_result(conn)
+                .optional()
+        })
+        .await
+    }
+
+    /// Permanently deletes the posts trashed before `cutoff`.
+    pub async fn purge_deleted(pool: &Pool, cutoff: NaiveDateTime) -> Result<usize, DBError> {
+        use crate::db::schema::posts::dsl::*;
+
+        execute_db_operation(pool, move |conn| {
+            diesel::delete(posts.filter(deleted_at.lt(cutoff))).execute(conn)
+        })
+        .await
+    }
+
+    /// Latest live posts for a feed, or `None` when the scoped category, tag or author
+    /// does not exist.
+    pub async fn find_feed(
+        pool: &Pool,
+        scope: FeedScope,
+        limit: i64,
+    ) -> Result<Option<PostFeed>, DBError> {
+        use crate::db::schema::{categories, posts, tags, users};
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            let mut query_builder = posts::table
+                .left_join(categories::table)
+                .inner_join(users::table)
+                .filter(is_live(now))
+                .into_boxed();
+            // Unpublished and trashed posts count too, their last change may be leaving the feed
+            let mut changes = posts::table
+                .select(
+                    dsl::sql::<diesel::sql_types::Nullable<diesel::sql_types::Timestamptz>>(
+                        "MAX(GREATEST(posts.updated_at, posts.deleted_at, \
+                         CASE WHEN posts.published_at <= ",
+                    )
+                    .bind::<diesel::sql_types::Timestamptz, _>(now)
+                    .sql(" THEN posts.published_at END))"),
+                )
+                .into_boxed();
+
+            let scope_name = match scope {
+                FeedScope::All => None,
+                FeedScope::Category(category_slug) => {
+                    let Some((category_id, name)) = categories::table
+                        .filter(categories::slug.eq(category_slug))
+                        .filter(categories::deleted_at.is_null())
+                        .select((categories::id, categories::name))
+                        .first::<(i32, String)>(conn)
+                        .optional()?
+                    else {
+                        return Ok(None);
+                    };
+                    query_builder = query_builder.filter(posts::category_id.eq(category_id));
+                    changes = changes.filter(posts::category_id.eq(category_id));
+                    Some(name)
+                }
+                FeedScope::Tag(tag_slug) => {
+                    let Some((tag_id, name)) = tags::table
+                        .filter(tags::slug.eq(tag_slug))
+                        .filter(tags::deleted_at.is_null())
+                        .select((tags::id, tags::name))
+                        .first::<(i32, String)>(conn)
+                        .optional()?
+                    else {
+                        return Ok(None);
+                    };
+                    query_builder = query_builder.filter(posts::tag_ids.contains(vec![tag_id]));
+                    changes = changes.filter(posts::tag_ids.contains(vec![tag_id]));
+                    Some(name)
+                }
+                FeedScope::Author(author_id) => {
+                    let Some(name) = users::table
+                        .filter(users::id.eq(author_id))
+                        .select(users::name)
+                        .first::<String>(conn)
+                        .optional()?
+                    else {
+                        return Ok(None);
+                    };
+                    query_builder = query_builder.filter(posts::author_id.eq(author_id));
+                    changes = changes.filter(posts::author_id.eq(author_id));
+                    Some(name)
+                }
+            };
+
+            let results: Vec<(Post, Option<Category>, User)> = query_builder
+                .order((
+                    posts::published_at.desc().nulls_last(),
+                    posts::created_at.desc(),
+                ))
+                .limit(limit)
+                .select((
+                    Post::as_select(),
+                    Option::<Category>::as_select(),
+                    User::as_select(),
+                ))
+                .load(conn)?;
+
+            let last_modified: Option<NaiveDateTime> = changes.first(conn)?;
+
+            Ok(Some(PostFeed {
+                scope_name,
+                posts: Self::with_relations(conn, results, None)?,
+                last_modified: last_modified.unwrap_or_default(),
+            }))
         })
         .await
-        .map(|_| ())
     }
 
     pub async fn sitemap(pool: &Pool) -> Result<Vec<PostSitemap>, DBError> {
         use crate::db::schema::posts::dsl::*;
 
+        let now = Utc::now().naive_utc();
+
         let res = execute_db_operation(pool, move |conn| {
             // only select slug, updated_at, and published_at
             crate::db::schema::posts::table
-                .filter(is_published.eq(true))
+                .filter(is_live(now))
                 .select((slug, updated_at, published_at))
                 .load::<(String, NaiveDateTime, Option<NaiveDateTime>)>(conn)
                 .map(|res| {
@@ -511,6 +1631,9 @@ impl Post {
     pub async fn find_all(pool: &Pool) -> Result<Vec<Self>, DBError> {
         use crate::db::schema::posts::dsl::*;
 
-        execute_db_operation(pool, move |conn| posts.load::<Self>(conn)).await
+        execute_db_operation(pool, move |conn| {
+            posts.filter(deleted_at.is_null()).load::<Self>(conn)
+        })
+        .await
     }
 }
@@ -0,0 +1,323 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use deadpool_diesel::postgres::Pool;
+use diesel::{
+    pg::Pg,
+    prelude::*,
+    query_builder::{BoxedSqlQuery, SqlQuery},
+    sql_types::{BigInt, Bool, Date, Int4, Nullable, Text, Timestamptz},
+};
+use serde::Serialize;
+
+use super::{post_contributor::credited_post_ids, user::User};
+use crate::db::{errors::DBError, utils::execute_db_operation};
+
+/// Rolls the raw views recorded before `$1` into the daily tables and deletes them, in one
+/// statement so views stored meanwhile are neither lost nor counted twice. Days are cut in
+/// UTC whatever the session time zone.
+///
+/// A day's unique visitors are final once it is rolled up: the visitor hashes are gone, so
+/// views flushed late can't be told apart from the ones already counted and only raise the
+/// count when they alone saw more visitors.
+const AGGREGATE_SQL: &str = r#"
+WITH rolled AS (
+    DELETE FROM post_views
+    WHERE created_at < $1
+    RETURNING id, post_id, (created_at AT TIME ZONE 'UTC')::date AS day, visitor_hash,
+              referrer_domain, utm_source, utm_medium, utm_campaign, device
+),
+daily AS (
+    INSERT INTO post_view_daily AS daily
+        (post_id, day, views, unique_visitors, desktop_views, mobile_views, tablet_views)
+    SELECT post_id, day, COUNT(*),
+           COUNT(DISTINCT COALESCE(visitor_hash, 'view:' || id)),
+           COUNT(*) FILTER (WHERE device = 'desktop'),
+           COUNT(*) FILTER (WHERE device = 'mobile'),
+           COUNT(*) FILTER (WHERE device = 'tablet')
+    FROM rolled
+    GROUP BY post_id, day
+    ON CONFLICT (post_id, day) DO UPDATE SET
+        views = daily.views + EXCLUDED.views,
+        unique_visitors = GREATEST(daily.unique_visitors, EXCLUDED.unique_visitors),
+        desktop_views = daily.desktop_views + EXCLUDED.desktop_views,
+        mobile_views = daily.mobile_views + EXCLUDED.mobile_views,
+        tablet_views = daily.tablet_views + EXCLUDED.tablet_views
+),
+sources AS (
+    INSERT INTO post_referrer_daily AS sources
+        (post_id, day, referrer_domain, utm_source, utm_medium, utm_campaign, views)
+    SELECT post_id, day, COALESCE(referrer_domain, ''), COALESCE(utm_source, ''),
+           COALESCE(utm_medium, ''), COALESCE(utm_campaign, ''), COUNT(*)
+    FROM rolled
+    GROUP BY 1, 2, 3, 4, 5, 6
+    ON CONFLICT (post_id, day, referrer_domain, utm_source, utm_medium, utm_campaign)
+    DO UPDATE SET views = sources.views + EXCLUDED.views
+)
+SELECT COUNT(*) AS count FROM rolled
+"#;
+
+/// The posts a report covers, and their views per day from the daily tables plus the raw views
+/// not rolled up yet.
+///
+/// Binds: all posts, viewer id, post id filter, first day, last day, size of the top lists.
+const DAILY_SQL: &str = r#"
+WITH scoped AS (
+    SELECT posts.id
+    FROM posts
+    WHERE ($1 OR posts.author_id = $2
+           OR EXISTS (SELECT 1 FROM post_contributors
+                      WHERE post_contributors.post_id = posts.id AND post_contributors.user_id = $2))
+      AND ($3::int4 IS NULL OR posts.id = $3)
+),
+daily AS (
+    SELECT post_id, day, views, unique_visitors, desktop_views, mobile_views, tablet_views
+    FROM post_view_daily
+    WHERE post_id IN (SELECT id FROM scoped) AND day BETWEEN $4 AND $5
+    UNION ALL
+    SELECT post_id, (created_at AT TIME ZONE 'UTC')::date,
+           COUNT(*)::int4,
+           COUNT(DISTINCT COALESCE(visitor_hash, 'view:' || id))::int4,
+           (COUNT(*) FILTER (WHERE device = 'desktop'))::int4,
+           (COUNT(*) FILTER (WHERE device = 'mobile'))::int4,
+           (COUNT(*) FILTER (WHERE device = 'tablet'))::int4
+    FROM post_views
+    WHERE post_id IN (SELECT id FROM scoped)
+      AND created_at >= $4::date AT TIME ZONE 'UTC'
+      AND created_at < ($5::date + 1) AT TIME ZONE 'UTC'
+    GROUP BY 1, 2
+),
+sources AS (
+    SELECT referrer_domain, utm_source, utm_medium, utm_campaign, views
+    FROM post_referrer_daily
+    WHERE post_id IN (SELECT id FROM scoped) AND day BETWEEN $4 AND $5
+    UNION ALL
+    SELECT COALESCE(referrer_domain, ''), COALESCE(utm_source, ''),
+           COALESCE(utm_medium, ''), COALESCE(utm_campaign, ''), COUNT(*)::int4
+    FROM post_views
+    WHERE post_id IN (SELECT id FROM scoped)
+      AND created_at >= $4::date AT TIME ZONE 'UTC'
+      AND created_at < ($5::date + 1) AT TIME ZONE 'UTC'
+    GROUP BY 1, 2, 3, 4
+)
+"#;
+
+const TOTALS_SQL: &str = r#"
+SELECT COALESCE(SUM(views), 0)::int8 AS views,
+       COALESCE(SUM(unique_visitors), 0)::int8 AS unique_visitors,
+       COALESCE(SUM(desktop_views), 0)::int8 AS desktop_views,
+       COALESCE(SUM(mobile_views), 0)::int8 AS mobile_views,
+       COALESCE(SUM(tablet_views), 0)::int8 AS tablet_views
+FROM daily
+"#;
+
+const SERIES_SQL: &str = r#"
+SELECT days.day::date AS day,
+       COALESCE(SUM(daily.views), 0)::int8 AS views,
+       COALESCE(SUM(daily.unique_visitors), 0)::int8 AS unique_visitors
+FROM generate_series($4::date, $5::date, interval '1 day') AS days(day)
+LEFT JOIN daily ON daily.day = days.day::date
+GROUP BY days.day
+ORDER BY days.day
+"#;
+
+const REFERRERS_SQL: &str = r#"
+SELECT NULLIF(referrer_domain, '') AS referrer_domain, SUM(views)::int8 AS views
+FROM sources
+GROUP BY referrer_domain
+ORDER BY views DESC, referrer_domain
+LIMIT $6
+"#;
+
+const CAMPAIGNS_SQL: &str = r#"
+SELECT NULLIF(utm_source, '') AS utm_source,
+       NULLIF(utm_medium, '') AS utm_medium,
+       NULLIF(utm_campaign, '') AS utm_campaign,
+       SUM(views)::int8 AS views
+FROM sources
+WHERE utm_source <> '' OR utm_medium <> '' OR utm_campaign <> ''
+GROUP BY sources.utm_source, sources.utm_medium, sources.utm_campaign
+ORDER BY views DESC, utm_source, utm_medium, utm_campaign
+LIMIT $6
+"#;
+
+const POSTS_SQL: &str = r#"
+SELECT posts.id AS post_id, posts.title, posts.slug,
+       SUM(daily.views)::int8 AS views,
+       SUM(daily.unique_visitors)::int8 AS unique_visitors
+FROM daily
+JOIN posts ON posts.id = daily.post_id
+GROUP BY posts.id
+ORDER BY views DESC, posts.id
+LIMIT $6
+"#;
+
+#[derive(QueryableByName)]
+struct RolledCount {
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+#[derive(QueryableByName, Debug, Serialize)]
+pub struct AnalyticsTotals {
+    #[diesel(sql_type = BigInt)]
+    pub views: i64,
+    /// Visitors are told apart within a day only, so this sums each day's unique visitors.
+    #[diesel(sql_type = BigInt)]
+    pub unique_visitors: i64,
+    #[diesel(sql_type = BigInt)]
+    pub desktop_views: i64,
+    #[diesel(sql_type = BigInt)]
+    pub mobile_views: i64,
+    #[diesel(sql_type = BigInt)]
+    pub tablet_views: i64,
+}
+
+#[derive(QueryableByName, Debug, Serialize)]
+pub struct AnalyticsDay {
+    #[diesel(sql_type = Date)]
+    pub day: NaiveDate,
+    #[diesel(sql_type = BigInt)]
+    pub views: i64,
+    #[diesel(sql_type = BigInt)]
+    pub unique_visitors: i64,
+}
+
+#[derive(QueryableByName, Debug, Serialize)]
+pub struct ReferrerViews {
+    /// `None` for direct visits.
+    #[diesel(sql_type = Nullable<Text>)]
+    pub referrer_domain: Option<String>,
+    #[diesel(sql_type = BigInt)]
+    pub views: i64,
+}
+
+#[derive(QueryableByName, Debug, Serialize)]
+pub struct CampaignViews {
+    #[diesel(sql_type = Nullable<Text>)]
+    pub utm_source: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub utm_medium: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub utm_campaign: Option<String>,
+    #[diesel(sql_type = BigInt)]
+    pub views: i64,
+}
+
+#[derive(QueryableByName, Debug, Serialize)]
+pub struct PostViewsSummary {
+    #[diesel(sql_type = Int4)]
+    pub post_id: i32,
+    #[diesel(sql_type = Text)]
+    pub title: String,
+    #[diesel(sql_type = Text)]
+    pub slug: String,
+    #[diesel(sql_type = BigInt)]
+    pub views: i64,
+    #[diesel(sql_type = BigInt)]
+    pub unique_visitors: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnalyticsQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub post_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalyticsReport {
+    /// Whether the report covers every post rather than the viewer's own.
+    pub site_wide: bool,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub post_id: Option<i32>,
+    pub totals: AnalyticsTotals,
+    /// One entry per day of the range, including days without views.
+    pub series: Vec<AnalyticsDay>,
+    pub top_referrers: Vec<ReferrerViews>,
+    pub top_campaigns: Vec<CampaignViews>,
+    pub top_posts: Vec<PostViewsSummary>,
+}
+
+pub struct PostAnalytics;
+
+impl PostAnalytics {
+    /// Entries in each top list of a report.
+    const TOP_LIMIT: i64 = 10;
+
+    /// Rolls raw views recorded before `before` into the daily aggregates and prunes them.
+    /// Returns how many raw views were rolled up.
+    pub async fn aggregate(pool: &Pool, before: NaiveDateTime) -> Result<i64, DBError> {
+        execute_db_operation(pool, move |conn| {
+            diesel::sql_query(AGGREGATE_SQL)
+                .bind::<Timestamptz, _>(before)
+                .get_result::<RolledCount>(conn)
+                .map(|rolled| rolled.count)
+        })
+        .await
+    }
+
+    /// Views of the viewer's posts, those they author or are credited on, or of every post for
+    /// admins. `None` if the requested post isn't one the viewer may see the views of.
+    pub async fn report(
+        pool: &Pool,
+        viewer: User,
+        query: AnalyticsQuery,
+    ) -> Result<Option<AnalyticsReport>, DBError> {
+        use crate::db::schema::posts;
+
+        let site_wide = viewer.is_admin();
+
+        execute_db_operation(pool, move |conn| {
+            conn.build_transaction()
+                .read_only()
+                .repeatable_read()
+                .run(|conn| {
+                    if let Some(post_id) = query.post_id {
+                        let mut post_query = posts::table
+                            .filter(posts::id.eq(post_id))
+                            .select(posts::id)
+                            .into_boxed();
+                        if !site_wide {
+                            post_query = post_query.filter(
+                                posts::author_id
+                                    .eq(viewer.id)
+                                    .or(posts::id.eq_any(credited_post_ids(viewer.id))),
+                            );
+                        }
+                        if post_query.first::<i32>(conn).optional()?.is_none() {
+                            return Ok(None);
+                        }
+                    }
+
+                    let scope = (site_wide, viewer.id, &query);
+                    Ok(Some(AnalyticsReport {
+                        site_wide,
+                        from: query.from,
+                        to: query.to,
+                        post_id: query.post_id,
+                        totals: report_query(TOTALS_SQL, scope).get_result(conn)?,
+                        series: report_query(SERIES_SQL, scope).load(conn)?,
+                        top_referrers: report_query(REFERRERS_SQL, scope).load(conn)?,
+                        top_campaigns: report_query(CAMPAIGNS_SQL, scope).load(conn)?,
+                        top_posts: report_query(POSTS_SQL, scope).load(conn)?,
+                    }))
+                })
+        })
+        .await
+    }
+}
+
+/// One of the report queries, after the shared CTEs and with the report's binds.
+fn report_query(
+    sql: &str,
+    (site_wide, viewer_id, query): (bool, i32, &AnalyticsQuery),
+) -> BoxedSqlQuery<'static, Pg, SqlQuery> {
+    diesel::sql_query(format!("{}{}", DAILY_SQL, sql))
+        .into_boxed()
+        .bind::<Bool, _>(site_wide)
+        .bind::<Int4, _>(viewer_id)
+        .bind::<Nullable<Int4>, _>(query.post_id)
+        .bind::<Date, _>(query.from)
+        .bind::<Date, _>(query.to)
+        .bind::<BigInt, _>(PostAnalytics::TOP_LIMIT)
+}
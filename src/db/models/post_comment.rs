@@ -11,6 +11,12 @@ use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
 use tokio::task;
 
+use super::{
+    pagination::{KeysetPage, PageRequest},
+    post::Post,
+    post_like::PostCommentLike,
+    user::User,
+};
 use crate::db::{
     errors::DBError,
     schema,
@@ -27,6 +33,10 @@ pub struct PostComment {
     pub likes_count: i32,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_by: Option<i32>,
 }
 
 #[derive(Insertable, Deserialize, Debug)]
@@ -44,6 +54,15 @@ pub struct UpdatePostComment {
     pub updated_at: NaiveDateTime,
 }
 
+#[derive(Debug, Serialize)]
+pub struct PostCommentView {
+    #[serde(flatten)]
+    pub comment: PostComment,
+    /// Only set when the comments are loaded for a signed-in user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub liked_by_me: Option<bool>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum PostCommentSortBy {
@@ -65,13 +84,24 @@ pub struct PostCommentQuery {
 const MAX_PER_PAGE: i64 = 24;
 
 impl PostComment {
-    pub async fn create(pool: &Pool, new_comment: NewPostComment) -> Result<Self, DBError> {
+    /// Listing name in the cursors of a post's comments, so they only page that post.
+    pub fn listing(post_id: i32) -> String {
+        format!("post_comments:{}", post_id)
+    }
+
+    /// Comments on a live post; `None` if the post isn't live or is in the trash.
+    pub async fn create(pool: &Pool, new_comment: NewPostComment) -> Result<Option<Self>, DBError> {
         use crate::db::schema::post_comments::dsl::*;
 
         execute_db_operation(pool, move |conn| {
+            if !Post::exists_live(conn, new_comment.post_id)? {
+                return Ok(None);
+            }
+
             diesel::insert_into(post_comments)
                 .values(&new_comment)
                 .get_result(conn)
+                .map(Some)
         })
         .await
     }
@@ -85,15 +115,22 @@ impl PostComment {
         use crate::db::schema::post_comments::dsl::*;
 
         execute_db_operation(pool, move |conn| {
-            diesel::update(post_comments.filter(id.eq(comment_id).and(user_id.eq(filter_user_id))))
-                .set(&update_comment)
-                .returning(Self::as_returning())
-                .get_result(conn)
-                .optional()
+            diesel::update(
+                post_comments.filter(
+                    id.eq(comment_id)
+                        .and(user_id.eq(filter_user_id))
+                        .and(deleted_at.is_null()),
+                ),
+            )
+            .set(&update_comment)
+            .returning(Self::as_returning())
+            .get_result(conn)
+            .optional()
         })
         .await
     }
 
+    /// Moves a comment to the trash.
     pub async fn delete(
         pool: &Pool,
         comment_id: i32,
@@ -101,9 +138,87 @@ impl PostComment {
     ) -> Result<usize, DBError> {
         use crate::db::schema::post_comments::dsl::*;
 
+        let now = Utc::now().naive_utc();
+
         execute_db_operation(pool, move |conn| {
-            diesel::delete(post_comments.filter(id.eq(comment_id).and(user_id.eq(filter_user_id))))
-                .execute(conn)
+            diesel::update(
+                post_comments.filter(
+                    id.eq(comment_id)
+                        .and(user_id.eq(filter_user_id))
+                        .and(deleted_at.is_null()),
+                ),
+            )
+            .set((deleted_at.eq(now), deleted_by.eq(filter_user_id)))
+            .execute(conn)
+        })
+        .await
+    }
+
+    /// Comments in the trash, most recently deleted first. Moderators see everyone's, other
+    /// users their own.
+    pub async fn find_deleted(
+        pool: &Pool,
+        user: User,
+        page: i64,
+    ) -> Result<(Vec<Self>, i64), DBError> {
+        use crate::db::schema::post_comments::dsl::*;
+
+        execute_db_operation(pool, move |conn| {
+            let mut count_query = post_comments.filter(deleted_at.is_not_null()).into_boxed();
+            let mut query_builder = post_comments.filter(deleted_at.is_not_null()).into_boxed();
+
+            if !user.is_mod() {
+                count_query = count_query.filter(user_id.eq(user.id));
+                query_builder = query_builder.filter(user_id.eq(user.id));
+            }
+
+            let total = count_query.count().get_result(conn)?;
+            let items = query_builder
+                .order((deleted_at.desc(), id.desc()))
+                .limit(MAX_PER_PAGE)
+                .offset((page - 1) * MAX_PER_PAGE)
+                .load::<PostComment>(conn)?;
+
+            Ok((items, total))
+        })
+        .await
+    }
+
+    pub async fn restore(
+        pool: &Pool,
+        user: User,
+        comment_id: i32,
+    ) -> Result<Option<Self>, DBError> {
+        use crate::db::schema::post_comments::dsl::*;
+
+        execute_db_operation(pool, move |conn| {
+            let mut query_builder = diesel::update(post_comments)
+                .filter(id.eq(comment_id))
+                .filter(deleted_at.is_not_null())
+                .into_boxed();
+
+            if !user.is_mod() {
+                query_builder = query_builder.filter(user_id.eq(user.id));
+            }
+
+            query_builder
+                .set((
+                    deleted_at.eq(None::<NaiveDateTime>),
+                    deleted_by.eq(None::<i32>),
+                ))
+                .returning(Self::as_returning())
+                .get_result(conn)
+                .optional()
+        })
+        .await
+    }
+
+    /// Permanently deletes the comments trashed before `cutoff`.
+    pub async fn purge_deleted(pool: &Pool, cutoff: NaiveDateTime) -> Result<usize, DBError> {
+        use crate::db::schema::post_comments::dsl::*;
+
+        execute_db_operation(pool, move |conn| {
+            diesel::delete(post_comments.filter(deleted_at.lt(cutoff))).execute(conn)
         })
         .await
     }
@@ -111,15 +226,24 @@ impl PostComment {
     pub async fn list_all(pool: &Pool) -> Result<Vec<Self>, DBError> {
         use crate::db::schema::post_comments::dsl::*;
 
-        execute_db_operation(pool, move |conn| post_comments.load::<PostComment>(conn)).await
+        execute_db_operation(pool, move |conn| {
+            post_comments
+                .filter(deleted_at.is_null())
+                .load::<PostComment>(conn)
+        })
+        .await
     }
 
     pub async fn list_paginated(pool: &Pool, page: i64) -> Result<(Vec<Self>, i64), DBError> {
         use crate::db::schema::post_comments::dsl::*;
 
         execute_db_operation(pool, move |conn| {
-            let total = post_comments.count().get_result(conn)?;
+            let total = post_comments
+                .filter(deleted_at.is_null())
+                .count()
+                .get_result(conn)?;
             let items = post_comments
+                .filter(deleted_at.is_null())
                 .order(created_at.desc())
                 .limit(MAX_PER_PAGE)
                 .offset((page - 1) * MAX_PER_PAGE)
@@ -136,7 +260,7 @@ impl PostComment {
         use crate::db::schema::post_comments::dsl::*;
 
         execute_db_operation(pool, move |conn| {
-            let mut query_builder = post_comments.into_boxed();
+            let mut query_builder = post_comments.filter(deleted_at.is_null()).into_boxed();
 
             if let Some(user_id_filter) = query.user_id {
                 query_builder = query_builder.filter(user_id.eq(user_id_filter));
@@ -173,25 +297,70 @@ impl PostComment {
         .await
     }
 
+    /// The post's comments, newest first, by page number or by cursor over
+    /// `(created_at, id)`. `None` if the post isn't live or is in the trash.
     pub async fn list_by_post(
         pool: &Pool,
         query_post_id: i32,
-        page: i64,
-    ) -> Result<(Vec<Self>, i64), DBError> {
+        page: PageRequest<NaiveDateTime>,
+        viewer_id: Option<i32>,
+    ) -> Result<Option<KeysetPage<PostCommentView>>, DBError> {
         use crate::db::schema::post_comments::dsl::*;
 
         execute_db_operation(pool, move |conn| {
-            let total = post_comments
-                .filter(post_id.eq(query_post_id))
-                .count()
-                .get_result(conn)?;
-            let items = post_comments
+            if !Post::exists_live(conn, query_post_id)? {
+                return Ok(None);
+            }
+
+            let total = match page {
+                PageRequest::Number(_) => Some(
+                    post_comments
+                        .filter(post_id.eq(query_post_id))
+                        .filter(deleted_at.is_null())
+                        .count()
+                        .get_result(conn)?,
+                ),
+                PageRequest::Cursor(_) => None,
+            };
+
+            let mut query_builder = post_comments
                 .filter(post_id.eq(query_post_id))
-                .order(created_at.desc())
-                .limit(MAX_PER_PAGE)
-                .offset((page - 1) * MAX_PER_PAGE)
-                .load::<PostComment>(conn)?;
-            Ok((items, total))
+                .filter(deleted_at.is_null())
+                .limit(MAX_PER_PAGE + 1)
+                .into_boxed();
+
+            query_builder = match &page {
+                PageRequest::Number(page_no) => query_builder
+                    .order((created_at.desc(), id.desc()))
+                    .offset((page_no - 1) * MAX_PER_PAGE),
+                PageRequest::Cursor(None) => query_builder.order((created_at.desc(), id.desc())),
+                PageRequest::Cursor(Some(cursor)) if cursor.backward => query_builder
+                    .filter(
+                        created_at
+                            .gt(cursor.key)
+                            .or(created_at.eq(cursor.key).and(id.gt(cursor.id))),
+                    )
+                    .order((created_at.asc(), id.asc())),
+                PageRequest::Cursor(Some(cursor)) => query_builder
+                    .filter(
+                        created_at
+                            .lt(cursor.key)
+                            .or(created_at.eq(cursor.key).and(id.lt(cursor.id))),
+                    )
+                    .order((created_at.desc(), id.desc())),
+            };
+
+            let items = query_builder.load::<PostComment>(conn)?;
+            let mut page = KeysetPage::new(
+                Self::with_likes(conn, items, viewer_id)?,
+                MAX_PER_PAGE,
+                &page,
+                &Self::listing(query_post_id),
+                |view| (view.comment.created_at, view.comment.id),
+            );
+            page.total = total;
+
+            Ok(Some(page))
         })
         .await
     }
@@ -200,22 +369,47 @@ impl PostComment {
         pool: &Pool,
         query_user_id: i32,
         page: i64,
-    ) -> Result<(Vec<Self>, i64), DBError> {
+        viewer_id: Option<i32>,
+    ) -> Result<(Vec<PostCommentView>, i64), DBError> {
         use crate::db::schema::post_comments::dsl::*;
 
         execute_db_operation(pool, move |conn| {
             let total = post_comments
                 .filter(user_id.eq(query_user_id))
+                .filter(deleted_at.is_null())
                 .count()
                 .get_result(conn)?;
             let items = post_comments
                 .filter(user_id.eq(query_user_id))
+                .filter(deleted_at.is_null())
                 .order(created_at.desc())
                 .limit(MAX_PER_PAGE)
                 .offset((page - 1) * MAX_PER_PAGE)
                 .load::<PostComment>(conn)?;
-            Ok((items, total))
+            Ok((Self::with_likes(conn, items, viewer_id)?, total))
         })
         .await
     }
+
+    fn with_likes(
+        conn: &mut PgConnection,
+        comments: Vec<Self>,
+        viewer_id: Option<i32>,
+    ) -> Result<Vec<PostCommentView>, diesel::result::Error> {
+        let liked = match viewer_id {
+            Some(viewer_id) => {
+                let comment_ids: Vec<i32> = comments.iter().map(|comment| comment.id).collect();
+                Some(PostCommentLike::liked_ids(conn, viewer_id, &comment_ids)?)
+            }
+            None => None,
+        };
+
+        Ok(comments
+            .into_iter()
+            .map(|comment| PostCommentView {
+                liked_by_me: liked.as_ref().map(|liked| liked.contains(&comment.id)),
+                comment,
+            })
+            .collect())
+    }
 }
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use deadpool_diesel::postgres::Pool;
+use diesel::{dsl, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{post::PostCredit, user::User};
+use crate::db::{errors::DBError, schema, utils::execute_db_operation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, diesel_derive_enum::DbEnum)]
+#[ExistingTypePath = "crate::db::schema::sql_types::ContributorRole"]
+#[serde(rename_all = "kebab-case")]
+pub enum ContributorRole {
+    Author,
+    #[db_rename = "co-author"]
+    CoAuthor,
+    Editor,
+    Reviewer,
+}
+
+impl ContributorRole {
+    /// Roles allowed to edit the post they are credited on; reviewers are only credited.
+    pub const EDITORS: [ContributorRole; 3] = [
+        ContributorRole::Author,
+        ContributorRole::CoAuthor,
+        ContributorRole::Editor,
+    ];
+
+    /// Roles allowed to approve or send back someone else's post for review.
+    pub const REVIEWERS: [ContributorRole; 2] =
+        [ContributorRole::Editor, ContributorRole::Reviewer];
+}
+
+/// Ids of the posts a user is credited on, in any role.
+#[dsl::auto_type]
+pub fn credited_post_ids(user_id: i32) -> _ {
+    schema::post_contributors::table
+        .filter(schema::post_contributors::user_id.eq(user_id))
+        .select(schema::post_contributors::post_id)
+}
+
+/// Ids of the posts a user may edit as a contributor.
+#[dsl::auto_type]
+pub fn editable_post_ids(user_id: i32) -> _ {
+    let roles: [ContributorRole; 3] = ContributorRole::EDITORS;
+    schema::post_contributors::table
+        .filter(schema::post_contributors::user_id.eq(user_id))
+        .filter(schema::post_contributors::role.eq_any(roles))
+        .select(schema::post_contributors::post_id)
+}
+
+#[derive(Queryable, Selectable, Debug, Clone, Serialize, PartialEq)]
+#[diesel(table_name = schema::post_contributors)]
+pub struct PostContributor {
+    pub post_id: i32,
+    pub user_id: i32,
+    pub role: ContributorRole,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Deserialize, Debug)]
+#[diesel(table_name = schema::post_contributors)]
+pub struct NewPostContributor {
+    pub post_id: i32,
+    pub user_id: i32,
+    pub role: ContributorRole,
+}
+
+impl PostContributor {
+    /// Credits the owning author of a freshly created post.
+    pub fn insert_author(
+        conn: &mut PgConnection,
+        post_id: i32,
+        author_id: i32,
+    ) -> Result<usize, diesel::result::Error> {
+        use crate::db::schema::post_contributors;
+
+        diesel::insert_into(post_contributors::table)
+            .values(NewPostContributor {
+                post_id,
+                user_id: author_id,
+                role: ContributorRole::Author,
+            })
+            .on_conflict_do_nothing()
+            .execute(conn)
+    }
+
+    /// Adds a contributor or changes their role. Only the owning author and moderators manage
+    /// contributors, and the owning author's own credit never changes.
+    pub async fn upsert(
+        pool: &Pool,
+        post_id: i32,
+        user: User,
+        contributor: NewPostContributor,
+    ) -> Result<Option<Vec<PostCredit>>, DBError> {
+        use crate::db::schema::{post_contributors, users};
+
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                let Some(owner_id) = Self::managed_post_owner(conn, post_id, &user)? else {
+                    return Ok(None);
+                };
+
+                let user_exists = users::table
+                    .filter(users::id.eq(contributor.user_id))
+                    .select(users::id)
+                    .first::<i32>(conn)
+                    .optional()?
+                    .is_some();
+                if !user_exists {
+                    return Ok(None);
+                }
+
+                if contributor.user_id != owner_id {
+                    diesel::insert_into(post_contributors::table)
+                        .values(&contributor)
+                        .on_conflict((post_contributors::post_id, post_contributors::user_id))
+                        .do_update()
+                        .set(post_contributors::role.eq(contributor.role))
+                        .execute(conn)?;
+                }
+
+                let mut credits = Self::credits(conn, &[post_id])?;
+                Ok(Some(credits.remove(&post_id).unwrap_or_default()))
+            })
+        })
+        .await
+    }
+
+    pub async fn remove(
+        pool: &Pool,
+        post_id: i32,
+        user: User,
+        contributor_id: i32,
+    ) -> Result<Option<Vec<PostCredit>>, DBError> {
+        use crate::db::schema::post_contributors;
+
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                let Some(owner_id) = Self::managed_post_owner(conn, post_id, &user)? else {
+                    return Ok(None);
+                };
+
+                diesel::delete(
+                    post_contributors::table.filter(
+                        post_contributors::post_id
+                            .eq(post_id)
+                            .and(post_contributors::user_id.eq(contributor_id))
+                            .and(post_contributors::user_id.ne(owner_id)),
+                    ),
+                )
+                .execute(conn)?;
+
+                let mut credits = Self::credits(conn, &[post_id])?;
+                Ok(Some(credits.remove(&post_id).unwrap_or_default()))
+            })
+        })
+        .await
+    }
+
+    /// The role a user is credited with on a post, if any.
+    pub fn role_of(
+        conn: &mut PgConnection,
+        post_id: i32,
+        user_id: i32,
+    ) -> Result<Option<ContributorRole>, diesel::result::Error> {
+        use crate::db::schema::post_contributors;
+
+        post_contributors::table
+            .filter(post_contributors::post_id.eq(post_id))
+            .filter(post_contributors::user_id.eq(user_id))
+            .select(post_contributors::role)
+            .first(conn)
+            .optional()
+    }
+
+    /// Contributors of each post with their user details, owning author first.
+    pub fn credits(
+        conn: &mut PgConnection,
+        post_ids: &[i32],
+    ) -> Result<HashMap<i32, Vec<PostCredit>>, diesel::result::Error> {
+        use crate::db::schema::{post_contributors, users};
+
+        if post_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let rows: Vec<(i32, i32, String, Option<String>, ContributorRole)> =
+            post_contributors::table
+                .inner_join(users::table)
+                .filter(post_contributors::post_id.eq_any(post_ids))
+                .order((
+                    post_contributors::post_id.asc(),
+                    post_contributors::role.asc(),
+                    post_contributors::created_at.asc(),
+                ))
+                .select((
+                    post_contributors::post_id,
+                    users::id,
+                    users::name,
+                    users::avatar,
+                    post_contributors::role,
+                ))
+                .load(conn)?;
+
+        let mut credits: HashMap<i32, Vec<PostCredit>> = HashMap::new();
+        for (post_id, id, name, avatar, role) in rows {
+            credits.entry(post_id).or_default().push(PostCredit {
+                id,
+                name,
+                avatar,
+                role,
+            });
+        }
+
+        Ok(credits)
+    }
+
+    /// Owning author of the post, if `user` may manage its contributors.
+    fn managed_post_owner(
+        conn: &mut PgConnection,
+        post_id: i32,
+        user: &User,
+    ) -> Result<Option<i32>, diesel::result::Error> {
+        use crate::db::schema::posts;
+
+        let mut query_builder = posts::table
+            .filter(posts::id.eq(post_id))
+            .filter(posts::deleted_at.is_null())
+            .select(posts::author_id)
+            .into_boxed();
+
+        if !user.is_mod() {
+            query_builder = query_builder.filter(posts::author_id.eq(user.id));
+        }
+
+        query_builder.first(conn).optional()
+    }
+}
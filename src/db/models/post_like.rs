@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDateTime;
+use deadpool_diesel::postgres::Pool;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use super::post::Post;
+use crate::db::{errors::DBError, schema, utils::execute_db_operation};
+
+#[derive(Queryable, Selectable, Debug, Serialize, PartialEq)]
+#[diesel(table_name = schema::post_likes)]
+pub struct PostLike {
+    pub post_id: i32,
+    pub user_id: i32,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Debug, Serialize, PartialEq)]
+#[diesel(table_name = schema::post_comment_likes)]
+pub struct PostCommentLike {
+    pub comment_id: i32,
+    pub user_id: i32,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LikeState {
+    pub liked: bool,
+    pub likes_count: i32,
+}
+
+impl PostLike {
+    /// Likes or unlikes a live post. Repeating the same call changes nothing, and
+    /// `posts.likes_count` only moves when a like row is actually added or removed.
+    pub async fn set(
+        pool: &Pool,
+        post_id: i32,
+        user_id: i32,
+        liked: bool,
+    ) -> Result<Option<LikeState>, DBError> {
+        use crate::db::schema::{post_likes, posts};
+
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                if !Post::exists_live(conn, post_id)? {
+                    return Ok(None);
+                }
+
+                let changed = if liked {
+                    diesel::insert_into(post_likes::table)
+                        .values((
+                            post_likes::post_id.eq(post_id),
+                            post_likes::user_id.eq(user_id),
+                        ))
+                        .on_conflict_do_nothing()
+                        .execute(conn)?
+                } else {
+                    diesel::delete(
+                        post_likes::table.filter(
+                            post_likes::post_id
+                                .eq(post_id)
+                                .and(post_likes::user_id.eq(user_id)),
+                        ),
+                    )
+                    .execute(conn)?
+                };
+
+                let delta = if liked { 1 } else { -1 } * changed as i32;
+                let likes_count = diesel::update(posts::table.find(post_id))
+                    .set(posts::likes_count.eq(posts::likes_count + delta))
+                    .returning(posts::likes_count)
+                    .get_result(conn)?;
+
+                Ok(Some(LikeState { liked, likes_count }))
+            })
+        })
+        .await
+    }
+
+    /// Which of `post_ids` the user has liked.
+    pub fn liked_ids(
+        conn: &mut PgConnection,
+        user_id: i32,
+        post_ids: &[i32],
+    ) -> Result<HashSet<i32>, diesel::result::Error> {
+        use crate::db::schema::post_likes;
+
+        if post_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        Ok(post_likes::table
+            .filter(post_likes::user_id.eq(user_id))
+            .filter(post_likes::post_id.eq_any(post_ids))
+            .select(post_likes::post_id)
+            .load::<i32>(conn)?
+            .into_iter()
+            .collect())
+    }
+}
+
+impl PostCommentLike {
+    /// Same as [`PostLike::set`], for comments.
+    pub async fn set(
+        pool: &Pool,
+        comment_id: i32,
+        user_id: i32,
+        liked: bool,
+    ) -> Result<Option<LikeState>, DBError> {
+        use crate::db::schema::{post_comment_likes, post_comments};
+
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                let exists = post_comments::table
+                    .find(comment_id)
+                    .filter(post_comments::deleted_at.is_null())
+                    .select(post_comments::id)
+                    .first::<i32>(conn)
+                    .optional()?
+                    .is_some();
+                if !exists {
+                    return Ok(None);
+                }
+
+                let changed = if liked {
+                    diesel::insert_into(post_comment_likes::table)
+                        .values((
+                            post_comment_likes::comment_id.eq(comment_id),
+                            post_comment_likes::user_id.eq(user_id),
+                        ))
+                        .on_conflict_do_nothing()
+                        .execute(conn)?
+                } else {
+                    diesel::delete(
+                        post_comment_likes::table.filter(
+                            post_comment_likes::comment_id
+                                .eq(comment_id)
+                                .and(post_comment_likes::user_id.eq(user_id)),
+                        ),
+                    )
+                    .execute(conn)?
+                };
+
+                let delta = if liked { 1 } else { -1 } * changed as i32;
+                let likes_count = diesel::update(post_comments::table.find(comment_id))
+                    .set(post_comments::likes_count.eq(post_comments::likes_count + delta))
+                    .returning(post_comments::likes_count)
+                    .get_result(conn)?;
+
+                Ok(Some(LikeState { liked, likes_count }))
+            })
+        })
+        .await
+    }
+
+    /// Which of `comment_ids` the user has liked.
+    pub fn liked_ids(
+        conn: &mut PgConnection,
+        user_id: i32,
+        comment_ids: &[i32],
+    ) -> Result<HashSet<i32>, diesel::result::Error> {
+        use crate::db::schema::post_comment_likes;
+
+        if comment_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        Ok(post_comment_likes::table
+            .filter(post_comment_likes::user_id.eq(user_id))
+            .filter(post_comment_likes::comment_id.eq_any(comment_ids))
+            .select(post_comment_likes::comment_id)
+            .load::<i32>(conn)?
+            .into_iter()
+            .collect())
+    }
+}
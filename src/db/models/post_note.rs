@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, Utc};
+use deadpool_diesel::postgres::Pool;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{post::Post, user::User};
+use crate::db::{errors::DBError, schema, utils::execute_db_operation};
+
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone, Serialize, PartialEq)]
+#[diesel(table_name = schema::post_notes)]
+pub struct PostNote {
+    pub id: i32,
+    pub post_id: i32,
+    pub parent_id: Option<i32>,
+    pub author_id: Option<i32>,
+    pub post_version: Option<i32>,
+    pub anchor_start: Option<i32>,
+    pub anchor_end: Option<i32>,
+    /// The anchored text as it was when the note was written.
+    pub quote: Option<String>,
+    pub body: String,
+    pub resolved_at: Option<NaiveDateTime>,
+    pub resolved_by: Option<i32>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Deserialize, Debug)]
+#[diesel(table_name = schema::post_notes)]
+pub struct NewPostNote {
+    pub post_id: i32,
+    pub parent_id: Option<i32>,
+    pub author_id: Option<i32>,
+    pub post_version: Option<i32>,
+    pub anchor_start: Option<i32>,
+    pub anchor_end: Option<i32>,
+    pub quote: Option<String>,
+    pub body: String,
+}
+
+/// A note along with the name of whoever wrote it.
+#[derive(Debug, Serialize)]
+pub struct PostNoteEntry {
+    #[serde(flatten)]
+    pub note: PostNote,
+    pub author_name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostNoteThread {
+    #[serde(flatten)]
+    pub note: PostNoteEntry,
+    /// Whether the post changed since the note was anchored, so the offsets may be off.
+    pub outdated: bool,
+    pub replies: Vec<PostNoteEntry>,
+}
+
+/// The post author to let know about a note someone else left on their post.
+#[derive(Debug, Clone)]
+pub struct NoteRecipient {
+    pub email: String,
+    pub post_title: String,
+    pub note_author: String,
+}
+
+/// Outcome of anchoring a new note on a post.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum AnchoredNote {
+    Created(PostNote, Option<NoteRecipient>),
+    /// The note was written against another version than the current one; holds the latter.
+    StaleVersion(i32),
+    /// The range doesn't fit in the post's content.
+    InvalidRange,
+}
+
+impl PostNote {
+    /// Starts a thread on a range of the post's current content, for the people taking part
+    /// in its workflow.
+    pub async fn create(
+        pool: &Pool,
+        user: User,
+        new_note: NewPostNote,
+    ) -> Result<Option<AnchoredNote>, DBError> {
+        use crate::db::schema::post_notes;
+
+        execute_db_operation(pool, move |conn| {
+            let Some(post) = Post::find_in_workflow(conn, new_note.post_id, &user)? else {
+                return Ok(None);
+            };
+            if new_note.post_version != Some(post.version) {
+                return Ok(Some(AnchoredNote::StaleVersion(post.version)));
+            }
+
+            let start = new_note.anchor_start.unwrap_or_default().max(0) as usize;
+            let end = new_note.anchor_end.unwrap_or_default().max(0) as usize;
+            if end <= start || end > post.content.chars().count() {
+                return Ok(Some(AnchoredNote::InvalidRange));
+            }
+            let quote: String = post.content.chars().skip(start).take(end - start).collect();
+
+            let note: Self = diesel::insert_into(post_notes::table)
+                .values(NewPostNote {
+                    quote: Some(quote),
+                    ..new_note
+                })
+                .returning(Self::as_returning())
+                .get_result(conn)?;
+
+            let recipient = Self::recipient(conn, &post, &user)?;
+            Ok(Some(AnchoredNote::Created(note, recipient)))
+        })
+        .await
+    }
+
+    /// Replies to a thread. Replying to a reply adds to the same thread.
+    pub async fn reply(
+        pool: &Pool,
+        user: User,
+        note_id: i32,
+        body: String,
+    ) -> Result<Option<(Self, Option<NoteRecipient>)>, DBError> {
+        use crate::db::schema::post_notes;
+
+        execute_db_operation(pool, move |conn| {
+            let Some((thread, post)) = Self::find_thread(conn, note_id, &user)? else {
+                return Ok(None);
+            };
+
+            let note: Self = diesel::insert_into(post_notes::table)
+                .values(NewPostNote {
+                    post_id: post.id,
+                    parent_id: Some(thread.id),
+                    author_id: Some(user.id),
+                    post_version: None,
+                    anchor_start: None,
+                    anchor_end: None,
+                    quote: None,
+                    body,
+                })
+                .returning(Self::as_returning())
+                .get_result(conn)?;
+
+            let recipient = Self::recipient(conn, &post, &user)?;
+            Ok(Some((note, recipient)))
+        })
+        .await
+    }
+
+    /// Threads on a post in content order, oldest reply first.
+    pub async fn list(
+        pool: &Pool,
+        user: User,
+        post_id: i32,
+        include_resolved: bool,
+    ) -> Result<Option<Vec<PostNoteThread>>, DBError> {
+        use crate::db::schema::{post_notes, users};
+
+        execute_db_operation(pool, move |conn| {
+            let Some(post) = Post::find_in_workflow(conn, post_id, &user)? else {
+                return Ok(None);
+            };
+
+            let mut query_builder = post_notes::table
+                .left_join(users::table)
+                .filter(post_notes::post_id.eq(post_id))
+                .filter(post_notes::parent_id.is_null())
+                .select((Self::as_select(), users::name.nullable()))
+                .into_boxed();
+
+            if !include_resolved {
+                query_builder = query_builder.filter(post_notes::resolved_at.is_null());
+            }
+
+            let threads: Vec<(Self, Option<String>)> = query_builder
+                .order((
+                    post_notes::anchor_start.asc(),
+                    post_notes::created_at.asc(),
+                    post_notes::id.asc(),
+                ))
+                .load(conn)?;
+
+            let thread_ids: Vec<i32> = threads.iter().map(|(note, _)| note.id).collect();
+            let replies: Vec<(Self, Option<String>)> = post_notes::table
+                .left_join(users::table)
+                .filter(post_notes::parent_id.eq_any(thread_ids))
+                .order((post_notes::created_at.asc(), post_notes::id.asc()))
+                .select((Self::as_select(), users::name.nullable()))
+                .load(conn)?;
+
+            let mut replies_by_thread: HashMap<i32, Vec<PostNoteEntry>> = HashMap::new();
+            for (note, author_name) in replies {
+                replies_by_thread
+                    .entry(note.parent_id.unwrap_or_default())
+                    .or_default()
+                    .push(PostNoteEntry { note, author_name });
+            }
+
+            Ok(Some(
+                threads
+                    .into_iter()
+                    .map(|(note, author_name)| PostNoteThread {
+                        outdated: note.post_version != Some(post.version),
+                        replies: replies_by_thread.remove(&note.id).unwrap_or_default(),
+                        note: PostNoteEntry { note, author_name },
+                    })
+                    .collect(),
+            ))
+        })
+        .await
+    }
+
+    /// Resolves or reopens the thread a note belongs to.
+    pub async fn set_resolved(
+        pool: &Pool,
+        user: User,
+        note_id: i32,
+        resolved: bool,
+    ) -> Result<Option<Self>, DBError> {
+        use crate::db::schema::post_notes;
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            let Some((thread, _)) = Self::find_thread(conn, note_id, &user)? else {
+                return Ok(None);
+            };
+
+            let (resolved_at, resolved_by) = if resolved {
+                (Some(now), Some(user.id))
+            } else {
+                (None, None)
+            };
+
+            diesel::update(post_notes::table.filter(post_notes::id.eq(thread.id)))
+                .set((
+                    post_notes::resolved_at.eq(resolved_at),
+                    post_notes::resolved_by.eq(resolved_by),
+                    post_notes::updated_at.eq(now),
+                ))
+                .returning(Self::as_returning())
+                .get_result(conn)
+                .optional()
+        })
+        .await
+    }
+
+    /// Deletes a note, and its replies when it starts a thread. Only its author and moderators
+    /// can delete it.
+    pub async fn delete(pool: &Pool, user: User, note_id: i32) -> Result<usize, DBError> {
+        use crate::db::schema::post_notes;
+
+        execute_db_operation(pool, move |conn| {
+            let mut query_builder = diesel::delete(post_notes::table)
+                .filter(post_notes::id.eq(note_id))
+                .into_boxed();
+
+            if !user.is_mod() {
+                query_builder = query_builder.filter(post_notes::author_id.eq(user.id));
+            }
+
+            query_builder.execute(conn)
+        })
+        .await
+    }
+
+    /// The first note of the thread `note_id` belongs to and its post, if the user takes part
+    /// in the post's workflow.
+    fn find_thread(
+        conn: &mut PgConnection,
+        note_id: i32,
+        user: &User,
+    ) -> Result<Option<(Self, Post)>, diesel::result::Error> {
+        use crate::db::schema::post_notes;
+
+        let note: Option<Self> = post_notes::table
+            .filter(post_notes::id.eq(note_id))
+            .select(Self::as_select())
+            .first(conn)
+            .optional()?;
+        let Some(note) = note else {
+            return Ok(None);
+        };
+
+        let thread = match note.parent_id {
+            Some(parent_id) => post_notes::table
+                .filter(post_notes::id.eq(parent_id))
+                .select(Self::as_select())
+                .first(conn)?,
+            None => note,
+        };
+
+        Ok(Post::find_in_workflow(conn, thread.post_id, user)?.map(|post| (thread, post)))
+    }
+
+    fn recipient(
+        conn: &mut PgConnection,
+        post: &Post,
+        note_author: &User,
+    ) -> Result<Option<NoteRecipient>, diesel::result::Error> {
+        use crate::db::schema::users;
+
+        if post.author_id == note_author.id {
+            return Ok(None);
+        }
+
+        let email: String = users::table
+            .filter(users::id.eq(post.author_id))
+            .select(users::email)
+            .first(conn)?;
+
+        Ok(Some(NoteRecipient {
+            email,
+            post_title: post.title.clone(),
+            note_author: note_author.name.clone(),
+        }))
+    }
+}
@@ -0,0 +1,159 @@
+use chrono::{NaiveDateTime, Utc};
+use deadpool_diesel::postgres::Pool;
+use diesel::{dsl, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{post::Post, user::User};
+use crate::db::{errors::DBError, schema, utils::execute_db_operation};
+use crate::services::{preview, site};
+
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone, Serialize, PartialEq)]
+#[diesel(table_name = schema::post_preview_tokens)]
+pub struct PostPreviewToken {
+    pub id: i32,
+    pub post_id: i32,
+    pub created_by: Option<i32>,
+    pub label: Option<String>,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Deserialize, Debug)]
+#[diesel(table_name = schema::post_preview_tokens)]
+pub struct NewPostPreviewToken {
+    pub post_id: i32,
+    pub created_by: Option<i32>,
+    pub label: Option<String>,
+    pub expires_at: NaiveDateTime,
+}
+
+/// A preview token along with the signed value and link to share.
+#[derive(Debug, Serialize)]
+pub struct PreviewLink {
+    #[serde(flatten)]
+    pub token: PostPreviewToken,
+    pub preview_token: String,
+    pub url: String,
+}
+
+impl PreviewLink {
+    fn new(token: PostPreviewToken, post_slug: &str) -> Self {
+        let preview_token = preview::sign(token.id, token.post_id, token.expires_at);
+        let url = format!(
+            "{}?preview_token={}",
+            site::post_url(post_slug),
+            preview_token
+        );
+
+        Self {
+            token,
+            preview_token,
+            url,
+        }
+    }
+}
+
+impl PostPreviewToken {
+    /// Issues a preview link for a post the user may edit, or `None` if they can't.
+    pub async fn create(
+        pool: &Pool,
+        user: User,
+        new_token: NewPostPreviewToken,
+    ) -> Result<Option<PreviewLink>, DBError> {
+        use crate::db::schema::post_preview_tokens;
+
+        execute_db_operation(pool, move |conn| {
+            let Some(post) = Post::find_editable(conn, new_token.post_id, &user)? else {
+                return Ok(None);
+            };
+
+            let token: Self = diesel::insert_into(post_preview_tokens::table)
+                .values(&new_token)
+                .returning(Self::as_returning())
+                .get_result(conn)?;
+
+            Ok(Some(PreviewLink::new(token, &post.slug)))
+        })
+        .await
+    }
+
+    /// Every preview link issued for a post the user may edit, newest first.
+    pub async fn list(
+        pool: &Pool,
+        user: User,
+        post_id: i32,
+    ) -> Result<Option<Vec<PreviewLink>>, DBError> {
+        use crate::db::schema::post_preview_tokens;
+
+        execute_db_operation(pool, move |conn| {
+            let Some(post) = Post::find_editable(conn, post_id, &user)? else {
+                return Ok(None);
+            };
+
+            let tokens: Vec<Self> = post_preview_tokens::table
+                .filter(post_preview_tokens::post_id.eq(post_id))
+                .order(post_preview_tokens::id.desc())
+                .select(Self::as_select())
+                .load(conn)?;
+
+            Ok(Some(
+                tokens
+                    .into_iter()
+                    .map(|token| PreviewLink::new(token, &post.slug))
+                    .collect(),
+            ))
+        })
+        .await
+    }
+
+    /// Revokes a preview link of a post the user may edit. Revoking twice is a no-op.
+    pub async fn revoke(pool: &Pool, user: User, token_id: i32) -> Result<Option<Self>, DBError> {
+        use crate::db::schema::post_preview_tokens;
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            let Some(token) = post_preview_tokens::table
+                .filter(post_preview_tokens::id.eq(token_id))
+                .select(Self::as_select())
+                .first(conn)
+                .optional()?
+            else {
+                return Ok(None);
+            };
+            if Post::find_editable(conn, token.post_id, &user)?.is_none() {
+                return Ok(None);
+            }
+            if token.revoked_at.is_some() {
+                return Ok(Some(token));
+            }
+
+            diesel::update(post_preview_tokens::table.filter(post_preview_tokens::id.eq(token_id)))
+                .set(post_preview_tokens::revoked_at.eq(now))
+                .returning(Self::as_returning())
+                .get_result(conn)
+                .map(Some)
+        })
+        .await
+    }
+
+    /// Whether a token that passed signature verification is still in force for the post.
+    pub fn is_active(
+        conn: &mut PgConnection,
+        token_id: i32,
+        post_id: i32,
+        now: NaiveDateTime,
+    ) -> Result<bool, diesel::result::Error> {
+        use crate::db::schema::post_preview_tokens;
+
+        diesel::select(dsl::exists(
+            post_preview_tokens::table
+                .filter(post_preview_tokens::id.eq(token_id))
+                .filter(post_preview_tokens::post_id.eq(post_id))
+                .filter(post_preview_tokens::revoked_at.is_null())
+                .filter(post_preview_tokens::expires_at.gt(now)),
+        ))
+        .get_result(conn)
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use deadpool_diesel::postgres::Pool;
+use diesel::{
+    prelude::*,
+    sql_types::{BigInt, Double, Int4, Nullable, Timestamptz},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    category::Category,
+    post::{is_live, Post, PostWithRelations},
+    user::User,
+};
+use crate::db::{errors::DBError, utils::execute_db_operation};
+
+/// Binds: now, start of the window, half-life in hours, limit, category filter, tag filter,
+/// oldest publish time.
+///
+/// Each view in the window weighs 1, each like 3 and each comment 5, halving every half-life.
+/// Views rolled into the daily tables count as seen at noon of their day. Views, likes and
+/// comments all store timestamptz, so they compare with the binds as they are.
+const TRENDING_SQL: &str = r#"
+WITH events AS (
+    SELECT post_id, views::float8 AS weight,
+           (day + time '12:00') AT TIME ZONE 'UTC' AS happened_at
+    FROM post_view_daily
+    WHERE day >= ($2 AT TIME ZONE 'UTC')::date
+    UNION ALL
+    SELECT post_id, 1.0, created_at FROM post_views WHERE created_at >= $2
+    UNION ALL
+    SELECT post_id, 3.0, created_at FROM post_likes WHERE created_at >= $2
+    UNION ALL
+    SELECT post_id, 5.0, created_at
+    FROM post_comments
+    WHERE deleted_at IS NULL AND created_at >= $2
+)
+SELECT posts.id,
+       SUM(events.weight
+           * power(0.5, GREATEST(extract(epoch FROM $1 - events.happened_at), 0) / 3600.0 / $3)
+       )::float8 AS score
+FROM events
+JOIN posts ON posts.id = events.post_id
+WHERE posts.deleted_at IS NULL
+  AND posts.status = 'published' AND (posts.published_at IS NULL OR posts.published_at <= $1)
+  AND ($5::int4 IS NULL OR posts.category_id = $5)
+  AND ($6::int4 IS NULL OR $6 = ANY(posts.tag_ids))
+  AND ($7::timestamptz IS NULL OR COALESCE(posts.published_at, posts.created_at) >= $7)
+GROUP BY posts.id
+ORDER BY score DESC, posts.id DESC
+LIMIT $4
+"#;
+
+/// Binds: now, limit, category filter, tag filter, oldest publish time.
+///
+/// The same weights as trending over the whole life of the post, without decay.
+const POPULAR_SQL: &str = r#"
+SELECT posts.id,
+       (posts.view_count + 3.0 * posts.likes_count + 5.0 * COALESCE(comments.count, 0))::float8
+           AS score
+FROM posts
+LEFT JOIN (
+    SELECT post_id, COUNT(*) AS count
+    FROM post_comments
+    WHERE deleted_at IS NULL
+    GROUP BY post_id
+) comments ON comments.post_id = posts.id
+WHERE posts.deleted_at IS NULL
+  AND posts.status = 'published' AND (posts.published_at IS NULL OR posts.published_at <= $1)
+  AND (posts.view_count > 0 OR posts.likes_count > 0 OR comments.count > 0)
+  AND ($3::int4 IS NULL OR posts.category_id = $3)
+  AND ($4::int4 IS NULL OR $4 = ANY(posts.tag_ids))
+  AND ($5::timestamptz IS NULL OR COALESCE(posts.published_at, posts.created_at) >= $5)
+ORDER BY score DESC, posts.id DESC
+LIMIT $2
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RankingKind {
+    /// Engagement over the last days, recent engagement weighing more.
+    Trending,
+    /// Engagement over all time.
+    Popular,
+}
+
+impl RankingKind {
+    pub const ALL: [RankingKind; 2] = [RankingKind::Trending, RankingKind::Popular];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RankingKind::Trending => "trending",
+            RankingKind::Popular => "popular",
+        }
+    }
+}
+
+#[derive(QueryableByName, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PostScore {
+    #[diesel(sql_type = Int4)]
+    pub id: i32,
+    #[diesel(sql_type = Double)]
+    pub score: f64,
+}
+
+/// Narrows a ranking down to some of the posts.
+#[derive(Debug, Clone, Default)]
+pub struct RankingFilter {
+    pub category_id: Option<i32>,
+    pub tag_id: Option<i32>,
+    /// Leaves out posts published longer ago than this.
+    pub max_age_days: Option<i64>,
+}
+
+impl RankingFilter {
+    /// Whether the filter keeps every post, so the stored ranking can be used.
+    pub fn is_empty(&self) -> bool {
+        self.category_id.is_none() && self.tag_id.is_none() && self.max_age_days.is_none()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RankedPost {
+    #[serde(flatten)]
+    pub post: PostWithRelations,
+    pub score: f64,
+}
+
+pub struct PostRanking;
+
+impl PostRanking {
+    /// Posts kept in each stored ranking.
+    pub const SIZE: i64 = 500;
+    /// Days of engagement trending looks at.
+    const TRENDING_WINDOW_DAYS: i64 = 7;
+    /// Hours after which engagement counts half as much for trending.
+    const TRENDING_HALF_LIFE_HOURS: f64 = 24.0;
+
+    /// Scores the live posts that match the filter, best first.
+    pub async fn compute(
+        pool: &Pool,
+        kind: RankingKind,
+        filter: RankingFilter,
+        limit: i64,
+    ) -> Result<Vec<PostScore>, DBError> {
+        let now = Utc::now().naive_utc();
+        let published_after: Option<NaiveDateTime> =
+            filter.max_age_days.map(|days| now - Duration::days(days));
+
+        execute_db_operation(pool, move |conn| match kind {
+            RankingKind::Trending => diesel::sql_query(TRENDING_SQL)
+                .bind::<Timestamptz, _>(now)
+                .bind::<Timestamptz, _>(now - Duration::days(Self::TRENDING_WINDOW_DAYS))
+                .bind::<Double, _>(Self::TRENDING_HALF_LIFE_HOURS)
+                .bind::<BigInt, _>(limit)
+                .bind::<Nullable<Int4>, _>(filter.category_id)
+                .bind::<Nullable<Int4>, _>(filter.tag_id)
+                .bind::<Nullable<Timestamptz>, _>(published_after)
+                .load(conn),
+            RankingKind::Popular => diesel::sql_query(POPULAR_SQL)
+                .bind::<Timestamptz, _>(now)
+                .bind::<BigInt, _>(limit)
+                .bind::<Nullable<Int4>, _>(filter.category_id)
+                .bind::<Nullable<Int4>, _>(filter.tag_id)
+                .bind::<Nullable<Timestamptz>, _>(published_after)
+                .load(conn),
+        })
+        .await
+    }
+
+    /// Loads the best scored posts that are still live, in score order.
+    pub async fn find(
+        pool: &Pool,
+        scores: Vec<PostScore>,
+        limit: i64,
+        viewer_id: Option<i32>,
+    ) -> Result<Vec<RankedPost>, DBError> {
+        use crate::db::schema::{categories, posts, users};
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            let matching: HashSet<i32> = posts::table
+                .filter(posts::id.eq_any(scores.iter().map(|scored| scored.id)))
+                .filter(is_live(now))
+                .select(posts::id)
+                .load::<i32>(conn)?
+                .into_iter()
+                .collect();
+            let top: Vec<PostScore> = scores
+                .into_iter()
+                .filter(|scored| matching.contains(&scored.id))
+                .take(limit as usize)
+                .collect();
+
+            let results: Vec<(Post, Option<Category>, User)> = posts::table
+                .left_join(categories::table)
+                .inner_join(users::table)
+                .filter(posts::id.eq_any(top.iter().map(|scored| scored.id)))
+                .select((
+                    Post::as_select(),
+                    Option::<Category>::as_select(),
+                    User::as_select(),
+                ))
+                .load(conn)?;
+
+            let mut posts_by_id: HashMap<i32, PostWithRelations> =
+                Post::with_relations(conn, results, viewer_id)?
+                    .into_iter()
+                    .map(|post| (post.post.id, post))
+                    .collect();
+
+            Ok(top
+                .into_iter()
+                .filter_map(|scored| {
+                    posts_by_id.remove(&scored.id).map(|post| RankedPost {
+                        post,
+                        score: scored.score,
+                    })
+                })
+                .collect())
+        })
+        .await
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use deadpool_diesel::postgres::Pool;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::post::Post;
+use crate::db::{errors::DBError, schema, utils::execute_db_operation};
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    diesel_derive_enum::DbEnum,
+)]
+#[ExistingTypePath = "crate::db::schema::sql_types::ReactionKind"]
+#[serde(rename_all = "snake_case")]
+pub enum ReactionKind {
+    ThumbsUp,
+    Heart,
+    Laugh,
+    Celebrate,
+    Insightful,
+    Confused,
+}
+
+#[derive(Queryable, Selectable, Debug, Serialize, PartialEq)]
+#[diesel(table_name = schema::post_reactions)]
+pub struct PostReaction {
+    pub post_id: i32,
+    pub user_id: i32,
+    pub kind: ReactionKind,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReactionCount {
+    pub kind: ReactionKind,
+    pub count: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reacted: Option<bool>,
+}
+
+impl PostReaction {
+    /// Adds or removes one of the user's reactions on a live post; a user can leave several
+    /// kinds, each at most once. Returns the post's updated reaction counts.
+    pub async fn set(
+        pool: &Pool,
+        post_id: i32,
+        user_id: i32,
+        kind: ReactionKind,
+        reacted: bool,
+    ) -> Result<Option<Vec<ReactionCount>>, DBError> {
+        use crate::db::schema::post_reactions;
+
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                if !Post::exists_live(conn, post_id)? {
+                    return Ok(None);
+                }
+
+                if reacted {
+                    diesel::insert_into(post_reactions::table)
+                        .values((
+                            post_reactions::post_id.eq(post_id),
+                            post_reactions::user_id.eq(user_id),
+                            post_reactions::kind.eq(kind),
+                        ))
+                        .on_conflict_do_nothing()
+                        .execute(conn)?;
+                } else {
+                    diesel::delete(
+                        post_reactions::table.filter(
+                            post_reactions::post_id
+                                .eq(post_id)
+                                .and(post_reactions::user_id.eq(user_id))
+                                .and(post_reactions::kind.eq(kind)),
+                        ),
+                    )
+                    .execute(conn)?;
+                }
+
+                let mut counts = Self::counts(conn, &[post_id], Some(user_id))?;
+                Ok(Some(counts.remove(&post_id).unwrap_or_default()))
+            })
+        })
+        .await
+    }
+
+    /// Reaction counts of each post, most used first. With a viewer, each count also says
+    /// whether they left that reaction.
+    pub fn counts(
+        conn: &mut PgConnection,
+        post_ids: &[i32],
+        viewer_id: Option<i32>,
+    ) -> Result<HashMap<i32, Vec<ReactionCount>>, diesel::result::Error> {
+        use crate::db::schema::post_reactions;
+
+        if post_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let totals: Vec<(i32, ReactionKind, i64)> = post_reactions::table
+            .filter(post_reactions::post_id.eq_any(post_ids))
+            .group_by((post_reactions::post_id, post_reactions::kind))
+            .select((
+                post_reactions::post_id,
+                post_reactions::kind,
+                diesel::dsl::count_star(),
+            ))
+            .load(conn)?;
+
+        let mine: Vec<(i32, ReactionKind)> = match viewer_id {
+            Some(viewer_id) => post_reactions::table
+                .filter(post_reactions::post_id.eq_any(post_ids))
+                .filter(post_reactions::user_id.eq(viewer_id))
+                .select((post_reactions::post_id, post_reactions::kind))
+                .load(conn)?,
+            None => Vec::new(),
+        };
+
+        let mut counts: HashMap<i32, Vec<ReactionCount>> = HashMap::new();
+        for (post_id, kind, count) in totals {
+            counts.entry(post_id).or_default().push(ReactionCount {
+                kind,
+                count,
+                reacted: viewer_id.map(|_| mine.contains(&(post_id, kind))),
+            });
+        }
+        for reactions in counts.values_mut() {
+            reactions.sort_by(|a, b| b.count.cmp(&a.count).then(a.kind.cmp(&b.kind)));
+        }
+
+        Ok(counts)
+    }
+}
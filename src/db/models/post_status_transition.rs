@@ -0,0 +1,100 @@
+use chrono::NaiveDateTime;
+use deadpool_diesel::postgres::Pool;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    post::{Post, PostStatus},
+    user::User,
+};
+use crate::db::{errors::DBError, schema, utils::execute_db_operation};
+
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone, Serialize, PartialEq)]
+#[diesel(table_name = schema::post_status_transitions)]
+pub struct PostStatusTransition {
+    pub id: i32,
+    pub post_id: i32,
+    /// `None` for the status a post was created with.
+    pub from_status: Option<PostStatus>,
+    pub to_status: PostStatus,
+    /// `None` for changes made by the scheduler.
+    pub actor_id: Option<i32>,
+    pub comment: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Deserialize, Debug)]
+#[diesel(table_name = schema::post_status_transitions)]
+pub struct NewPostStatusTransition {
+    pub post_id: i32,
+    pub from_status: Option<PostStatus>,
+    pub to_status: PostStatus,
+    pub actor_id: Option<i32>,
+    pub comment: Option<String>,
+}
+
+/// A workflow step along with the name of whoever took it.
+#[derive(Debug, Serialize)]
+pub struct PostStatusHistoryEntry {
+    #[serde(flatten)]
+    pub transition: PostStatusTransition,
+    pub actor_name: Option<String>,
+}
+
+impl PostStatusTransition {
+    pub fn record(
+        conn: &mut PgConnection,
+        post_id: i32,
+        from_status: Option<PostStatus>,
+        to_status: PostStatus,
+        actor_id: Option<i32>,
+        comment: Option<String>,
+    ) -> Result<usize, diesel::result::Error> {
+        use crate::db::schema::post_status_transitions;
+
+        diesel::insert_into(post_status_transitions::table)
+            .values(NewPostStatusTransition {
+                post_id,
+                from_status,
+                to_status,
+                actor_id,
+                comment,
+            })
+            .execute(conn)
+    }
+
+    /// Workflow history of a post, oldest first, for the people taking part in its workflow.
+    pub async fn history(
+        pool: &Pool,
+        user: User,
+        post_id: i32,
+    ) -> Result<Option<Vec<PostStatusHistoryEntry>>, DBError> {
+        use crate::db::schema::{post_status_transitions, users};
+
+        execute_db_operation(pool, move |conn| {
+            if Post::find_in_workflow(conn, post_id, &user)?.is_none() {
+                return Ok(None);
+            }
+
+            let rows: Vec<(Self, Option<String>)> = post_status_transitions::table
+                .left_join(users::table)
+                .filter(post_status_transitions::post_id.eq(post_id))
+                .order((
+                    post_status_transitions::created_at.asc(),
+                    post_status_transitions::id.asc(),
+                ))
+                .select((Self::as_select(), users::name.nullable()))
+                .load(conn)?;
+
+            Ok(Some(
+                rows.into_iter()
+                    .map(|(transition, actor_name)| PostStatusHistoryEntry {
+                        transition,
+                        actor_name,
+                    })
+                    .collect(),
+            ))
+        })
+        .await
+    }
+}
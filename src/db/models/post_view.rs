@@ -1,10 +1,21 @@
-use chrono::NaiveDateTime;
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::{Duration, NaiveDateTime, Utc};
 use deadpool_diesel::postgres::Pool;
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::db::{errors::DBError, schema, utils::execute_db_operation};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, diesel_derive_enum::DbEnum)]
+#[ExistingTypePath = "crate::db::schema::sql_types::DeviceClass"]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceClass {
+    Desktop,
+    Mobile,
+    Tablet,
+}
+
 #[derive(Queryable, Identifiable, Selectable, Debug, PartialEq, Serialize)]
 #[diesel(table_name = schema::post_views)]
 pub struct PostView {
@@ -12,13 +23,34 @@ pub struct PostView {
     pub post_id: i32,
     pub user_id: Option<i32>,
     pub created_at: NaiveDateTime,
+    pub visitor_hash: Option<String>,
+    pub referrer_domain: Option<String>,
+    pub utm_source: Option<String>,
+    pub utm_medium: Option<String>,
+    pub utm_campaign: Option<String>,
+    pub device: Option<DeviceClass>,
 }
 
-#[derive(Insertable, Deserialize, Debug)]
+/// A view as it is buffered and stored. The analytics fields default to empty so views
+/// buffered before they existed can still be read.
+#[derive(Insertable, Serialize, Deserialize, Debug, Clone)]
 #[diesel(table_name = schema::post_views)]
 pub struct NewPostView {
     pub post_id: i32,
     pub user_id: Option<i32>,
+    pub created_at: NaiveDateTime,
+    #[serde(default)]
+    pub visitor_hash: Option<String>,
+    #[serde(default)]
+    pub referrer_domain: Option<String>,
+    #[serde(default)]
+    pub utm_source: Option<String>,
+    #[serde(default)]
+    pub utm_medium: Option<String>,
+    #[serde(default)]
+    pub utm_campaign: Option<String>,
+    #[serde(default)]
+    pub device: Option<DeviceClass>,
 }
 
 impl PostView {
@@ -34,22 +66,71 @@ impl PostView {
         .await
     }
 
-    pub fn create_query(
-        conn: &mut PgConnection,
-        q_post_id: i32,
-        q_user_id: Option<i32>,
-    ) -> Result<Self, diesel::result::Error> {
-        use crate::db::schema::post_views::dsl::*;
-        let new_view = NewPostView {
-            post_id: q_post_id,
-            user_id: q_user_id,
-        };
+    /// Stores a batch of views and adds them to the view counts of their posts, in one
+    /// transaction. Views of posts that were deleted in the meantime are dropped. A batch with
+    /// an id is stored once, trying it again stores nothing. Returns how many views were stored.
+    pub async fn record_batch(
+        pool: &Pool,
+        batch_id: Option<String>,
+        views: Vec<NewPostView>,
+    ) -> Result<usize, DBError> {
+        use crate::db::schema::{post_view_batches, post_views, posts};
+
+        if views.is_empty() {
+            return Ok(0);
+        }
 
-        let view = diesel::insert_into(post_views)
-            .values(&new_view)
-            .returning(Self::as_returning())
-            .get_result(conn)?;
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                if let Some(batch_id) = batch_id {
+                    let new_batch = diesel::insert_into(post_view_batches::table)
+                        .values(post_view_batches::id.eq(batch_id))
+                        .on_conflict_do_nothing()
+                        .execute(conn)?;
+                    if new_batch == 0 {
+                        return Ok(0);
+                    }
 
-        Ok(view)
+                    let expired = Utc::now() - Duration::days(1);
+                    diesel::delete(
+                        post_view_batches::table.filter(post_view_batches::stored_at.lt(expired)),
+                    )
+                    .execute(conn)?;
+                }
+
+                let post_ids: HashSet<i32> = views.iter().map(|view| view.post_id).collect();
+                let existing: HashSet<i32> = posts::table
+                    .filter(posts::id.eq_any(post_ids))
+                    .select(posts::id)
+                    .load::<i32>(conn)?
+                    .into_iter()
+                    .collect();
+                let views: Vec<NewPostView> = views
+                    .into_iter()
+                    .filter(|view| existing.contains(&view.post_id))
+                    .collect();
+
+                // Posts in id order, so concurrent batches lock them in the same order
+                let mut counts: BTreeMap<i32, i32> = BTreeMap::new();
+                for view in &views {
+                    *counts.entry(view.post_id).or_default() += 1;
+                }
+                for (post_id, count) in counts {
+                    diesel::update(posts::table.filter(posts::id.eq(post_id)))
+                        .set(posts::view_count.eq(posts::view_count + count))
+                        .execute(conn)?;
+                }
+
+                // Stay well below the bind parameter limit of Postgres
+                for chunk in views.chunks(5_000) {
+                    diesel::insert_into(post_views::table)
+                        .values(chunk)
+                        .execute(conn)?;
+                }
+
+                Ok(views.len())
+            })
+        })
+        .await
     }
 }
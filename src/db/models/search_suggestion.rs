@@ -0,0 +1,113 @@
+use chrono::Utc;
+use deadpool_diesel::postgres::Pool;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Float4, Int4, Text, Timestamptz};
+use serde::Serialize;
+
+use crate::db::{errors::DBError, utils::execute_db_operation};
+
+/// Minimum `word_similarity` for a fuzzy match, low enough to forgive a typo or two.
+const WORD_SIMILARITY_THRESHOLD: f32 = 0.4;
+/// Shortest term, after trimming, that is looked up at all.
+const MIN_TERM_CHARS: usize = 2;
+
+/// Binds: term, starts-with pattern, contains pattern, now, limit per kind.
+///
+/// Titles and names starting with the term always rank above fuzzy matches; within each
+/// group the trigram word similarity decides.
+const SUGGEST_SQL: &str = r#"
+(SELECT 'post' AS kind, id, title AS label, slug,
+        (word_similarity($1, title) + CASE WHEN title ILIKE $2 THEN 1 ELSE 0 END)::real AS score
+ FROM posts
+ WHERE (title ILIKE $3 OR $1 <% title)
+   AND status = 'published' AND (published_at IS NULL OR published_at <= $4)
+   AND deleted_at IS NULL
+ ORDER BY score DESC, view_count DESC, id DESC
+ LIMIT $5)
+UNION ALL
+(SELECT 'tag' AS kind, id, name AS label, slug,
+        (word_similarity($1, name) + CASE WHEN name ILIKE $2 THEN 1 ELSE 0 END)::real AS score
+ FROM tags
+ WHERE (name ILIKE $3 OR $1 <% name) AND deleted_at IS NULL
+ ORDER BY score DESC, id DESC
+ LIMIT $5)
+UNION ALL
+(SELECT 'category' AS kind, id, name AS label, slug,
+        (word_similarity($1, name) + CASE WHEN name ILIKE $2 THEN 1 ELSE 0 END)::real AS score
+ FROM categories
+ WHERE (name ILIKE $3 OR $1 <% name) AND deleted_at IS NULL
+ ORDER BY score DESC, id DESC
+ LIMIT $5)
+"#;
+
+#[derive(QueryableByName, Debug, Serialize)]
+pub struct SearchSuggestion {
+    #[serde(skip)]
+    #[diesel(sql_type = Text)]
+    pub kind: String,
+    #[diesel(sql_type = Int4)]
+    pub id: i32,
+    #[diesel(sql_type = Text)]
+    pub label: String,
+    #[diesel(sql_type = Text)]
+    pub slug: String,
+    #[diesel(sql_type = Float4)]
+    pub score: f32,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SearchSuggestions {
+    pub posts: Vec<SearchSuggestion>,
+    pub tags: Vec<SearchSuggestion>,
+    pub categories: Vec<SearchSuggestion>,
+}
+
+impl SearchSuggestion {
+    /// Live post titles, tags and categories matching `term`, at most `limit` of each. Terms
+    /// that are too short once trimmed match nothing.
+    pub async fn find(pool: &Pool, term: String, limit: i64) -> Result<SearchSuggestions, DBError> {
+        let term = term.trim().to_string();
+        if term.chars().count() < MIN_TERM_CHARS {
+            return Ok(SearchSuggestions::default());
+        }
+        let pattern = escape_like(&term);
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                diesel::sql_query(format!(
+                    "SET LOCAL pg_trgm.word_similarity_threshold = {}",
+                    WORD_SIMILARITY_THRESHOLD
+                ))
+                .execute(conn)?;
+
+                let suggestions: Vec<SearchSuggestion> = diesel::sql_query(SUGGEST_SQL)
+                    .bind::<Text, _>(&term)
+                    .bind::<Text, _>(format!("{}%", pattern))
+                    .bind::<Text, _>(format!("%{}%", pattern))
+                    .bind::<Timestamptz, _>(now)
+                    .bind::<BigInt, _>(limit)
+                    .load(conn)?;
+
+                let mut grouped = SearchSuggestions::default();
+                for suggestion in suggestions {
+                    match suggestion.kind.as_str() {
+                        "post" => grouped.posts.push(suggestion),
+                        "tag" => grouped.tags.push(suggestion),
+                        _ => grouped.categories.push(suggestion),
+                    }
+                }
+
+                Ok(grouped)
+            })
+        })
+        .await
+    }
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
@@ -0,0 +1,461 @@
+use chrono::{NaiveDateTime, Utc};
+use deadpool_diesel::postgres::Pool;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{post::is_live, user::User};
+use crate::db::{errors::DBError, schema, utils::execute_db_operation};
+
+#[derive(Queryable, Identifiable, Selectable, Debug, Clone, Serialize, PartialEq)]
+#[diesel(table_name = schema::series)]
+pub struct Series {
+    pub id: i32,
+    pub title: String,
+    pub slug: String,
+    pub description: Option<String>,
+    pub cover_image: Option<String>,
+    pub author_id: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Deserialize, Debug)]
+#[diesel(table_name = schema::series)]
+pub struct NewSeries {
+    pub title: String,
+    pub slug: String,
+    pub description: Option<String>,
+    pub cover_image: Option<String>,
+    pub author_id: i32,
+}
+
+#[derive(AsChangeset, Deserialize, Debug)]
+#[diesel(table_name = schema::series)]
+pub struct UpdateSeries {
+    pub title: Option<String>,
+    pub slug: Option<String>,
+    pub description: Option<Option<String>>,
+    pub cover_image: Option<Option<String>>,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesPart {
+    pub post_id: i32,
+    pub title: String,
+    pub slug: String,
+    pub position: i32,
+    pub is_published: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeriesWithPosts {
+    #[serde(flatten)]
+    pub series: Series,
+    pub posts: Vec<SeriesPart>,
+}
+
+/// Where a post sits in its series, included when viewing the post.
+#[derive(Debug, Serialize)]
+pub struct SeriesNavigation {
+    pub id: i32,
+    pub title: String,
+    pub slug: String,
+    pub position: i32,
+    pub total: i32,
+    pub previous: Option<SeriesPart>,
+    pub next: Option<SeriesPart>,
+}
+
+impl Series {
+    pub async fn create(pool: &Pool, new_series: NewSeries) -> Result<Self, DBError> {
+        use crate::db::schema::series::dsl::*;
+
+        execute_db_operation(pool, move |conn| {
+            diesel::insert_into(series)
+                .values(&new_series)
+                .returning(Self::as_returning())
+                .get_result(conn)
+        })
+        .await
+    }
+
+    pub async fn update(
+        pool: &Pool,
+        series_id: i32,
+        user: User,
+        update_series: UpdateSeries,
+    ) -> Result<Option<Self>, DBError> {
+        use crate::db::schema::series::dsl::*;
+
+        execute_db_operation(pool, move |conn| {
+            let mut query_builder = diesel::update(series).filter(id.eq(series_id)).into_boxed();
+
+            if !user.is_mod() {
+                query_builder = query_builder.filter(author_id.eq(user.id));
+            }
+
+            query_builder
+                .set(&update_series)
+                .returning(Self::as_returning())
+                .get_result(conn)
+                .optional()
+        })
+        .await
+    }
+
+    pub async fn delete(pool: &Pool, user: User, series_id: i32) -> Result<usize, DBError> {
+        use crate::db::schema::series::dsl::*;
+
+        execute_db_operation(pool, move |conn| {
+            if !user.is_mod() {
+                diesel::delete(series.filter(id.eq(series_id).and(author_id.eq(user.id))))
+                    .execute(conn)
+            } else {
+                diesel::delete(series.filter(id.eq(series_id))).execute(conn)
+            }
+        })
+        .await
+    }
+
+    /// Public view of a series, listing only the parts that are live.
+    pub async fn find_by_id_or_slug(
+        pool: &Pool,
+        series_id: Option<i32>,
+        series_slug: Option<String>,
+    ) -> Result<Option<SeriesWithPosts>, DBError> {
+        use crate::db::schema::series::dsl::*;
+
+        execute_db_operation(pool, move |conn| {
+            let query_builder = match (series_id, series_slug) {
+                (Some(series_id), _) => series.filter(id.eq(series_id)).into_boxed(),
+                (_, Some(series_slug)) => series.filter(slug.eq(series_slug)).into_boxed(),
+                _ => return Ok(None),
+            };
+
+            match query_builder
+                .select(Self::as_select())
+                .first(conn)
+                .optional()?
+            {
+                Some(found) => Self::with_posts(conn, found, true).map(Some),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    /// Series owned by `user` (every series for moderators) with all of their parts.
+    pub async fn list(pool: &Pool, user: User) -> Result<Vec<SeriesWithPosts>, DBError> {
+        use crate::db::schema::series::dsl::*;
+
+        execute_db_operation(pool, move |conn| {
+            let mut query_builder = series.into_boxed();
+
+            if !user.is_mod() {
+                query_builder = query_builder.filter(author_id.eq(user.id));
+            }
+
+            query_builder
+                .order(updated_at.desc())
+                .select(Self::as_select())
+                .load(conn)?
+                .into_iter()
+                .map(|found| Self::with_posts(conn, found, false))
+                .collect()
+        })
+        .await
+    }
+
+    /// Adds a post owned by the user at `position` (appended when missing or out of range).
+    /// A post that is already part of the series is moved instead.
+    pub async fn add_post(
+        pool: &Pool,
+        series_id: i32,
+        user: User,
+        post_id: i32,
+        position: Option<i32>,
+    ) -> Result<Option<SeriesWithPosts>, DBError> {
+        use crate::db::schema::{posts, series_posts};
+
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                if Self::find_owned(conn, series_id, &user)?.is_none() {
+                    return Ok(None);
+                }
+
+                let mut post_query = posts::table
+                    .filter(posts::id.eq(post_id))
+                    .filter(posts::deleted_at.is_null())
+                    .select(posts::id)
+                    .into_boxed();
+                if !user.is_mod() {
+                    post_query = post_query.filter(posts::author_id.eq(user.id));
+                }
+                if post_query.first::<i32>(conn).optional()?.is_none() {
+                    return Ok(None);
+                }
+
+                Self::detach(conn, series_id, post_id)?;
+
+                let total: i64 = series_posts::table
+                    .filter(series_posts::series_id.eq(series_id))
+                    .count()
+                    .get_result(conn)?;
+                let last = total as i32 + 1;
+                let position = position.map_or(last, |position| position.clamp(1, last));
+
+                diesel::update(
+                    series_posts::table.filter(
+                        series_posts::series_id
+                            .eq(series_id)
+                            .and(series_posts::position.ge(position)),
+                    ),
+                )
+                .set(series_posts::position.eq(series_posts::position + 1))
+                .execute(conn)?;
+
+                diesel::insert_into(series_posts::table)
+                    .values((
+                        series_posts::series_id.eq(series_id),
+                        series_posts::post_id.eq(post_id),
+                        series_posts::position.eq(position),
+                    ))
+                    .execute(conn)?;
+
+                let found = Self::touch(conn, series_id)?;
+                Self::with_posts(conn, found, false).map(Some)
+            })
+        })
+        .await
+    }
+
+    pub async fn remove_post(
+        pool: &Pool,
+        series_id: i32,
+        user: User,
+        post_id: i32,
+    ) -> Result<Option<SeriesWithPosts>, DBError> {
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                if Self::find_owned(conn, series_id, &user)?.is_none() {
+                    return Ok(None);
+                }
+
+                Self::detach(conn, series_id, post_id)?;
+                let found = Self::touch(conn, series_id)?;
+                Self::with_posts(conn, found, false).map(Some)
+            })
+        })
+        .await
+    }
+
+    /// Moves the listed posts to the front in the given order; parts that are not listed keep
+    /// their relative order after them and ids that are not part of the series are ignored.
+    pub async fn reorder(
+        pool: &Pool,
+        series_id: i32,
+        user: User,
+        post_ids: Vec<i32>,
+    ) -> Result<Option<SeriesWithPosts>, DBError> {
+        use crate::db::schema::series_posts;
+
+        execute_db_operation(pool, move |conn| {
+            conn.transaction(|conn| {
+                if Self::find_owned(conn, series_id, &user)?.is_none() {
+                    return Ok(None);
+                }
+
+                let current: Vec<i32> = series_posts::table
+                    .filter(series_posts::series_id.eq(series_id))
+                    .order(series_posts::position.asc())
+                    .select(series_posts::post_id)
+                    .load(conn)?;
+
+                let mut ordered: Vec<i32> = Vec::with_capacity(current.len());
+                for post_id in post_ids.into_iter().chain(current.iter().copied()) {
+                    if current.contains(&post_id) && !ordered.contains(&post_id) {
+                        ordered.push(post_id);
+                    }
+                }
+
+                for (index, post_id) in ordered.into_iter().enumerate() {
+                    diesel::update(
+                        series_posts::table.filter(
+                            series_posts::series_id
+                                .eq(series_id)
+                                .and(series_posts::post_id.eq(post_id)),
+                        ),
+                    )
+                    .set(series_posts::position.eq(index as i32 + 1))
+                    .execute(conn)?;
+                }
+
+                let found = Self::touch(conn, series_id)?;
+                Self::with_posts(conn, found, false).map(Some)
+            })
+        })
+        .await
+    }
+
+    /// Series navigation for a post. Parts that aren't live are skipped, except the post
+    /// itself so its author can preview a draft part in place.
+    pub fn navigation(
+        conn: &mut PgConnection,
+        post_id: i32,
+    ) -> Result<Option<SeriesNavigation>, diesel::result::Error> {
+        use crate::db::schema::{series, series_posts};
+
+        let found: Option<Series> = series::table
+            .inner_join(series_posts::table)
+            .filter(series_posts::post_id.eq(post_id))
+            .select(Series::as_select())
+            .first(conn)
+            .optional()?;
+
+        let Some(found) = found else {
+            return Ok(None);
+        };
+
+        let mut parts = Self::load_parts(conn, found.id, false)?;
+        parts.retain(|part| part.is_published || part.post_id == post_id);
+        for (index, part) in parts.iter_mut().enumerate() {
+            part.position = index as i32 + 1;
+        }
+
+        let Some(index) = parts.iter().position(|part| part.post_id == post_id) else {
+            return Ok(None);
+        };
+
+        Ok(Some(SeriesNavigation {
+            id: found.id,
+            title: found.title,
+            slug: found.slug,
+            position: index as i32 + 1,
+            total: parts.len() as i32,
+            previous: index.checked_sub(1).map(|previous| parts[previous].clone()),
+            next: parts.get(index + 1).cloned(),
+        }))
+    }
+
+    fn find_owned(
+        conn: &mut PgConnection,
+        series_id: i32,
+        user: &User,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        use crate::db::schema::series::dsl::*;
+
+        let mut query_builder = series.filter(id.eq(series_id)).into_boxed();
+
+        if !user.is_mod() {
+            query_builder = query_builder.filter(author_id.eq(user.id));
+        }
+
+        query_builder
+            .select(Self::as_select())
+            .first(conn)
+            .optional()
+    }
+
+    /// Removes a post from the series and closes the gap it leaves behind.
+    fn detach(
+        conn: &mut PgConnection,
+        series_id: i32,
+        post_id: i32,
+    ) -> Result<(), diesel::result::Error> {
+        use crate::db::schema::series_posts;
+
+        let removed: Option<i32> = diesel::delete(
+            series_posts::table.filter(
+                series_posts::series_id
+                    .eq(series_id)
+                    .and(series_posts::post_id.eq(post_id)),
+            ),
+        )
+        .returning(series_posts::position)
+        .get_result(conn)
+        .optional()?;
+
+        if let Some(removed) = removed {
+            diesel::update(
+                series_posts::table.filter(
+                    series_posts::series_id
+                        .eq(series_id)
+                        .and(series_posts::position.gt(removed)),
+                ),
+            )
+            .set(series_posts::position.eq(series_posts::position - 1))
+            .execute(conn)?;
+        }
+
+        Ok(())
+    }
+
+    fn touch(conn: &mut PgConnection, series_id: i32) -> Result<Self, diesel::result::Error> {
+        use crate::db::schema::series::dsl::*;
+
+        diesel::update(series.filter(id.eq(series_id)))
+            .set(updated_at.eq(Utc::now().naive_utc()))
+            .returning(Self::as_returning())
+            .get_result(conn)
+    }
+
+    fn load_parts(
+        conn: &mut PgConnection,
+        series_id: i32,
+        live_only: bool,
+    ) -> Result<Vec<SeriesPart>, diesel::result::Error> {
+        use crate::db::schema::{posts, series_posts};
+
+        let now = Utc::now().naive_utc();
+
+        let mut query_builder = series_posts::table
+            .inner_join(posts::table)
+            .filter(series_posts::series_id.eq(series_id))
+            .select((
+                posts::id,
+                posts::title,
+                posts::slug,
+                series_posts::position,
+                is_live(now),
+            ))
+            .into_boxed();
+
+        if live_only {
+            query_builder = query_builder.filter(is_live(now));
+        }
+
+        let parts: Vec<(i32, String, String, i32, Option<bool>)> = query_builder
+            .order(series_posts::position.asc())
+            .load(conn)?;
+
+        Ok(parts
+            .into_iter()
+            .map(
+                |(post_id, title, slug, position, is_published)| SeriesPart {
+                    post_id,
+                    title,
+                    slug,
+                    position,
+                    is_published: is_published.unwrap_or(false),
+                },
+            )
+            .collect())
+    }
+
+    fn with_posts(
+        conn: &mut PgConnection,
+        series: Series,
+        live_only: bool,
+    ) -> Result<SeriesWithPosts, diesel::result::Error> {
+        let mut posts = Self::load_parts(conn, series.id, live_only)?;
+
+        if live_only {
+            for (index, part) in posts.iter_mut().enumerate() {
+                part.position = index as i32 + 1;
+            }
+        }
+
+        Ok(SeriesWithPosts { series, posts })
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{NaiveDateTime, Utc};
+use deadpool_diesel::postgres::Pool;
+use diesel::{dsl, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::post::is_live;
+use crate::db::{errors::DBError, utils::execute_db_operation};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SitemapSection {
+    Posts,
+    Categories,
+    Tags,
+    Authors,
+}
+
+impl SitemapSection {
+    pub const ALL: [SitemapSection; 4] = [
+        SitemapSection::Posts,
+        SitemapSection::Categories,
+        SitemapSection::Tags,
+        SitemapSection::Authors,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SitemapSection::Posts => "posts",
+            SitemapSection::Categories => "categories",
+            SitemapSection::Tags => "tags",
+            SitemapSection::Authors => "authors",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|section| section.name() == name)
+    }
+}
+
+/// One page of a section, as listed in the sitemap index.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SitemapPage {
+    pub section: SitemapSection,
+    pub page: i64,
+    pub lastmod: Option<NaiveDateTime>,
+}
+
+/// A page to list: the slug (or id, for authors) it is found at and when it last changed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SitemapEntry {
+    pub key: String,
+    pub lastmod: NaiveDateTime,
+}
+
+pub struct Sitemap;
+
+impl Sitemap {
+    /// Pages of every section that has entries, `per_page` entries each.
+    pub async fn pages(pool: &Pool, per_page: i64) -> Result<Vec<SitemapPage>, DBError> {
+        use crate::db::schema::{categories, posts, tags};
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            let (post_count, post_lastmod): (i64, Option<NaiveDateTime>) = posts::table
+                .filter(is_live(now))
+                .select((dsl::count_star(), dsl::max(posts::updated_at)))
+                .first(conn)?;
+            let (category_count, category_lastmod): (i64, Option<NaiveDateTime>) =
+                categories::table
+                    .filter(categories::deleted_at.is_null())
+                    .select((dsl::count_star(), dsl::max(categories::updated_at)))
+                    .first(conn)?;
+            let (tag_count, tag_lastmod): (i64, Option<NaiveDateTime>) = tags::table
+                .filter(tags::deleted_at.is_null())
+                .select((dsl::count_star(), dsl::max(tags::updated_at)))
+                .first(conn)?;
+            let author_count: i64 = posts::table
+                .filter(is_live(now))
+                .select(dsl::count_distinct(posts::author_id))
+                .first(conn)?;
+
+            let sections = [
+                (SitemapSection::Posts, post_count, post_lastmod),
+                (SitemapSection::Categories, category_count, category_lastmod),
+                (SitemapSection::Tags, tag_count, tag_lastmod),
+                (SitemapSection::Authors, author_count, post_lastmod),
+            ];
+
+            Ok(sections
+                .into_iter()
+                .flat_map(|(section, count, lastmod)| {
+                    let pages = (count + per_page - 1) / per_page;
+                    (1..=pages).map(move |page| SitemapPage {
+                        section,
+                        page,
+                        lastmod,
+                    })
+                })
+                .collect())
+        })
+        .await
+    }
+
+    /// One page of entries of a section, in id order so pages stay stable as content is added.
+    pub async fn entries(
+        pool: &Pool,
+        section: SitemapSection,
+        page: i64,
+        per_page: i64,
+    ) -> Result<Vec<SitemapEntry>, DBError> {
+        use crate::db::schema::{categories, posts, tags};
+
+        let now = Utc::now().naive_utc();
+        let offset = (page - 1) * per_page;
+
+        execute_db_operation(pool, move |conn| {
+            let rows: Vec<(String, NaiveDateTime)> = match section {
+                SitemapSection::Posts => posts::table
+                    .filter(is_live(now))
+                    .order(posts::id.asc())
+                    .select((posts::slug, posts::updated_at))
+                    .limit(per_page)
+                    .offset(offset)
+                    .load(conn)?,
+                SitemapSection::Categories => categories::table
+                    .filter(categories::deleted_at.is_null())
+                    .order(categories::id.asc())
+                    .select((categories::slug, categories::updated_at))
+                    .limit(per_page)
+                    .offset(offset)
+                    .load(conn)?,
+                SitemapSection::Tags => tags::table
+                    .filter(tags::deleted_at.is_null())
+                    .order(tags::id.asc())
+                    .select((tags::slug, tags::updated_at))
+                    .limit(per_page)
+                    .offset(offset)
+                    .load(conn)?,
+                SitemapSection::Authors => posts::table
+                    .filter(is_live(now))
+                    .group_by(posts::author_id)
+                    .order(posts::author_id.asc())
+                    .select((posts::author_id, dsl::max(posts::updated_at)))
+                    .limit(per_page)
+                    .offset(offset)
+                    .load::<(i32, Option<NaiveDateTime>)>(conn)?
+                    .into_iter()
+                    .filter_map(|(author_id, lastmod)| Some((author_id.to_string(), lastmod?)))
+                    .collect(),
+            };
+
+            Ok(rows
+                .into_iter()
+                .map(|(key, lastmod)| SitemapEntry { key, lastmod })
+                .collect())
+        })
+        .await
+    }
+}
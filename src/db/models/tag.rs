@@ -1,10 +1,13 @@
-use chrono::NaiveDateTime;
+use std::collections::HashSet;
+
+use chrono::{NaiveDateTime, Utc};
 use deadpool_diesel::postgres::Pool;
 use diesel::prelude::*;
 use diesel::QueryDsl;
 use serde::{Deserialize, Serialize};
 
 use crate::db::{errors::DBError, schema, utils::execute_db_operation};
+use crate::services::slug;
 
 #[derive(Queryable, Identifiable, Selectable, Debug, PartialEq, Serialize, Clone)]
 #[diesel(table_name = schema::tags)]
@@ -15,6 +18,10 @@ pub struct Tag {
     pub description: Option<String>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_by: Option<i32>,
 }
 
 #[derive(Insertable, Deserialize, Debug)]
@@ -44,14 +51,40 @@ pub struct TagQuery {
 impl Tag {
     const PER_PAGE: i64 = 20;
     pub async fn create(pool: &Pool, new_tag: NewTag) -> Result<Self, DBError> {
-        use crate::db::schema::tags::dsl::*;
-
         execute_db_operation(pool, move |conn| {
-            diesel::insert_into(tags).values(&new_tag).get_result(conn)
+            conn.transaction(|conn| Self::insert(conn, new_tag))
         })
         .await
     }
 
+    pub fn insert(
+        conn: &mut PgConnection,
+        mut new_tag: NewTag,
+    ) -> Result<Self, diesel::result::Error> {
+        use crate::db::schema::tags::dsl::*;
+
+        if new_tag.slug.is_empty() {
+            new_tag.slug = Self::unique_slug(conn, &new_tag.name)?;
+        }
+
+        diesel::insert_into(tags).values(&new_tag).get_result(conn)
+    }
+
+    /// A slug derived from `name` that no other tag uses.
+    fn unique_slug(conn: &mut PgConnection, name: &str) -> Result<String, diesel::result::Error> {
+        use crate::db::schema::tags;
+
+        let base = slug::slugify(name, "tag");
+        let taken: HashSet<String> = tags::table
+            .filter(tags::slug.like(format!("{}%", base)))
+            .select(tags::slug)
+            .load::<String>(conn)?
+            .into_iter()
+            .collect();
+
+        Ok(slug::unique(&base, &taken))
+    }
+
     pub async fn update(
         pool: &Pool,
         tag_id: i32,
@@ -60,7 +93,7 @@ impl Tag {
         use crate::db::schema::tags::dsl::*;
 
         execute_db_operation(pool, move |conn| {
-            diesel::update(tags.filter(id.eq(tag_id)))
+            diesel::update(tags.filter(id.eq(tag_id).and(deleted_at.is_null())))
                 .set(&update_tag)
                 .returning(Self::as_returning())
                 .get_result(conn)
@@ -69,11 +102,73 @@ impl Tag {
         .await
     }
 
-    pub async fn delete(pool: &Pool, tag_id: i32) -> Result<usize, DBError> {
+    /// Moves a tag to the trash. Posts keep it in their `tag_ids` until it is purged.
+    pub async fn delete(pool: &Pool, tag_id: i32, user_id: i32) -> Result<usize, DBError> {
+        use crate::db::schema::tags::dsl::*;
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            diesel::update(tags.filter(id.eq(tag_id).and(deleted_at.is_null())))
+                .set((deleted_at.eq(now), deleted_by.eq(user_id)))
+                .execute(conn)
+        })
+        .await
+    }
+
+    /// Tags in the trash, most recently deleted first.
+    pub async fn find_deleted(pool: &Pool) -> Result<Vec<Self>, DBError> {
+        use crate::db::schema::tags::dsl::*;
+
+        execute_db_operation(pool, move |conn| {
+            tags.filter(deleted_at.is_not_null())
+                .order((deleted_at.desc(), id.desc()))
+                .load::<Self>(conn)
+        })
+        .await
+    }
+
+    pub async fn restore(pool: &Pool, tag_id: i32) -> Result<Option<Self>, DBError> {
+        use crate::db::schema::tags::dsl::*;
+
+        let now = Utc::now().naive_utc();
+
+        execute_db_operation(pool, move |conn| {
+            diesel::update(tags.filter(id.eq(tag_id).and(deleted_at.is_not_null())))
+                .set((
+                    deleted_at.eq(None::<NaiveDateTime>),
+                    deleted_by.eq(None::<i32>),
+                    updated_at.eq(now),
+                ))
+                .returning(Self::as_returning())
+                .get_result(conn)
+                .optional()
+        })
+        .await
+    }
+
+    /// Permanently deletes the tags trashed before `cutoff` and drops them from posts.
+    pub async fn purge_deleted(pool: &Pool, cutoff: NaiveDateTime) -> Result<usize, DBError> {
         use crate::db::schema::tags::dsl::*;
+        use diesel::sql_types::Int4;
 
         execute_db_operation(pool, move |conn| {
-            diesel::delete(tags.filter(id.eq(tag_id))).execute(conn)
+            conn.transaction(|conn| {
+                let purged_ids: Vec<i32> = tags
+                    .filter(deleted_at.lt(cutoff))
+                    .select(id)
+                    .load(conn)?;
+
+                for tag_id in purged_ids.iter() {
+                    diesel::sql_query(
+                        "UPDATE posts SET tag_ids = array_remove(tag_ids, $1) WHERE $1 = ANY(tag_ids)",
+                    )
+                    .bind::<Int4, _>(tag_id)
+                    .execute(conn)?;
+                }
+
+                diesel::delete(tags.filter(id.eq_any(purged_ids))).execute(conn)
+            })
         })
         .await
     }
@@ -82,7 +177,10 @@ impl Tag {
         use crate::db::schema::tags::dsl::*;
 
         execute_db_operation(pool, move |conn| {
-            tags.filter(id.eq(tag_id)).first::<Self>(conn).optional()
+            tags.filter(id.eq(tag_id))
+                .filter(deleted_at.is_null())
+                .first::<Self>(conn)
+                .optional()
         })
         .await
     }
@@ -90,14 +188,17 @@ impl Tag {
     pub async fn find_all(pool: &Pool) -> Result<Vec<Self>, DBError> {
         use crate::db::schema::tags::dsl::*;
 
-        execute_db_operation(pool, move |conn| tags.load::<Self>(conn)).await
+        execute_db_operation(pool, move |conn| {
+            tags.filter(deleted_at.is_null()).load::<Self>(conn)
+        })
+        .await
     }
 
     pub async fn find_with_query(pool: &Pool, query: TagQuery) -> Result<Vec<Self>, DBError> {
         use crate::db::schema::tags::dsl::*;
 
         execute_db_operation(pool, move |conn| {
-            let mut query_builder = tags.into_boxed();
+            let mut query_builder = tags.filter(deleted_at.is_null()).into_boxed();
 
             if let Some(search_term) = query.search {
                 let search_pattern = format!("%{}%", search_term.to_lowercase());
@@ -3,15 +3,20 @@
 
 use crate::db::{
     errors::DBError,
-    models::{email_verification::EmailVerification, forgot_password::ForgotPassword},
+    models::{
+        email_verification::EmailVerification,
+        forgot_password::ForgotPassword,
+        media::Media,
+        pagination::{Cursor, KeysetPage, PageRequest},
+    },
     schema::{self},
-    utils::{combine_errors, execute_db_operation},
+    utils::{combine_errors, execute_db_operation, execute_db_transaction},
 };
 use axum::{http::StatusCode, Json};
 use chrono::NaiveDateTime;
 use deadpool_diesel::postgres::Pool;
 use diesel::{
-    associations::HasTable, dsl::count_star, prelude::*, query_builder::QueryFragment,
+    associations::HasTable, dsl::count_star, pg::Pg, prelude::*, query_builder::QueryFragment,
     sql_types::BigInt,
 };
 use serde::{Deserialize, Serialize};
@@ -114,6 +119,7 @@ pub struct User {
     pub role: UserRole,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub avatar_media_id: Option<i32>,
 }
 
 #[derive(Insertable, Deserialize, Debug)]
@@ -131,6 +137,8 @@ pub struct UpdateUser {
     pub name: Option<String>,
     pub email: Option<String>,
     pub updated_at: NaiveDateTime,
+    pub avatar: Option<Option<String>>,
+    pub avatar_media_id: Option<Option<i32>>,
 }
 
 #[derive(Deserialize, Debug, Insertable, AsChangeset)]
@@ -185,6 +193,8 @@ pub struct AdminUpdateUser {
 
 impl User {
     pub const ADMIN_PER_PAGE: i64 = 20;
+    /// Listing name in the cursors of `admin_list_by_cursor`.
+    pub const ADMIN_LISTING: &'static str = "admin_users";
     pub async fn find_by_id(pool: &Pool, user_id: i32) -> Result<Option<Self>, DBError> {
         use crate::db::schema::users::dsl::*;
 
@@ -249,14 +259,24 @@ impl User {
         .await
     }
 
-    pub async fn update(pool: &Pool, user_id: i32, payload: UpdateUser) -> Result<Self, DBError> {
+    pub async fn update(
+        pool: &Pool,
+        user_id: i32,
+        mut payload: UpdateUser,
+    ) -> Result<Self, DBError> {
         use crate::db::schema::users::dsl::*;
 
-        execute_db_operation(pool, move |conn| {
-            diesel::update(users.filter(id.eq(user_id)))
+        execute_db_transaction(pool, move |conn| {
+            if let Some(Some(media_id)) = payload.avatar_media_id {
+                let user: User = users.find(user_id).select(User::as_select()).first(conn)?;
+                Media::check_attachable(conn, media_id, Some(&user))?;
+            }
+            Media::sync_url(conn, payload.avatar_media_id, &mut payload.avatar)?;
+
+            Ok(diesel::update(users.filter(id.eq(user_id)))
                 .set(&payload)
                 .returning(User::as_returning())
-                .get_result(conn)
+                .get_result(conn)?)
         })
         .await
     }
@@ -425,26 +445,7 @@ impl User {
         use diesel::dsl::sql;
 
         execute_db_operation(pool, move |conn| {
-            let mut query_builder = users.into_boxed();
-
-            if let Some(email_filter) = query.email {
-                query_builder = query_builder.filter(email.ilike(format!("%{}%", email_filter)));
-            }
-            if let Some(name_filter) = query.name {
-                query_builder = query_builder.filter(name.ilike(format!("%{}%", name_filter)));
-            }
-            if let Some(role_filter) = query.role {
-                query_builder = query_builder.filter(role.eq(role_filter));
-            }
-            if let Some(status_filter) = query.status {
-                query_builder = query_builder.filter(is_verified.eq(status_filter));
-            }
-            if let Some(created_at_filter) = query.created_at {
-                query_builder = query_builder.filter(created_at.eq(created_at_filter));
-            }
-            if let Some(updated_at_filter) = query.updated_at {
-                query_builder = query_builder.filter(updated_at.eq(updated_at_filter));
-            }
+            let mut query_builder = Self::admin_filtered(&query);
 
             if let Some(sort_by_fields) = query.sort_by {
                 for field in sort_by_fields {
@@ -477,4 +478,85 @@ impl User {
         })
         .await
     }
+
+    /// The users matching the same filters as `admin_list`, by cursor over `(created_at, id)`,
+    /// newest first unless `sort_order` is `asc`. `page_no` and `sort_by` don't apply.
+    pub async fn admin_list_by_cursor(
+        pool: &Pool,
+        query: AdminUserQuery,
+        cursor: Option<Cursor<NaiveDateTime>>,
+    ) -> Result<KeysetPage<Self>, DBError> {
+        use crate::db::schema::users::dsl::*;
+
+        let ascending = query.sort_order.as_deref() == Some("asc");
+
+        execute_db_operation(pool, move |conn| {
+            let mut query_builder = Self::admin_filtered(&query);
+            let backward = cursor.as_ref().is_some_and(|cursor| cursor.backward);
+            // Going back loads the rows before the cursor in reverse
+            let descending = ascending == backward;
+
+            if let Some(cursor) = &cursor {
+                query_builder = if descending {
+                    query_builder.filter(
+                        created_at
+                            .lt(cursor.key)
+                            .or(created_at.eq(cursor.key).and(id.lt(cursor.id))),
+                    )
+                } else {
+                    query_builder.filter(
+                        created_at
+                            .gt(cursor.key)
+                            .or(created_at.eq(cursor.key).and(id.gt(cursor.id))),
+                    )
+                };
+            }
+            query_builder = if descending {
+                query_builder.order((created_at.desc(), id.desc()))
+            } else {
+                query_builder.order((created_at.asc(), id.asc()))
+            };
+
+            let items = query_builder
+                .select(users::all_columns())
+                .limit(Self::ADMIN_PER_PAGE + 1)
+                .load::<User>(conn)?;
+
+            Ok(KeysetPage::new(
+                items,
+                Self::ADMIN_PER_PAGE,
+                &PageRequest::Cursor(cursor),
+                Self::ADMIN_LISTING,
+                |user| (user.created_at, user.id),
+            ))
+        })
+        .await
+    }
+
+    fn admin_filtered(query: &AdminUserQuery) -> schema::users::BoxedQuery<'static, Pg> {
+        use crate::db::schema::users::dsl::*;
+
+        let mut query_builder = users.into_boxed();
+
+        if let Some(email_filter) = &query.email {
+            query_builder = query_builder.filter(email.ilike(format!("%{}%", email_filter)));
+        }
+        if let Some(name_filter) = &query.name {
+            query_builder = query_builder.filter(name.ilike(format!("%{}%", name_filter)));
+        }
+        if let Some(role_filter) = query.role {
+            query_builder = query_builder.filter(role.eq(role_filter));
+        }
+        if let Some(status_filter) = query.status {
+            query_builder = query_builder.filter(is_verified.eq(status_filter));
+        }
+        if let Some(created_at_filter) = query.created_at {
+            query_builder = query_builder.filter(created_at.eq(created_at_filter));
+        }
+        if let Some(updated_at_filter) = query.updated_at {
+            query_builder = query_builder.filter(updated_at.eq(updated_at_filter));
+        }
+
+        query_builder
+    }
 }
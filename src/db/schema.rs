@@ -1,11 +1,51 @@
 // @generated automatically by Diesel CLI.
 
 pub mod sql_types {
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "content_format"))]
+    pub struct ContentFormat;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "contributor_role"))]
+    pub struct ContributorRole;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "device_class"))]
+    pub struct DeviceClass;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "post_status"))]
+    pub struct PostStatus;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "reaction_kind"))]
+    pub struct ReactionKind;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "user_role"))]
     pub struct UserRole;
 }
 
+diesel::table! {
+    bookmark_collections (id) {
+        id -> Int4,
+        user_id -> Int4,
+        name -> Varchar,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    bookmarks (collection_id, post_id) {
+        collection_id -> Int4,
+        post_id -> Int4,
+        position -> Int4,
+        read_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     categories (id) {
         id -> Int4,
@@ -17,6 +57,11 @@ diesel::table! {
         logo_image -> Nullable<Varchar>,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamptz>,
+        deleted_by -> Nullable<Int4>,
+        version -> Int4,
+        cover_media_id -> Nullable<Int4>,
+        logo_media_id -> Nullable<Int4>,
     }
 }
 
@@ -40,15 +85,141 @@ diesel::table! {
     }
 }
 diesel::table! {
+    import_records (id) {
+        id -> Int4,
+        source -> Varchar,
+        external_id -> Varchar,
+        post_id -> Nullable<Int4>,
+        comment_id -> Nullable<Int4>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    media (id) {
+        id -> Int4,
+        owner_id -> Nullable<Int4>,
+        storage_key -> Varchar,
+        url -> Varchar,
+        original_name -> Nullable<Varchar>,
+        content_type -> Varchar,
+        byte_size -> Int4,
+        width -> Int4,
+        height -> Int4,
+        alt_text -> Nullable<Varchar>,
+        variants -> Jsonb,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::DeviceClass;
+
     post_views (id) {
         id -> Int4,
         user_id -> Nullable<Int4>,
         post_id -> Int4,
+        created_at -> Timestamptz,
+        visitor_hash -> Nullable<Varchar>,
+        referrer_domain -> Nullable<Varchar>,
+        utm_source -> Nullable<Varchar>,
+        utm_medium -> Nullable<Varchar>,
+        utm_campaign -> Nullable<Varchar>,
+        device -> Nullable<DeviceClass>,
+    }
+}
+
+diesel::table! {
+    post_view_batches (id) {
+        id -> Varchar,
+        stored_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    post_view_daily (post_id, day) {
+        post_id -> Int4,
+        day -> Date,
+        views -> Int4,
+        unique_visitors -> Int4,
+        desktop_views -> Int4,
+        mobile_views -> Int4,
+        tablet_views -> Int4,
+    }
+}
+
+diesel::table! {
+    post_referrer_daily (post_id, day, referrer_domain, utm_source, utm_medium, utm_campaign) {
+        post_id -> Int4,
+        day -> Date,
+        referrer_domain -> Varchar,
+        utm_source -> Varchar,
+        utm_medium -> Varchar,
+        utm_campaign -> Varchar,
+        views -> Int4,
+    }
+}
+
+diesel::table! {
+    post_notes (id) {
+        id -> Int4,
+        post_id -> Int4,
+        parent_id -> Nullable<Int4>,
+        author_id -> Nullable<Int4>,
+        post_version -> Nullable<Int4>,
+        anchor_start -> Nullable<Int4>,
+        anchor_end -> Nullable<Int4>,
+        quote -> Nullable<Text>,
+        body -> Text,
+        resolved_at -> Nullable<Timestamp>,
+        resolved_by -> Nullable<Int4>,
         created_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
 diesel::table! {
+    post_preview_tokens (id) {
+        id -> Int4,
+        post_id -> Int4,
+        created_by -> Nullable<Int4>,
+        label -> Nullable<Varchar>,
+        expires_at -> Timestamp,
+        revoked_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    post_slug_history (slug) {
+        slug -> Varchar,
+        post_id -> Int4,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::PostStatus;
+
+    post_status_transitions (id) {
+        id -> Int4,
+        post_id -> Int4,
+        from_status -> Nullable<PostStatus>,
+        to_status -> PostStatus,
+        actor_id -> Nullable<Int4>,
+        comment -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ContentFormat;
+    use super::sql_types::PostStatus;
+
     posts (id) {
         id -> Int4,
         title -> Varchar,
@@ -57,7 +228,6 @@ diesel::table! {
         created_at -> Timestamp,
         updated_at -> Timestamp,
         published_at -> Nullable<Timestamptz>,
-        is_published -> Bool,
         slug -> Varchar,
         excerpt -> Nullable<Text>,
         featured_image_url -> Nullable<Varchar>,
@@ -65,6 +235,30 @@ diesel::table! {
         view_count -> Int4,
         likes_count -> Int4,
         tag_ids -> Array<Int4>,
+        content_format -> ContentFormat,
+        rendered_html -> Nullable<Text>,
+        word_count -> Int4,
+        reading_time -> Int4,
+        toc -> Jsonb,
+        excerpt_generated -> Bool,
+        search_language -> Varchar,
+        deleted_at -> Nullable<Timestamptz>,
+        deleted_by -> Nullable<Int4>,
+        version -> Int4,
+        status -> PostStatus,
+        featured_media_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ContributorRole;
+
+    post_contributors (post_id, user_id) {
+        post_id -> Int4,
+        user_id -> Int4,
+        role -> ContributorRole,
+        created_at -> Timestamp,
     }
 }
 
@@ -75,11 +269,62 @@ diesel::table! {
         user_id -> Int4,
         content -> Text,
         likes_count -> Int4,
+        created_at -> Timestamptz,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamptz>,
+        deleted_by -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    post_comment_likes (comment_id, user_id) {
+        comment_id -> Int4,
+        user_id -> Int4,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    post_likes (post_id, user_id) {
+        post_id -> Int4,
+        user_id -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ReactionKind;
+
+    post_reactions (post_id, user_id, kind) {
+        post_id -> Int4,
+        user_id -> Int4,
+        kind -> ReactionKind,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    series (id) {
+        id -> Int4,
+        title -> Varchar,
+        slug -> Varchar,
+        description -> Nullable<Text>,
+        cover_image -> Nullable<Varchar>,
+        author_id -> Int4,
         created_at -> Timestamp,
         updated_at -> Timestamp,
     }
 }
 
+diesel::table! {
+    series_posts (series_id, post_id) {
+        series_id -> Int4,
+        post_id -> Int4,
+        position -> Int4,
+    }
+}
+
 diesel::table! {
     tags (id) {
         id -> Int4,
@@ -88,6 +333,8 @@ diesel::table! {
         description -> Nullable<Text>,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        deleted_by -> Nullable<Int4>,
 
     }
 }
@@ -106,25 +353,70 @@ diesel::table! {
         role -> UserRole,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        avatar_media_id -> Nullable<Int4>,
     }
 }
 
+diesel::joinable!(bookmark_collections -> users (user_id));
+diesel::joinable!(bookmarks -> bookmark_collections (collection_id));
+diesel::joinable!(bookmarks -> posts (post_id));
 diesel::joinable!(email_verifications -> users (user_id));
 diesel::joinable!(forgot_password -> users (user_id));
+diesel::joinable!(import_records -> post_comments (comment_id));
+diesel::joinable!(import_records -> posts (post_id));
+diesel::joinable!(media -> users (owner_id));
 diesel::joinable!(posts -> users (author_id));
 diesel::joinable!(post_comments -> posts (post_id));
 diesel::joinable!(post_comments -> users (user_id));
+diesel::joinable!(post_comment_likes -> post_comments (comment_id));
+diesel::joinable!(post_comment_likes -> users (user_id));
+diesel::joinable!(post_contributors -> posts (post_id));
+diesel::joinable!(post_contributors -> users (user_id));
 diesel::joinable!(posts -> categories (category_id));
+diesel::joinable!(posts -> media (featured_media_id));
+diesel::joinable!(post_likes -> posts (post_id));
+diesel::joinable!(post_likes -> users (user_id));
+diesel::joinable!(post_notes -> posts (post_id));
+diesel::joinable!(post_notes -> users (author_id));
+diesel::joinable!(post_preview_tokens -> posts (post_id));
+diesel::joinable!(post_preview_tokens -> users (created_by));
+diesel::joinable!(post_reactions -> posts (post_id));
+diesel::joinable!(post_reactions -> users (user_id));
+diesel::joinable!(post_referrer_daily -> posts (post_id));
+diesel::joinable!(post_slug_history -> posts (post_id));
+diesel::joinable!(post_status_transitions -> posts (post_id));
+diesel::joinable!(post_status_transitions -> users (actor_id));
 diesel::joinable!(post_views -> posts (post_id));
 diesel::joinable!(post_views -> users (user_id));
+diesel::joinable!(post_view_daily -> posts (post_id));
+diesel::joinable!(series -> users (author_id));
+diesel::joinable!(series_posts -> posts (post_id));
+diesel::joinable!(series_posts -> series (series_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    bookmark_collections,
+    bookmarks,
     categories,
     email_verifications,
     forgot_password,
     users,
     posts,
+    import_records,
+    media,
     post_comments,
+    post_comment_likes,
+    post_contributors,
+    post_likes,
+    post_notes,
+    post_preview_tokens,
+    post_reactions,
+    post_referrer_daily,
+    post_slug_history,
+    post_status_transitions,
     tags,
     post_views,
+    post_view_batches,
+    post_view_daily,
+    series,
+    series_posts,
 );
@@ -1,6 +1,6 @@
 use super::errors::DBError;
 use deadpool_diesel::{postgres::Pool, InteractError};
-use diesel::PgConnection;
+use diesel::{Connection, PgConnection};
 
 pub fn combine_errors<S>(
     nested_result: Result<Result<S, diesel::result::Error>, InteractError>,
@@ -19,3 +19,22 @@ where
     let result = conn.interact(operation).await;
     combine_errors(result)
 }
+
+/// Like [`execute_db_operation`], but runs `operation` in a transaction that may also fail
+/// with a [`DBError`] of its own, rolling back whatever it wrote.
+pub async fn execute_db_transaction<F, T>(pool: &Pool, operation: F) -> Result<T, DBError>
+where
+    F: FnOnce(&mut PgConnection) -> Result<T, DBError> + Send + 'static,
+    T: Send + 'static,
+{
+    let conn = pool.get().await?;
+    conn.interact(|conn| conn.transaction(operation)).await?
+}
+
+/// Outcome of an edit guarded by the version the client last saw.
+#[derive(Debug)]
+pub enum VersionedUpdate<T> {
+    Updated(T),
+    /// Someone else changed the row first; holds it as it is now.
+    Conflict(T),
+}
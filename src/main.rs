@@ -1,3 +1,4 @@
+mod cli;
 pub mod db;
 pub mod middlewares;
 pub mod modules;
@@ -18,6 +19,7 @@ use tower_http::{
     compression::CompressionLayer,
     cors::{AllowOrigin, CorsLayer},
     limit::RequestBodyLimitLayer,
+    services::ServeDir,
 };
 
 // use axum_csrf::{CsrfConfig, CsrfLayer, Key as CsrfKey};
@@ -77,6 +79,10 @@ fn get_allowed_origins() -> Vec<HeaderValue> {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv::dotenv().ok();
+    if let Some(command) = cli::Command::from_args(env::args().skip(1))? {
+        return command.run().await;
+    }
+
     tracing_subscriber::fmt()
         .with_max_level(tracing::Level::DEBUG)
         .with_thread_ids(true)
@@ -84,6 +90,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .init();
 
     let cookie_key_str = env::var("COOKIE_KEY").expect("COOKIE_KEY must be set");
+    services::preview::secret().expect("PREVIEW_TOKEN_SECRET or COOKIE_KEY must be set");
     // let csrf_key_str = env::var("CSRF_KEY").expect("CSRF_KEY must be set");
 
     tracing::info!("Starting server.");
@@ -92,13 +99,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let backend = AuthBackend::new(&pool);
     let (redis_pool, redis_connection) = init_redis_store().await?;
     let mailer = services::mail::smtp::create_connection().await;
+    let media_storage = services::media::storage::from_env()?;
     let state = AppState {
         db_pool: pool,
         redis_pool: redis_pool.clone(),
         mailer,
+        media_storage,
     };
 
     run_migrations(&state.db_pool).await?;
+    services::scheduler::start(state.clone());
 
     tracing::info!("Redis successfully established.");
     let session_store = RedisStore::new(redis_pool);
@@ -178,21 +188,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let auth_layer = AuthManagerLayerBuilder::new(backend, session_layer).build();
 
-    let app = router::router()
+    let mut app = router::router()
+        .layer(request_size)
+        .merge(router::upload_router())
         .layer(SecureClientIpSource::ConnectInfo.into_extension())
         .layer(auth_layer)
         // .layer(GovernorLayer {
         //     config: governor_conf,
         // })
         .layer(compression)
-        .layer(request_size)
         .layer(middleware::from_fn(middlewares::static_csrf::csrf_gaurd))
         .route(
             "/csrf/v1/generate",
             routing::post(csrf_v1::controller::generate),
-        )
-        .layer(cors)
-        .with_state(state);
+        );
+    // Uploaded files are loaded by plain browser requests, outside the session and CSRF layers
+    if let Some(root) = state.media_storage.local_root() {
+        app = app.nest_service(services::media::local::PUBLIC_PATH, ServeDir::new(root));
+    }
+
+    let app = app.layer(cors).with_state(state);
 
     let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
     let port = env::var("PORT").unwrap_or_else(|_| "8888".to_string());
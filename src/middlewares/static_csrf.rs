@@ -9,13 +9,23 @@ use axum::{
 };
 use serde_json::json;
 
+/// Paths fetched by feed readers and crawlers, which can't send the CSRF header.
+const PUBLIC_PATH_PREFIXES: [&str; 4] = ["/feed/v1/", "/sitemap.xml", "/sitemaps/", "/robots.txt"];
+
 pub fn get_static_csrf_key() -> String {
-    let key = env::var("CSRF_KEY").unwrap_or_else(|_| "ultra-instinct-goku".to_string());
-    return key;
+    env::var("CSRF_KEY").unwrap_or_else(|_| "ultra-instinct-goku".to_string())
 }
 
 // pub async fn csrf_gaurd(req: Request, next: Next) -> Result<Response, impl IntoResponse> {
 pub async fn csrf_gaurd(req: Request, next: Next) -> Result<Response, Response> {
+    let path = req.uri().path();
+    if PUBLIC_PATH_PREFIXES
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+    {
+        return Ok(next.run(req).await);
+    }
+
     let err_json = Json(
         json!({"error": "invalid request", "message": "requset is from a un verified client" }),
     );
@@ -37,9 +47,7 @@ pub async fn csrf_gaurd(req: Request, next: Next) -> Result<Response, Response>
 
                     Ok(next.run(req).await)
                 }
-                Err(_) => {
-                    return Err((StatusCode::BAD_REQUEST, err_json).into_response());
-                }
+                Err(_) => Err((StatusCode::BAD_REQUEST, err_json).into_response()),
             }
         } else {
             Err((StatusCode::BAD_REQUEST, err_json).into_response())
@@ -6,12 +6,14 @@ use axum::{
     Json,
 };
 use serde_json::json;
+use std::env;
 
 use crate::{
     db::models::user::{User, UserRole},
     services::auth::AuthSession,
 };
 
+#[allow(clippy::result_large_err)]
 fn check_user_role(user: Option<User>, req_role: UserRole) -> Result<bool, Response> {
     let user = user.ok_or_else(|| {
         (
@@ -32,6 +34,20 @@ fn check_user_role(user: Option<User>, req_role: UserRole) -> Result<bool, Respo
     }
 }
 
+/// Lowest role allowed to publish or schedule a post that hasn't been approved
+/// (`DIRECT_PUBLISH_ROLE`, defaults to `moderator`).
+fn direct_publish_role() -> UserRole {
+    env::var("DIRECT_PUBLISH_ROLE")
+        .ok()
+        .and_then(|role| role.parse().ok())
+        .unwrap_or(UserRole::Moderator)
+}
+
+/// Whether the user may skip review and publish posts they can edit right away.
+pub fn can_publish_directly(user: &User) -> bool {
+    user.role.to_i32() >= direct_publish_role().to_i32()
+}
+
 pub async fn user(auth: AuthSession, request: Request, next: Next) -> Result<Response, Response> {
     check_user_role(auth.user, UserRole::User)?;
     Ok(next.run(request).await)
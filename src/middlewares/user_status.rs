@@ -48,7 +48,7 @@ pub async fn only_unauthenticated(
     request: Request,
     next: Next,
 ) -> Result<Response, Response> {
-    if !auth.user.is_none() {
+    if auth.user.is_some() {
         return Ok((
             StatusCode::BAD_REQUEST,
             Json(json!({"message": "Resource not available"})),
@@ -0,0 +1,329 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_macros::debug_handler;
+use axum_valid::Valid;
+use serde_json::json;
+
+use crate::{
+    db::models::{
+        bookmark::{Bookmark, BookmarkCollection},
+        post::Post,
+    },
+    services::auth::AuthSession,
+    AppState,
+};
+
+use super::validator::{
+    V1AddBookmarkPayload, V1BookmarkQueryParams, V1CreateBookmarkCollectionPayload,
+    V1MarkBookmarkReadPayload, V1RemoveBookmarkPayload, V1ReorderBookmarksPayload,
+    V1UpdateBookmarkCollectionPayload,
+};
+
+#[debug_handler]
+pub async fn create_collection(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    payload: Valid<Json<V1CreateBookmarkCollectionPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let new_collection = payload.into_inner().0.into_new_bookmark_collection(user.id);
+
+    match BookmarkCollection::create(&state.db_pool, new_collection).await {
+        Ok(collection) => (StatusCode::CREATED, Json(json!(collection))).into_response(),
+        Err(err) if err.is_unique_violation() => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "A collection with this name already exists",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to create collection",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn update_collection(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(collection_id): Path<i32>,
+    payload: Valid<Json<V1UpdateBookmarkCollectionPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let update_collection = payload.into_inner().0.into_update_bookmark_collection();
+
+    match BookmarkCollection::update(&state.db_pool, collection_id, user, update_collection).await {
+        Ok(Some(collection)) => (StatusCode::OK, Json(json!(collection))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Collection does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) if err.is_unique_violation() => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "A collection with this name already exists",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to update collection",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn delete_collection(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(collection_id): Path<i32>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+
+    match BookmarkCollection::delete(&state.db_pool, user, collection_id).await {
+        Ok(1) => (
+            StatusCode::OK,
+            Json(json!({ "message": "Collection deleted successfully" })),
+        )
+            .into_response(),
+        Ok(0) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Collection does not exist",
+            })),
+        )
+            .into_response(),
+        Ok(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "unexpected result",
+                "message": "Internal server error occurred while deleting collection",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to delete collection",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn list_collections(
+    State(state): State<AppState>,
+    auth: AuthSession,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+
+    match BookmarkCollection::list(&state.db_pool, user).await {
+        Ok(collections) => (StatusCode::OK, Json(json!(collections))).into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch collections",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn add(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    payload: Valid<Json<V1AddBookmarkPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let payload = payload.into_inner().0;
+
+    match Bookmark::add(&state.db_pool, user, payload.collection_id, payload.post_id).await {
+        Ok(Some(bookmark)) => (StatusCode::OK, Json(json!(bookmark))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Collection or post does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to bookmark post",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn remove(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(collection_id): Path<i32>,
+    payload: Valid<Json<V1RemoveBookmarkPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let post_id = payload.into_inner().0.post_id;
+
+    match Bookmark::remove(&state.db_pool, user, collection_id, post_id).await {
+        Ok(1) => (
+            StatusCode::OK,
+            Json(json!({ "message": "Bookmark removed successfully" })),
+        )
+            .into_response(),
+        Ok(0) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Bookmark does not exist",
+            })),
+        )
+            .into_response(),
+        Ok(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "unexpected result",
+                "message": "Internal server error occurred while removing bookmark",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to remove bookmark",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn mark_read(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(collection_id): Path<i32>,
+    payload: Valid<Json<V1MarkBookmarkReadPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let payload = payload.into_inner().0;
+
+    match Bookmark::mark_read(
+        &state.db_pool,
+        user,
+        collection_id,
+        payload.post_id,
+        payload.is_read,
+    )
+    .await
+    {
+        Ok(Some(bookmark)) => (StatusCode::OK, Json(json!(bookmark))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Bookmark does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to update bookmark",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn reorder(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(collection_id): Path<i32>,
+    payload: Valid<Json<V1ReorderBookmarksPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let post_ids = payload.into_inner().0.post_ids;
+
+    match Bookmark::reorder(&state.db_pool, user, collection_id, post_ids).await {
+        Ok(Some(bookmarks)) => (StatusCode::OK, Json(json!(bookmarks))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Collection does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to reorder bookmarks",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn list(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    query: Valid<Json<V1BookmarkQueryParams>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let bookmark_query = query.into_inner().0.into_bookmark_query();
+    let page = bookmark_query.page_no.unwrap_or(1);
+
+    match Bookmark::list(&state.db_pool, user, bookmark_query).await {
+        Ok((bookmarks, total)) => (
+            StatusCode::OK,
+            Json(json!({
+                "data": bookmarks,
+                "total": total,
+                "per_page": Post::PER_PAGE,
+                "page": page,
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch bookmarks",
+            })),
+        )
+            .into_response(),
+    }
+}
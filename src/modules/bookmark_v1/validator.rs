@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::db::models::bookmark::{BookmarkQuery, NewBookmarkCollection, UpdateBookmarkCollection};
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1CreateBookmarkCollectionPayload {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+}
+
+impl V1CreateBookmarkCollectionPayload {
+    pub fn into_new_bookmark_collection(self, user_id: i32) -> NewBookmarkCollection {
+        NewBookmarkCollection {
+            user_id,
+            name: self.name,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1UpdateBookmarkCollectionPayload {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+}
+
+impl V1UpdateBookmarkCollectionPayload {
+    pub fn into_update_bookmark_collection(self) -> UpdateBookmarkCollection {
+        UpdateBookmarkCollection {
+            name: self.name,
+            updated_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1AddBookmarkPayload {
+    pub post_id: i32,
+    /// Defaults to the user's reading list.
+    pub collection_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1RemoveBookmarkPayload {
+    pub post_id: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1MarkBookmarkReadPayload {
+    pub post_id: i32,
+    #[serde(default = "default_is_read")]
+    pub is_read: bool,
+}
+
+fn default_is_read() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1ReorderBookmarksPayload {
+    #[validate(length(min = 1, max = 500))]
+    pub post_ids: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+pub struct V1BookmarkQueryParams {
+    #[validate(range(min = 1))]
+    pub page: Option<i64>,
+    pub collection_id: Option<i32>,
+    pub is_read: Option<bool>,
+}
+
+impl V1BookmarkQueryParams {
+    pub fn into_bookmark_query(self) -> BookmarkQuery {
+        BookmarkQuery {
+            page_no: self.page,
+            collection_id: self.collection_id,
+            is_read: self.is_read,
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
@@ -8,7 +8,15 @@ use axum_macros::debug_handler;
 use axum_valid::Valid;
 use serde_json::json;
 
-use crate::{db::models::category::Category, AppState};
+use crate::{
+    db::{errors::DBError, models::category::Category, utils::VersionedUpdate},
+    services::{
+        auth::AuthSession,
+        cache,
+        version::{self, ExpectedVersion},
+    },
+    AppState,
+};
 
 use super::validator::{V1CategoryQueryParams, V1CreateCategoryPayload, V1UpdateCategoryPayload};
 
@@ -21,6 +29,22 @@ pub async fn create(
 
     match Category::create(&state.db_pool, new_category).await {
         Ok(category) => (StatusCode::CREATED, Json(json!(category))).into_response(),
+        Err(err @ DBError::MediaNotFound(_)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": err.to_string(),
+            })),
+        )
+            .into_response(),
+        Err(err @ DBError::MediaNotOwned(_)) => (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "request failed",
+                "message": err.to_string(),
+            })),
+        )
+            .into_response(),
         Err(err) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({
@@ -38,7 +62,12 @@ pub async fn get_category_by_id(
     Path(category_id): Path<i32>,
 ) -> impl IntoResponse {
     match Category::get_category_by_id(&state.db_pool, category_id).await {
-        Ok(Some(category)) => (StatusCode::OK, Json(json!(category))).into_response(),
+        Ok(Some(category)) => (
+            StatusCode::OK,
+            [(header::ETAG, version::etag(category.version))],
+            Json(json!(category)),
+        )
+            .into_response(),
         Ok(None) => (
             StatusCode::NOT_FOUND,
             Json(json!({ "message": "Category not found" })),
@@ -79,12 +108,50 @@ pub async fn get_categories(
 pub async fn update(
     State(state): State<AppState>,
     Path(category_id): Path<i32>,
+    headers: HeaderMap,
     payload: Valid<Json<V1UpdateCategoryPayload>>,
 ) -> impl IntoResponse {
-    let update_category = payload.into_inner().0.into_update_category();
+    let payload = payload.into_inner().0;
+    let Some(expected) = ExpectedVersion::from_request(&headers, payload.version) else {
+        return (
+            StatusCode::PRECONDITION_REQUIRED,
+            Json(json!({
+                "error": "request failed",
+                "message": "Send the category version being edited in If-Match or version",
+            })),
+        )
+            .into_response();
+    };
+    let update_category = payload.into_update_category();
 
-    match Category::update(&state.db_pool, category_id, update_category).await {
-        Ok(Some(category)) => (StatusCode::OK, Json(json!(category))).into_response(),
+    match Category::update(
+        &state.db_pool,
+        category_id,
+        update_category,
+        expected.versions(),
+    )
+    .await
+    {
+        Ok(Some(VersionedUpdate::Updated(category))) => {
+            cache::invalidate_posts(&state.redis_pool).await;
+            (
+                StatusCode::OK,
+                [(header::ETAG, version::etag(category.version))],
+                Json(json!(category)),
+            )
+                .into_response()
+        }
+        Ok(Some(VersionedUpdate::Conflict(category))) => (
+            expected.conflict_status(),
+            [(header::ETAG, version::etag(category.version))],
+            Json(json!({
+                "error": "version conflict",
+                "message": "Category was changed by someone else since it was loaded",
+                "version": category.version,
+                "current": category,
+            })),
+        )
+            .into_response(),
         Ok(None) => (
             StatusCode::NOT_FOUND,
             Json(json!({
@@ -93,6 +160,22 @@ pub async fn update(
             })),
         )
             .into_response(),
+        Err(err @ DBError::MediaNotFound(_)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": err.to_string(),
+            })),
+        )
+            .into_response(),
+        Err(err @ DBError::MediaNotOwned(_)) => (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "request failed",
+                "message": err.to_string(),
+            })),
+        )
+            .into_response(),
         Err(err) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({
@@ -107,14 +190,19 @@ pub async fn update(
 #[debug_handler]
 pub async fn delete(
     State(state): State<AppState>,
+    auth: AuthSession,
     Path(category_id): Path<i32>,
 ) -> impl IntoResponse {
-    match Category::delete(&state.db_pool, category_id).await {
-        Ok(1) => (
-            StatusCode::OK,
-            Json(json!({ "message": "Category deleted successfully" })),
-        )
-            .into_response(),
+    let user = auth.user.unwrap();
+    match Category::delete(&state.db_pool, category_id, user.id).await {
+        Ok(1) => {
+            cache::invalidate_posts(&state.redis_pool).await;
+            (
+                StatusCode::OK,
+                Json(json!({ "message": "Category moved to trash" })),
+            )
+                .into_response()
+        }
         Ok(0) => (
             StatusCode::NOT_FOUND,
             Json(json!({
@@ -141,3 +229,47 @@ pub async fn delete(
             .into_response(),
     }
 }
+
+#[debug_handler]
+pub async fn trash(State(state): State<AppState>) -> impl IntoResponse {
+    match Category::find_deleted(&state.db_pool).await {
+        Ok(categories) => (StatusCode::OK, Json(json!(categories))).into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch deleted categories",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn restore(
+    State(state): State<AppState>,
+    Path(category_id): Path<i32>,
+) -> impl IntoResponse {
+    match Category::restore(&state.db_pool, category_id).await {
+        Ok(Some(category)) => {
+            cache::invalidate_posts(&state.redis_pool).await;
+            (StatusCode::OK, Json(json!(category))).into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Category is not in the trash",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to restore category",
+            })),
+        )
+            .into_response(),
+    }
+}
@@ -8,23 +8,29 @@ pub struct V1CreateCategoryPayload {
     #[validate(length(min = 1, max = 255))]
     pub name: String,
     #[validate(length(min = 1, max = 255))]
-    pub slug: String,
+    pub slug: Option<String>,
     pub parent_id: Option<i32>,
     #[validate(length(max = 1000))]
     pub description: Option<String>,
     pub cover_image: Option<String>,
     pub logo_image: Option<String>,
+    /// Library images; they replace `cover_image` and `logo_image`.
+    pub cover_media_id: Option<i32>,
+    pub logo_media_id: Option<i32>,
 }
 
 impl V1CreateCategoryPayload {
     pub fn into_new_category(self) -> NewCategory {
         NewCategory {
             name: self.name,
-            slug: self.slug,
+            // An empty slug is generated from the name on insert
+            slug: self.slug.unwrap_or_default(),
             parent_id: self.parent_id,
             description: self.description,
             cover_image: self.cover_image,
             logo_image: self.logo_image,
+            cover_media_id: self.cover_media_id,
+            logo_media_id: self.logo_media_id,
         }
     }
 }
@@ -40,6 +46,10 @@ pub struct V1UpdateCategoryPayload {
     pub description: Option<Option<String>>,
     pub cover_image: Option<Option<String>>,
     pub logo_image: Option<Option<String>>,
+    pub cover_media_id: Option<Option<i32>>,
+    pub logo_media_id: Option<Option<i32>>,
+    /// Version the edit is based on, unless it is sent in `If-Match`.
+    pub version: Option<i32>,
 }
 
 impl V1UpdateCategoryPayload {
@@ -52,6 +62,8 @@ impl V1UpdateCategoryPayload {
             cover_image: self.cover_image,
             logo_image: self.logo_image,
             updated_at: chrono::Utc::now().naive_utc(),
+            cover_media_id: self.cover_media_id,
+            logo_media_id: self.logo_media_id,
         }
     }
 }
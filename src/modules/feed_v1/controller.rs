@@ -0,0 +1,118 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_macros::debug_handler;
+use axum_valid::Valid;
+use chrono::DateTime;
+use serde_json::json;
+
+use crate::{
+    db::models::post::Post,
+    services::{
+        cache,
+        feed::{self, FeedFormat, RenderedFeed},
+    },
+    AppState,
+};
+
+use super::validator::V1FeedQueryParams;
+
+/// How long a rendered feed stays in Redis; post changes invalidate it sooner.
+const FEED_CACHE_TTL_SECS: i64 = 900;
+
+#[debug_handler]
+pub async fn feed(
+    State(state): State<AppState>,
+    Path(format): Path<FeedFormat>,
+    headers: HeaderMap,
+    Valid(query): Valid<Query<V1FeedQueryParams>>,
+) -> impl IntoResponse {
+    let scope = query.feed_scope();
+    // Built from the validated parameters only, so extra query parameters can't add cache entries
+    let self_path = format!("/feed/v1/{}?{}", format.name(), query.canonical_query());
+    let cache_key = self_path.as_str();
+
+    let rendered =
+        match cache::get_json::<RenderedFeed>(&state.redis_pool, cache::FEEDS, cache_key).await {
+            Some(rendered) => rendered,
+            None => match Post::find_feed(&state.db_pool, scope, feed::FEED_SIZE).await {
+                Ok(Some(post_feed)) => {
+                    let rendered = feed::render(format, query.content, &post_feed, &self_path);
+                    cache::set_json(
+                        &state.redis_pool,
+                        cache::FEEDS,
+                        cache_key,
+                        &rendered,
+                        FEED_CACHE_TTL_SECS,
+                    )
+                    .await;
+                    rendered
+                }
+                Ok(None) => {
+                    return (
+                        StatusCode::NOT_FOUND,
+                        Json(json!({
+                            "error": "request failed",
+                            "message": "Feed does not exist",
+                        })),
+                    )
+                        .into_response()
+                }
+                Err(err) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({
+                            "error": err.to_string(),
+                            "message": "Failed to fetch feed",
+                        })),
+                    )
+                        .into_response()
+                }
+            },
+        };
+
+    let last_modified = rendered
+        .last_modified
+        .and_utc()
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string();
+    let validators = [
+        (header::ETAG, rendered.etag.clone()),
+        (header::LAST_MODIFIED, last_modified),
+        (header::CACHE_CONTROL, "public, max-age=300".to_string()),
+    ];
+
+    if is_not_modified(&headers, &rendered) {
+        return (StatusCode::NOT_MODIFIED, validators).into_response();
+    }
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, format.content_type())],
+        validators,
+        rendered.body,
+    )
+        .into_response()
+}
+
+/// `If-None-Match` wins over `If-Modified-Since` when both are sent.
+fn is_not_modified(headers: &HeaderMap, rendered: &RenderedFeed) -> bool {
+    if let Some(if_none_match) = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+    {
+        return if_none_match.split(',').any(|tag| {
+            let tag = tag.trim();
+            tag == "*" || tag.trim_start_matches("W/") == rendered.etag
+        });
+    }
+
+    headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+        .is_some_and(|since| rendered.last_modified.and_utc().timestamp() <= since.timestamp())
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+use crate::{db::models::post::FeedScope, services::feed::FeedContent};
+
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+#[validate(schema(function = "validate_single_scope"))]
+pub struct V1FeedQueryParams {
+    /// Category slug.
+    #[validate(length(min = 1, max = 255))]
+    pub category: Option<String>,
+    /// Tag slug.
+    #[validate(length(min = 1, max = 255))]
+    pub tag: Option<String>,
+    /// Author id.
+    pub author: Option<i32>,
+    #[serde(default)]
+    pub content: FeedContent,
+}
+
+impl V1FeedQueryParams {
+    pub fn feed_scope(&self) -> FeedScope {
+        match (&self.category, &self.tag, self.author) {
+            (Some(category), _, _) => FeedScope::Category(category.clone()),
+            (_, Some(tag), _) => FeedScope::Tag(tag.clone()),
+            (_, _, Some(author)) => FeedScope::Author(author),
+            _ => FeedScope::All,
+        }
+    }
+
+    /// The parameters as a query string in a fixed order, without anything unknown.
+    pub fn canonical_query(&self) -> String {
+        serde_urlencoded::to_string(self).unwrap_or_default()
+    }
+}
+
+fn validate_single_scope(params: &V1FeedQueryParams) -> Result<(), ValidationError> {
+    let scopes = [
+        params.category.is_some(),
+        params.tag.is_some(),
+        params.author.is_some(),
+    ];
+
+    if scopes.into_iter().filter(|&scoped| scoped).count() > 1 {
+        Err(ValidationError::new("multiple_feed_scopes"))
+    } else {
+        Ok(())
+    }
+}
@@ -62,7 +62,7 @@ pub async fn generate(
     }
     let user_id = user.unwrap().unwrap().id;
 
-    match ForgotPassword::find_by_user_id(pool, user_id.clone()).await {
+    match ForgotPassword::find_by_user_id(pool, user_id).await {
         Ok(Some(verification)) => {
             if verification.is_in_delay() {
                 return (
@@ -95,26 +95,22 @@ pub async fn generate(
             let mailer = &state.mailer;
             let code = result.code;
 
-            match send_forgot_password_email(&mailer, "test@hello.xyz", &code).await {
-                Ok(()) => {
-                    return (
-                        StatusCode::OK,
-                        Json(json!({
-                            "message": "Verification code sent to your email successfully",
-                        })),
-                    )
-                        .into_response();
-                }
-                Err(err) => {
-                    return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(json!({
-                            "error": err.to_string(),
-                            "message": "Failed to send verification code",
-                        })),
-                    )
-                        .into_response();
-                }
+            match send_forgot_password_email(mailer, "test@hello.xyz", &code).await {
+                Ok(()) => (
+                    StatusCode::OK,
+                    Json(json!({
+                        "message": "Verification code sent to your email successfully",
+                    })),
+                )
+                    .into_response(),
+                Err(err) => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({
+                        "error": err.to_string(),
+                        "message": "Failed to send verification code",
+                    })),
+                )
+                    .into_response(),
             }
         }
         Err(err) => (
@@ -222,24 +218,20 @@ pub async fn reset(
     // SAFETY: `result` is checked to be `Some` above
     let user_id = result.unwrap().unwrap().0.id;
     match User::change_password(pool, user_id, payload.password.clone()).await {
-        Ok(_) => {
-            return (
-                StatusCode::OK,
-                Json(json!({
-                    "message": "Password reset successfully",
-                })),
-            )
-                .into_response();
-        }
-        Err(err) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "error": err.to_string(),
-                    "message": "Failed to reset password",
-                })),
-            )
-                .into_response();
-        }
+        Ok(_) => (
+            StatusCode::OK,
+            Json(json!({
+                "message": "Password reset successfully",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to reset password",
+            })),
+        )
+            .into_response(),
     }
 }
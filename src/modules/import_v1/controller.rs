@@ -0,0 +1,193 @@
+use axum::{
+    extract::{multipart::MultipartError, Multipart, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_macros::debug_handler;
+use axum_valid::Valid;
+use serde_json::json;
+
+use crate::{
+    db::models::import_record::ImportRecord,
+    services::{
+        auth::AuthSession,
+        cache,
+        import::{self, markdown, wxr, ImportBatch, ImportError},
+    },
+    AppState,
+};
+
+use super::validator::{V1ImportQueryParams, V1MarkdownImportQueryParams};
+
+/// Takes a multipart form with one or more Markdown files in `file` fields. The `site` and
+/// file names identify the posts on re-import, so upload the same files under the same names.
+#[debug_handler]
+pub async fn markdown(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Valid(Query(query)): Valid<Query<V1MarkdownImportQueryParams>>,
+    multipart: Multipart,
+) -> impl IntoResponse {
+    let files = match read_files(multipart).await {
+        Ok(files) => files,
+        Err(response) => return response,
+    };
+    let mut sources = Vec::with_capacity(files.len());
+    for (index, (name, bytes)) in files.into_iter().enumerate() {
+        let name = name.unwrap_or_else(|| format!("upload-{}.md", index + 1));
+        match String::from_utf8(bytes) {
+            Ok(raw) => sources.push((name, raw)),
+            Err(_) => return not_text(&name),
+        }
+    }
+
+    let site = query.site;
+    let batch = tokio::task::spawn_blocking(move || markdown::parse_files(&site, sources)).await;
+    run(&state, auth, query.apply, batch).await
+}
+
+/// Takes a multipart form with a WordPress export in `file`.
+#[debug_handler]
+pub async fn wxr(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Valid(Query(query)): Valid<Query<V1ImportQueryParams>>,
+    multipart: Multipart,
+) -> impl IntoResponse {
+    let files = match read_files(multipart).await {
+        Ok(files) => files,
+        Err(response) => return response,
+    };
+    let Some((name, bytes)) = files.into_iter().next() else {
+        return no_files();
+    };
+    let Ok(xml) = String::from_utf8(bytes) else {
+        return not_text(name.as_deref().unwrap_or("file"));
+    };
+
+    let batch = tokio::task::spawn_blocking(move || wxr::parse(&xml)).await;
+    run(&state, auth, query.apply, batch).await
+}
+
+async fn run(
+    state: &AppState,
+    auth: AuthSession,
+    apply: Option<bool>,
+    batch: Result<Result<ImportBatch, ImportError>, tokio::task::JoinError>,
+) -> Response {
+    let user = auth.user.unwrap();
+    let batch = match batch {
+        Ok(Ok(batch)) => batch,
+        Ok(Err(err)) => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({
+                    "error": err.to_string(),
+                    "message": "Failed to read import",
+                })),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": err.to_string(),
+                    "message": "Failed to read import",
+                })),
+            )
+                .into_response()
+        }
+    };
+    let dry_run = !apply.unwrap_or(false);
+
+    match ImportRecord::import(&state.db_pool, batch, user.id, dry_run).await {
+        Ok(report) => {
+            if !dry_run {
+                cache::invalidate_posts(&state.redis_pool).await;
+            }
+            (StatusCode::OK, Json(json!(report))).into_response()
+        }
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to import posts",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// The `file` fields of the form with their file names.
+async fn read_files(mut multipart: Multipart) -> Result<Vec<(Option<String>, Vec<u8>)>, Response> {
+    let mut files = vec![];
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(err) => return Err(multipart_error(err)),
+        };
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let name = field.file_name().map(str::to_string);
+        match field.bytes().await {
+            Ok(bytes) => files.push((name, bytes.to_vec())),
+            Err(err) => return Err(multipart_error(err)),
+        }
+    }
+
+    let total: usize = files.iter().map(|(_, bytes)| bytes.len()).sum();
+    if total > import::max_upload_bytes() {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(json!({
+                "error": "request failed",
+                "message": format!("Files are larger than {} bytes", import::max_upload_bytes()),
+            })),
+        )
+            .into_response());
+    }
+    if files.is_empty() {
+        return Err(no_files());
+    }
+
+    Ok(files)
+}
+
+fn no_files() -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": "request failed",
+            "message": "No file was uploaded",
+        })),
+    )
+        .into_response()
+}
+
+fn not_text(name: &str) -> Response {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(json!({
+            "error": "request failed",
+            "message": format!("{} is not UTF-8 text", name),
+        })),
+    )
+        .into_response()
+}
+
+fn multipart_error(err: MultipartError) -> Response {
+    (
+        err.status(),
+        Json(json!({
+            "error": err.body_text(),
+            "message": "Failed to read upload",
+        })),
+    )
+        .into_response()
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1ImportQueryParams {
+    /// Save the import. Without it the import is a dry run that only reports.
+    pub apply: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1MarkdownImportQueryParams {
+    /// Name of the site or repository the files come from. Imports are matched per site, so
+    /// use the same name when importing it again.
+    #[validate(length(min = 1, max = 200))]
+    pub site: String,
+    /// Save the import. Without it the import is a dry run that only reports.
+    pub apply: Option<bool>,
+}
@@ -0,0 +1,270 @@
+use axum::{
+    extract::{multipart::MultipartError, Multipart, Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_macros::debug_handler;
+use axum_valid::Valid;
+use serde_json::json;
+use validator::Validate;
+
+use crate::{
+    db::models::media::{Media, MediaDeletion},
+    services::{
+        auth::AuthSession,
+        media::{self, MediaError},
+    },
+    AppState,
+};
+
+use super::validator::{V1MediaListQueryParams, V1UpdateMediaPayload, V1UploadMediaFields};
+
+/// Takes a multipart form with the image in `file` and an optional `alt_text`.
+#[debug_handler]
+pub async fn upload(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let mut file = None;
+    let mut fields = V1UploadMediaFields::default();
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(err) => return multipart_error(err),
+        };
+
+        match field.name() {
+            Some("file") => {
+                let original_name = field
+                    .file_name()
+                    .map(|name| name.chars().take(255).collect::<String>());
+                match field.bytes().await {
+                    Ok(bytes) => file = Some((original_name, bytes)),
+                    Err(err) => return multipart_error(err),
+                }
+            }
+            Some("alt_text") => match field.text().await {
+                Ok(alt_text) => fields.alt_text = Some(alt_text),
+                Err(err) => return multipart_error(err),
+            },
+            _ => {}
+        }
+    }
+
+    if let Err(err) = fields.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Invalid upload",
+            })),
+        )
+            .into_response();
+    }
+    let Some((original_name, bytes)) = file else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "request failed",
+                "message": "No file was uploaded",
+            })),
+        )
+            .into_response();
+    };
+    if bytes.len() > media::max_upload_bytes() {
+        return (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(json!({
+                "error": "request failed",
+                "message": format!("File is larger than {} bytes", media::max_upload_bytes()),
+            })),
+        )
+            .into_response();
+    }
+
+    let upload = match media::store(state.media_storage.as_ref(), bytes.to_vec()).await {
+        Ok(upload) => upload,
+        Err(err) => return media_error(err),
+    };
+    let keys = upload.storage_keys();
+    let new_media = fields.into_new_media(user.id, original_name, upload);
+
+    match Media::create(&state.db_pool, new_media).await {
+        Ok(media) => (StatusCode::CREATED, Json(json!(media))).into_response(),
+        Err(err) => {
+            media::remove(state.media_storage.as_ref(), &keys).await;
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": err.to_string(),
+                    "message": "Failed to save upload",
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[debug_handler]
+pub async fn list(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Valid(Query(query)): Valid<Query<V1MediaListQueryParams>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+
+    match Media::list(&state.db_pool, user, query.page.unwrap_or(1)).await {
+        Ok(media) => (StatusCode::OK, Json(json!(media))).into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch media",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn find_by_id(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(media_id): Path<i32>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+
+    match Media::find_by_id(&state.db_pool, user, media_id).await {
+        Ok(Some(media)) => (StatusCode::OK, Json(json!(media))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Media does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch media",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn update(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(media_id): Path<i32>,
+    payload: Valid<Json<V1UpdateMediaPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let alt_text = payload
+        .into_inner()
+        .0
+        .alt_text
+        .filter(|alt_text| !alt_text.trim().is_empty());
+
+    match Media::update_alt_text(&state.db_pool, user, media_id, alt_text).await {
+        Ok(Some(media)) => (StatusCode::OK, Json(json!(media))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Media does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to update media",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn delete(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(media_id): Path<i32>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+
+    match Media::delete(&state.db_pool, user, media_id).await {
+        Ok(Some(MediaDeletion::Deleted(media))) => {
+            media::remove(state.media_storage.as_ref(), &media.storage_keys()).await;
+            (
+                StatusCode::OK,
+                Json(json!({ "message": "Media deleted successfully" })),
+            )
+                .into_response()
+        }
+        Ok(Some(MediaDeletion::InUse(usage))) => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "request failed",
+                "message": "Media is still in use",
+                "usage": usage,
+            })),
+        )
+            .into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Media does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to delete media",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+fn multipart_error(err: MultipartError) -> Response {
+    (
+        err.status(),
+        Json(json!({
+            "error": err.body_text(),
+            "message": "Failed to read upload",
+        })),
+    )
+        .into_response()
+}
+
+fn media_error(err: MediaError) -> Response {
+    let status = match &err {
+        MediaError::UnsupportedType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        MediaError::Image(image::ImageError::Limits(_)) => StatusCode::PAYLOAD_TOO_LARGE,
+        MediaError::Image(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (
+        status,
+        Json(json!({
+            "error": err.to_string(),
+            "message": "Failed to store upload",
+        })),
+    )
+        .into_response()
+}
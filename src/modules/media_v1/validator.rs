@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{
+    db::models::media::{MediaVariants, NewMedia},
+    services::media::StoredUpload,
+};
+
+/// Text fields sent along with the file of an upload.
+#[derive(Debug, Default, Deserialize, Serialize, Validate)]
+pub struct V1UploadMediaFields {
+    #[validate(length(max = 500))]
+    pub alt_text: Option<String>,
+}
+
+impl V1UploadMediaFields {
+    pub fn into_new_media(
+        self,
+        owner_id: i32,
+        original_name: Option<String>,
+        upload: StoredUpload,
+    ) -> NewMedia {
+        NewMedia {
+            owner_id: Some(owner_id),
+            storage_key: upload.storage_key,
+            url: upload.url,
+            original_name,
+            content_type: upload.content_type.to_string(),
+            byte_size: upload.byte_size as i32,
+            width: upload.width as i32,
+            height: upload.height as i32,
+            alt_text: self.alt_text.filter(|alt_text| !alt_text.trim().is_empty()),
+            variants: MediaVariants(upload.variants),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1UpdateMediaPayload {
+    #[validate(length(max = 500))]
+    pub alt_text: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1MediaListQueryParams {
+    #[validate(range(min = 1))]
+    pub page: Option<i64>,
+}
@@ -1,11 +1,19 @@
 pub mod auth_v1;
+pub mod bookmark_v1;
 pub mod category_v1;
 pub mod csrf_v1;
 pub mod email_verification_v1;
+pub mod feed_v1;
 pub mod forgot_password_v1;
+pub mod import_v1;
+pub mod media_v1;
 pub mod post_comment_v1;
+pub mod post_note_v1;
 pub mod post_v1;
+pub mod search_v1;
 pub mod seed_v1;
+pub mod series_v1;
+pub mod sitemap_v1;
 pub mod super_admin_v1;
 pub mod tag_v1;
 pub mod user_v1;
@@ -8,7 +8,11 @@ use axum_macros::debug_handler;
 use axum_valid::Valid;
 use serde_json::json;
 
-use crate::{db::models::post_comment::PostComment, services::auth::AuthSession, AppState};
+use crate::{
+    db::models::{pagination::PageRequest, post_comment::PostComment, post_like::PostCommentLike},
+    services::auth::AuthSession,
+    AppState,
+};
 
 use super::validator::{
     V1CreatePostCommentPayload, V1PostCommentQueryParams, V1UpdatePostCommentPayload,
@@ -24,7 +28,15 @@ pub async fn create(
     let new_comment = payload.into_inner().0.into_new_post_comment(user.id);
 
     match PostComment::create(&state.db_pool, new_comment).await {
-        Ok(comment) => (StatusCode::CREATED, Json(json!(comment))).into_response(),
+        Ok(Some(comment)) => (StatusCode::CREATED, Json(json!(comment))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post does not exist",
+            })),
+        )
+            .into_response(),
         Err(err) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({
@@ -77,7 +89,7 @@ pub async fn delete(
     match PostComment::delete(&state.db_pool, comment_id, user.id).await {
         Ok(1) => (
             StatusCode::OK,
-            Json(json!({ "message": "Comment deleted successfully" })),
+            Json(json!({ "message": "Comment moved to trash" })),
         )
             .into_response(),
         Ok(0) => (
@@ -107,6 +119,111 @@ pub async fn delete(
     }
 }
 
+#[debug_handler]
+pub async fn trash(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Valid(query): Valid<Query<V1PostCommentQueryParams>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let page = query.page.unwrap_or(1);
+
+    match PostComment::find_deleted(&state.db_pool, user, page).await {
+        Ok((comments, total)) => (
+            StatusCode::OK,
+            Json(json!({
+                "data": comments,
+                "total": total,
+                "page": page,
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch deleted comments",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn restore(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(comment_id): Path<i32>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    match PostComment::restore(&state.db_pool, user, comment_id).await {
+        Ok(Some(comment)) => (StatusCode::OK, Json(json!(comment))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Comment is not in the trash",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to restore comment",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn like(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(comment_id): Path<i32>,
+) -> impl IntoResponse {
+    set_like(state, auth, comment_id, true).await
+}
+
+#[debug_handler]
+pub async fn unlike(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(comment_id): Path<i32>,
+) -> impl IntoResponse {
+    set_like(state, auth, comment_id, false).await
+}
+
+async fn set_like(
+    state: AppState,
+    auth: AuthSession,
+    comment_id: i32,
+    liked: bool,
+) -> axum::response::Response {
+    let user = auth.user.unwrap();
+
+    match PostCommentLike::set(&state.db_pool, comment_id, user.id, liked).await {
+        Ok(Some(like)) => (StatusCode::OK, Json(json!(like))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Comment does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to update comment like",
+            })),
+        )
+            .into_response(),
+    }
+}
+
 #[debug_handler]
 pub async fn list_all(State(state): State<AppState>) -> impl IntoResponse {
     match PostComment::list_all(&state.db_pool).await {
@@ -173,18 +290,48 @@ pub async fn list_with_query(
 #[debug_handler]
 pub async fn list_by_post(
     State(state): State<AppState>,
+    auth: AuthSession,
     Path(post_id): Path<i32>,
     Valid(query): Valid<Query<V1PostCommentQueryParams>>,
 ) -> impl IntoResponse {
-    let page = query.page.unwrap_or(1);
+    let Some(page) = PageRequest::from_params(
+        query.page,
+        query.cursor.as_deref(),
+        &PostComment::listing(post_id),
+    ) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Invalid cursor",
+                "message": "The cursor is malformed or from another listing",
+            })),
+        )
+            .into_response();
+    };
+    let page_no = match page {
+        PageRequest::Number(page_no) => Some(page_no),
+        PageRequest::Cursor(_) => None,
+    };
 
-    match PostComment::list_by_post(&state.db_pool, post_id, page).await {
-        Ok((comments, total)) => (
+    match PostComment::list_by_post(&state.db_pool, post_id, page, auth.user.map(|user| user.id))
+        .await
+    {
+        Ok(Some(comments)) => (
             StatusCode::OK,
             Json(json!({
-                "data": comments,
-                "total": total,
-                "page": page,
+                "data": comments.data,
+                "total": comments.total,
+                "page": page_no,
+                "next_cursor": comments.next_cursor,
+                "prev_cursor": comments.prev_cursor,
+            })),
+        )
+            .into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post does not exist",
             })),
         )
             .into_response(),
@@ -202,12 +349,15 @@ pub async fn list_by_post(
 #[debug_handler]
 pub async fn list_by_user(
     State(state): State<AppState>,
+    auth: AuthSession,
     Path(user_id): Path<i32>,
     Valid(query): Valid<Query<V1PostCommentQueryParams>>,
 ) -> impl IntoResponse {
     let page = query.page.unwrap_or(1);
 
-    match PostComment::list_by_user(&state.db_pool, user_id, page).await {
+    match PostComment::list_by_user(&state.db_pool, user_id, page, auth.user.map(|user| user.id))
+        .await
+    {
         Ok((comments, total)) => (
             StatusCode::OK,
             Json(json!({
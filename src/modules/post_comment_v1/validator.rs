@@ -40,6 +40,8 @@ impl V1UpdatePostCommentPayload {
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct V1PostCommentQueryParams {
     pub page: Option<i64>,
+    /// Pages by cursor instead of number when listing by post; empty for the first page.
+    pub cursor: Option<String>,
     pub user_id: Option<i32>,
     pub post_id: Option<i32>,
     pub search: Option<String>,
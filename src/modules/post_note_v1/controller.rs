@@ -0,0 +1,244 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_macros::debug_handler;
+use axum_valid::Valid;
+use serde_json::json;
+
+use crate::{
+    db::models::post_note::{AnchoredNote, NoteRecipient, PostNote},
+    services::{auth::AuthSession, mail::send_editorial_note_email},
+    AppState,
+};
+
+use super::validator::{
+    V1CreatePostNotePayload, V1PostNoteListQueryParams, V1ReplyPostNotePayload,
+};
+
+#[debug_handler]
+pub async fn create(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    payload: Valid<Json<V1CreatePostNotePayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let new_note = payload.into_inner().0.into_new_post_note(user.id);
+
+    match PostNote::create(&state.db_pool, user, new_note).await {
+        Ok(Some(AnchoredNote::Created(note, recipient))) => {
+            notify_author(&state, recipient, &note.body);
+            (StatusCode::CREATED, Json(json!(note))).into_response()
+        }
+        Ok(Some(AnchoredNote::StaleVersion(version))) => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "version conflict",
+                "message": "Post was changed since the note was written",
+                "version": version,
+            })),
+        )
+            .into_response(),
+        Ok(Some(AnchoredNote::InvalidRange)) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "error": "request failed",
+                "message": "Anchor is outside of the post's content",
+            })),
+        )
+            .into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to create note",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn reply(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(note_id): Path<i32>,
+    payload: Valid<Json<V1ReplyPostNotePayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let body = payload.into_inner().0.body;
+
+    match PostNote::reply(&state.db_pool, user, note_id, body).await {
+        Ok(Some((note, recipient))) => {
+            notify_author(&state, recipient, &note.body);
+            (StatusCode::CREATED, Json(json!(note))).into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Note does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to reply to note",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn list(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(post_id): Path<i32>,
+    Valid(Query(query)): Valid<Query<V1PostNoteListQueryParams>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+
+    match PostNote::list(&state.db_pool, user, post_id, query.include_resolved).await {
+        Ok(Some(threads)) => (StatusCode::OK, Json(json!(threads))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch notes",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn resolve(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(note_id): Path<i32>,
+) -> impl IntoResponse {
+    set_resolved(state, auth, note_id, true).await
+}
+
+#[debug_handler]
+pub async fn unresolve(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(note_id): Path<i32>,
+) -> impl IntoResponse {
+    set_resolved(state, auth, note_id, false).await
+}
+
+#[debug_handler]
+pub async fn delete(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(note_id): Path<i32>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+
+    match PostNote::delete(&state.db_pool, user, note_id).await {
+        Ok(1) => (
+            StatusCode::OK,
+            Json(json!({ "message": "Note deleted successfully" })),
+        )
+            .into_response(),
+        Ok(0) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Note does not exist",
+            })),
+        )
+            .into_response(),
+        Ok(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "unexpected result",
+                "message": "Internal server error occurred while deleting note",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to delete note",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+async fn set_resolved(
+    state: AppState,
+    auth: AuthSession,
+    note_id: i32,
+    resolved: bool,
+) -> axum::response::Response {
+    let user = auth.user.unwrap();
+
+    match PostNote::set_resolved(&state.db_pool, user, note_id, resolved).await {
+        Ok(Some(note)) => (StatusCode::OK, Json(json!(note))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Note does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to update note",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// Emails the post's author in the background; a failed email doesn't fail the note.
+fn notify_author(state: &AppState, recipient: Option<NoteRecipient>, note: &str) {
+    let Some(recipient) = recipient else {
+        return;
+    };
+    let mailer = state.mailer.clone();
+    let note = note.to_string();
+
+    tokio::spawn(async move {
+        if let Err(err) = send_editorial_note_email(
+            &mailer,
+            &recipient.email,
+            &recipient.note_author,
+            &recipient.post_title,
+            &note,
+        )
+        .await
+        {
+            tracing::warn!("Failed to send editorial note email: {}", err);
+        }
+    });
+}
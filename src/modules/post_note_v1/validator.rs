@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+use crate::db::models::post_note::NewPostNote;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+#[validate(schema(function = "validate_anchor"))]
+pub struct V1CreatePostNotePayload {
+    pub post_id: i32,
+    /// Version of the post the offsets refer to; it has to be the current one.
+    pub version: i32,
+    /// Character offsets into the post's content, end exclusive.
+    #[validate(range(min = 0))]
+    pub anchor_start: i32,
+    pub anchor_end: i32,
+    #[validate(length(min = 1, max = 5000))]
+    pub body: String,
+}
+
+impl V1CreatePostNotePayload {
+    pub fn into_new_post_note(self, user_id: i32) -> NewPostNote {
+        NewPostNote {
+            post_id: self.post_id,
+            parent_id: None,
+            author_id: Some(user_id),
+            post_version: Some(self.version),
+            anchor_start: Some(self.anchor_start),
+            anchor_end: Some(self.anchor_end),
+            // Filled in from the content once the range is checked
+            quote: None,
+            body: self.body,
+        }
+    }
+}
+
+fn validate_anchor(payload: &V1CreatePostNotePayload) -> Result<(), ValidationError> {
+    if payload.anchor_end > payload.anchor_start {
+        Ok(())
+    } else {
+        Err(ValidationError::new("empty_anchor"))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1ReplyPostNotePayload {
+    #[validate(length(min = 1, max = 5000))]
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1PostNoteListQueryParams {
+    #[serde(default)]
+    pub include_resolved: bool,
+}
@@ -1,20 +1,50 @@
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use axum_client_ip::SecureClientIp;
 use axum_valid::Valid;
 
 use axum_macros::debug_handler;
+use chrono::Utc;
 use serde_json::json;
 
 use crate::{
-    db::models::post::Post, modules::post_v1::validator::V1UpdatePostPayload,
-    services::auth::AuthSession, AppState,
+    db::{
+        errors::DBError,
+        models::{
+            pagination::PageRequest,
+            post::{Post, PostStatus, RelatedPostScore, StatusChange},
+            post_analytics::PostAnalytics,
+            post_contributor::PostContributor,
+            post_like::PostLike,
+            post_preview_token::PostPreviewToken,
+            post_ranking::{PostRanking, PostScore, RankingFilter, RankingKind},
+            post_reaction::PostReaction,
+            post_status_transition::PostStatusTransition,
+            post_view::{NewPostView, PostView},
+        },
+        utils::VersionedUpdate,
+    },
+    middlewares::user_permission,
+    modules::post_v1::validator::V1UpdatePostPayload,
+    services::{
+        auth::AuthSession,
+        cache, ranking,
+        version::{self, ExpectedVersion},
+        view_tracker,
+    },
+    AppState,
 };
 
-use super::validator::{V1CreatePostPayload, V1PostQueryParams};
+use super::validator::{
+    V1AddContributorPayload, V1ChangeStatusPayload, V1CreatePostPayload, V1CreatePreviewPayload,
+    V1PostAnalyticsQueryParams, V1PostQueryParams, V1PostReactionPayload, V1PostSearchParams,
+    V1PostViewQueryParams, V1RankedPostsQueryParams, V1RelatedPostsQueryParams,
+    V1RemoveContributorPayload, V1SchedulePostPayload, V1TrackViewQueryParams,
+};
 
 #[debug_handler]
 pub async fn create(
@@ -25,8 +55,42 @@ pub async fn create(
     let user = auth.user.unwrap();
     let new_post = payload.into_inner().0.into_new_post(user.id);
 
+    if matches!(
+        new_post.status,
+        PostStatus::Published | PostStatus::Scheduled
+    ) && !user_permission::can_publish_directly(&user)
+    {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post has to be approved before it is published",
+            })),
+        )
+            .into_response();
+    }
+
     match Post::create(&state.db_pool, new_post).await {
-        Ok(post) => (StatusCode::CREATED, Json(json!(post))).into_response(),
+        Ok(post) => {
+            cache::invalidate_posts(&state.redis_pool).await;
+            (StatusCode::CREATED, Json(json!(post))).into_response()
+        }
+        Err(err @ DBError::MediaNotFound(_)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": err.to_string(),
+            })),
+        )
+            .into_response(),
+        Err(err @ DBError::MediaNotOwned(_)) => (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "request failed",
+                "message": err.to_string(),
+            })),
+        )
+            .into_response(),
         Err(err) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({
@@ -41,20 +105,58 @@ pub async fn create(
 #[debug_handler]
 pub async fn find_by_id_or_slug(
     State(state): State<AppState>,
+    auth: AuthSession,
     Path(slug_or_id): Path<String>,
+    Valid(Query(query)): Valid<Query<V1PostViewQueryParams>>,
 ) -> impl IntoResponse {
-    let query = match slug_or_id.parse::<i32>() {
-        Ok(id) => Post::find_by_id_or_slug(&state.db_pool, Some(id), None).await,
-        Err(_) => Post::find_by_id_or_slug(&state.db_pool, None, Some(slug_or_id)).await,
+    let (post_id, post_slug) = match slug_or_id.parse::<i32>() {
+        Ok(id) => (Some(id), None),
+        Err(_) => (None, Some(slug_or_id)),
     };
 
-    match query {
-        Ok(Some(post)) => (StatusCode::OK, Json(json!(post))).into_response(),
-        Ok(None) => (
-            StatusCode::NOT_FOUND,
-            Json(json!({ "message": "Post not found" })),
+    match Post::find_by_id_or_slug(
+        &state.db_pool,
+        post_id,
+        post_slug.clone(),
+        auth.user,
+        query.preview_token,
+    )
+    .await
+    {
+        Ok(Some(post)) => (
+            StatusCode::OK,
+            [(header::ETAG, version::etag(post.post.version))],
+            Json(json!(post)),
+        )
+            .into_response(),
+        Ok(None) => match post_slug {
+            Some(old_slug) => moved_or_not_found(&state, old_slug).await,
+            None => post_not_found(),
+        },
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch post",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// Points clients holding a slug the post has since moved away from at its current one.
+async fn moved_or_not_found(state: &AppState, old_slug: String) -> Response {
+    match Post::find_redirected_slug(&state.db_pool, old_slug).await {
+        Ok(Some(current_slug)) => (
+            StatusCode::PERMANENT_REDIRECT,
+            [(header::LOCATION, format!("/post/v1/view/{}", current_slug))],
+            Json(json!({
+                "message": "Post has moved",
+                "slug": current_slug,
+            })),
         )
             .into_response(),
+        Ok(None) => post_not_found(),
         Err(err) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({
@@ -66,18 +168,223 @@ pub async fn find_by_id_or_slug(
     }
 }
 
+fn post_not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "message": "Post not found" })),
+    )
+        .into_response()
+}
+
+#[debug_handler]
+pub async fn related(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(slug_or_id): Path<String>,
+    Valid(query): Valid<Query<V1RelatedPostsQueryParams>>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(4);
+    let cache_key = format!("{}:{}", slug_or_id, limit);
+
+    let scores = match cache::get_json::<Vec<RelatedPostScore>>(
+        &state.redis_pool,
+        cache::RELATED_POSTS,
+        &cache_key,
+    )
+    .await
+    {
+        Some(scores) => scores,
+        None => {
+            let query = match slug_or_id.parse::<i32>() {
+                Ok(id) => Post::related_scores(&state.db_pool, Some(id), None, limit).await,
+                Err(_) => Post::related_scores(&state.db_pool, None, Some(slug_or_id), limit).await,
+            };
+
+            match query {
+                Ok(Some(scores)) => {
+                    cache::set_json(
+                        &state.redis_pool,
+                        cache::RELATED_POSTS,
+                        &cache_key,
+                        &scores,
+                        3600,
+                    )
+                    .await;
+                    scores
+                }
+                Ok(None) => {
+                    return (
+                        StatusCode::NOT_FOUND,
+                        Json(json!({ "message": "Post not found" })),
+                    )
+                        .into_response()
+                }
+                Err(err) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({
+                            "error": err.to_string(),
+                            "message": "Failed to fetch related posts",
+                        })),
+                    )
+                        .into_response()
+                }
+            }
+        }
+    };
+
+    match Post::find_related(&state.db_pool, scores, auth.user.map(|user| user.id)).await {
+        Ok(posts) => (StatusCode::OK, Json(json!(posts))).into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch related posts",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn trending(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Valid(Query(query)): Valid<Query<V1RankedPostsQueryParams>>,
+) -> impl IntoResponse {
+    ranked_posts(&state, auth, RankingKind::Trending, query).await
+}
+
+#[debug_handler]
+pub async fn popular(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Valid(Query(query)): Valid<Query<V1RankedPostsQueryParams>>,
+) -> impl IntoResponse {
+    ranked_posts(&state, auth, RankingKind::Popular, query).await
+}
+
+async fn ranked_posts(
+    state: &AppState,
+    auth: AuthSession,
+    kind: RankingKind,
+    query: V1RankedPostsQueryParams,
+) -> Response {
+    let limit = query.limit.unwrap_or(10);
+    let viewer_id = auth.user.map(|user| user.id);
+    let filter = query.into_ranking_filter();
+
+    // The stored ranking only holds the overall top posts, so filtered lists are scored on
+    // the spot
+    let scores = if filter.is_empty() {
+        stored_ranking(state, kind).await
+    } else {
+        PostRanking::compute(&state.db_pool, kind, filter, limit).await
+    };
+    let scores = match scores {
+        Ok(scores) => scores,
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": err.to_string(),
+                    "message": format!("Failed to fetch {} posts", kind.name()),
+                })),
+            )
+                .into_response()
+        }
+    };
+
+    match PostRanking::find(&state.db_pool, scores, limit, viewer_id).await {
+        Ok(posts) => (StatusCode::OK, Json(json!(posts))).into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": format!("Failed to fetch {} posts", kind.name()),
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// The ranking stored in Redis, computed and stored on the spot when it is missing.
+async fn stored_ranking(state: &AppState, kind: RankingKind) -> Result<Vec<PostScore>, DBError> {
+    let stored = ranking::load(&state.redis_pool, kind).await;
+    if let Ok(Some(scores)) = stored {
+        return Ok(scores);
+    }
+    if let Err(err) = &stored {
+        tracing::warn!("Failed to read the {} ranking: {}", kind.name(), err);
+    }
+
+    let scores = PostRanking::compute(
+        &state.db_pool,
+        kind,
+        RankingFilter::default(),
+        PostRanking::SIZE,
+    )
+    .await?;
+    if stored.is_ok() {
+        if let Err(err) = ranking::store(&state.redis_pool, kind, &scores).await {
+            tracing::warn!("Failed to store the {} ranking: {}", kind.name(), err);
+        }
+    }
+
+    Ok(scores)
+}
+
 #[debug_handler]
 pub async fn update(
     State(state): State<AppState>,
     auth: AuthSession,
     Path(post_id): Path<i32>,
+    headers: HeaderMap,
     payload: Valid<Json<V1UpdatePostPayload>>,
 ) -> impl IntoResponse {
     let user = auth.user.unwrap();
-    let update_post = payload.into_inner().0.into_update_post(user.id);
+    let payload = payload.into_inner().0;
+    let Some(expected) = ExpectedVersion::from_request(&headers, payload.version) else {
+        return (
+            StatusCode::PRECONDITION_REQUIRED,
+            Json(json!({
+                "error": "request failed",
+                "message": "Send the post version being edited in If-Match or version",
+            })),
+        )
+            .into_response();
+    };
+    let update_post = payload.into_update_post();
 
-    match Post::update(&state.db_pool, post_id, user, update_post).await {
-        Ok(Some(post)) => (StatusCode::OK, Json(json!(post))).into_response(),
+    match Post::update(
+        &state.db_pool,
+        post_id,
+        user,
+        update_post,
+        expected.versions(),
+    )
+    .await
+    {
+        Ok(Some(VersionedUpdate::Updated(post))) => {
+            cache::invalidate_posts(&state.redis_pool).await;
+            (
+                StatusCode::OK,
+                [(header::ETAG, version::etag(post.version))],
+                Json(json!(post)),
+            )
+                .into_response()
+        }
+        Ok(Some(VersionedUpdate::Conflict(post))) => (
+            expected.conflict_status(),
+            [(header::ETAG, version::etag(post.version))],
+            Json(json!({
+                "error": "version conflict",
+                "message": "Post was changed by someone else since it was loaded",
+                "version": post.version,
+                "current": post,
+            })),
+        )
+            .into_response(),
         Ok(None) => (
             StatusCode::NOT_FOUND,
             Json(json!({
@@ -86,6 +393,22 @@ pub async fn update(
             })),
         )
             .into_response(),
+        Err(err @ DBError::MediaNotFound(_)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": err.to_string(),
+            })),
+        )
+            .into_response(),
+        Err(err @ DBError::MediaNotOwned(_)) => (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "request failed",
+                "message": err.to_string(),
+            })),
+        )
+            .into_response(),
         Err(err) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({
@@ -98,18 +421,317 @@ pub async fn update(
 }
 
 #[debug_handler]
-pub async fn delete(
+pub async fn schedule(
     State(state): State<AppState>,
     auth: AuthSession,
     Path(post_id): Path<i32>,
+    payload: Valid<Json<V1SchedulePostPayload>>,
 ) -> impl IntoResponse {
     let user = auth.user.unwrap();
-    match Post::delete(&state.db_pool, user, post_id).await {
-        Ok(1) => (
-            StatusCode::OK,
-            Json(json!({ "message": "Post deleted successfully" })),
+    let can_publish_directly = user_permission::can_publish_directly(&user);
+    let publish_at = payload.into_inner().0.published_at;
+
+    let result = Post::schedule(
+        &state.db_pool,
+        post_id,
+        user,
+        publish_at,
+        can_publish_directly,
+    )
+    .await;
+    status_change_response(&state, result, "Failed to schedule post").await
+}
+
+#[debug_handler]
+pub async fn unschedule(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(post_id): Path<i32>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+
+    let result = Post::unschedule(&state.db_pool, post_id, user).await;
+    status_change_response(&state, result, "Failed to unschedule post").await
+}
+
+#[debug_handler]
+pub async fn change_status(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(post_id): Path<i32>,
+    payload: Valid<Json<V1ChangeStatusPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let can_publish_directly = user_permission::can_publish_directly(&user);
+    let request = payload.into_inner().0.into_status_change_request();
+
+    let result =
+        Post::change_status(&state.db_pool, post_id, user, request, can_publish_directly).await;
+    status_change_response(&state, result, "Failed to change post status").await
+}
+
+#[debug_handler]
+pub async fn status_history(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(post_id): Path<i32>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+
+    match PostStatusTransition::history(&state.db_pool, user, post_id).await {
+        Ok(Some(history)) => (StatusCode::OK, Json(json!(history))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch post status history",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+async fn status_change_response(
+    state: &AppState,
+    result: Result<Option<StatusChange>, DBError>,
+    failure_message: &str,
+) -> Response {
+    match result {
+        Ok(Some(StatusChange::Changed(post))) => {
+            cache::invalidate_posts(&state.redis_pool).await;
+            (
+                StatusCode::OK,
+                [(header::ETAG, version::etag(post.version))],
+                Json(json!(post)),
+            )
+                .into_response()
+        }
+        Ok(Some(StatusChange::Invalid(post))) => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post can't move to this status from its current one",
+                "status": post.status,
+            })),
+        )
+            .into_response(),
+        Ok(Some(StatusChange::Forbidden(post))) => (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "request failed",
+                "message": "You are not allowed to move this post to this status",
+                "status": post.status,
+            })),
+        )
+            .into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": failure_message,
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn add_contributor(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(post_id): Path<i32>,
+    payload: Valid<Json<V1AddContributorPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let contributor = payload.into_inner().0.into_new_post_contributor(post_id);
+
+    match PostContributor::upsert(&state.db_pool, post_id, user, contributor).await {
+        Ok(Some(contributors)) => {
+            cache::invalidate_posts(&state.redis_pool).await;
+            (StatusCode::OK, Json(json!(contributors))).into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post or user does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to add contributor",
+            })),
         )
             .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn remove_contributor(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(post_id): Path<i32>,
+    payload: Valid<Json<V1RemoveContributorPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let contributor_id = payload.into_inner().0.user_id;
+
+    match PostContributor::remove(&state.db_pool, post_id, user, contributor_id).await {
+        Ok(Some(contributors)) => {
+            cache::invalidate_posts(&state.redis_pool).await;
+            (StatusCode::OK, Json(json!(contributors))).into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to remove contributor",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn like(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(post_id): Path<i32>,
+) -> impl IntoResponse {
+    set_like(state, auth, post_id, true).await
+}
+
+#[debug_handler]
+pub async fn unlike(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(post_id): Path<i32>,
+) -> impl IntoResponse {
+    set_like(state, auth, post_id, false).await
+}
+
+async fn set_like(
+    state: AppState,
+    auth: AuthSession,
+    post_id: i32,
+    liked: bool,
+) -> axum::response::Response {
+    let user = auth.user.unwrap();
+
+    match PostLike::set(&state.db_pool, post_id, user.id, liked).await {
+        Ok(Some(like)) => (StatusCode::OK, Json(json!(like))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to update post like",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn add_reaction(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(post_id): Path<i32>,
+    payload: Valid<Json<V1PostReactionPayload>>,
+) -> impl IntoResponse {
+    set_reaction(state, auth, post_id, payload.into_inner().0, true).await
+}
+
+#[debug_handler]
+pub async fn remove_reaction(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(post_id): Path<i32>,
+    payload: Valid<Json<V1PostReactionPayload>>,
+) -> impl IntoResponse {
+    set_reaction(state, auth, post_id, payload.into_inner().0, false).await
+}
+
+async fn set_reaction(
+    state: AppState,
+    auth: AuthSession,
+    post_id: i32,
+    payload: V1PostReactionPayload,
+    reacted: bool,
+) -> axum::response::Response {
+    let user = auth.user.unwrap();
+
+    match PostReaction::set(&state.db_pool, post_id, user.id, payload.kind, reacted).await {
+        Ok(Some(reactions)) => (StatusCode::OK, Json(json!(reactions))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to update post reaction",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn delete(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(post_id): Path<i32>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    match Post::delete(&state.db_pool, user, post_id).await {
+        Ok(1) => {
+            cache::invalidate_posts(&state.redis_pool).await;
+            (
+                StatusCode::OK,
+                Json(json!({ "message": "Post moved to trash" })),
+            )
+                .into_response()
+        }
         Ok(0) => (
             StatusCode::NOT_FOUND,
             Json(json!({
@@ -137,6 +759,68 @@ pub async fn delete(
     }
 }
 
+#[debug_handler]
+pub async fn trash(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Valid(query): Valid<Query<V1PostQueryParams>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let page = query.page.unwrap_or(1);
+
+    match Post::find_deleted(&state.db_pool, user, page).await {
+        Ok((posts, total)) => (
+            StatusCode::OK,
+            Json(json!({
+                "data": posts,
+                "total": total,
+                "per_page": Post::PER_PAGE,
+                "page": page,
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch deleted posts",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn restore(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(post_id): Path<i32>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    match Post::restore(&state.db_pool, user, post_id).await {
+        Ok(Some(post)) => {
+            cache::invalidate_posts(&state.redis_pool).await;
+            (StatusCode::OK, Json(json!(post))).into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post is not in the trash",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to restore post",
+            })),
+        )
+            .into_response(),
+    }
+}
+
 #[debug_handler]
 pub async fn find_posts_with_query(
     State(state): State<AppState>,
@@ -161,11 +845,61 @@ pub async fn find_posts_with_query(
 #[debug_handler]
 pub async fn find_published_posts(
     State(state): State<AppState>,
+    auth: AuthSession,
     Valid(query): Valid<Query<V1PostQueryParams>>,
 ) -> impl IntoResponse {
-    let page = query.page.unwrap_or(1);
+    let Some(page) =
+        PageRequest::from_params(query.page, query.cursor.as_deref(), Post::PUBLISHED_LISTING)
+    else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Invalid cursor",
+                "message": "The cursor is malformed or from another listing",
+            })),
+        )
+            .into_response();
+    };
+    let page_no = match page {
+        PageRequest::Number(page_no) => Some(page_no),
+        PageRequest::Cursor(_) => None,
+    };
+
+    match Post::find_published_paginated(&state.db_pool, page, auth.user.map(|user| user.id)).await
+    {
+        Ok(posts) => (
+            StatusCode::OK,
+            Json(json!({
+                "data": posts.data,
+                "total": posts.total,
+                "per_page": Post::PER_PAGE,
+                "page": page_no,
+                "next_cursor": posts.next_cursor,
+                "prev_cursor": posts.prev_cursor,
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch published posts",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn search(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Valid(query): Valid<Query<V1PostSearchParams>>,
+) -> impl IntoResponse {
+    let search_query = query.0.into_search_query();
+    let page = search_query.page_no.unwrap_or(1);
 
-    match Post::find_published_paginated(&state.db_pool, page).await {
+    match Post::search(&state.db_pool, search_query, auth.user).await {
         Ok((posts, total)) => (
             StatusCode::OK,
             Json(json!({
@@ -180,7 +914,7 @@ pub async fn find_published_posts(
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({
                 "error": err.to_string(),
-                "message": "Failed to fetch published posts",
+                "message": "Failed to search posts",
             })),
         )
             .into_response(),
@@ -191,11 +925,79 @@ pub async fn find_published_posts(
 pub async fn track_view(
     State(state): State<AppState>,
     auth: AuthSession,
+    secure_ip: SecureClientIp,
+    headers: HeaderMap,
     Path(post_id): Path<i32>,
+    Valid(Query(query)): Valid<Query<V1TrackViewQueryParams>>,
 ) -> impl IntoResponse {
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok());
+    if view_tracker::is_bot(user_agent) {
+        return (
+            StatusCode::OK,
+            Json(json!({ "message": "View not counted" })),
+        )
+            .into_response();
+    }
+
+    match Post::is_live(&state.db_pool, post_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "request failed",
+                    "message": "Post does not exist",
+                })),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": err.to_string(),
+                    "message": "Failed to track view",
+                })),
+            )
+                .into_response()
+        }
+    }
+
     let user_id: Option<i32> = auth.user.map(|user| user.id);
-    match Post::increment_view_count(&state.db_pool, post_id, user_id).await {
-        Ok(_) => (
+    let user_agent = user_agent.unwrap_or_default();
+    let visitor = view_tracker::visitor_key(user_id, secure_ip.0, user_agent);
+    let referrer = query.referrer.as_deref().or_else(|| {
+        headers
+            .get(header::REFERER)
+            .and_then(|value| value.to_str().ok())
+    });
+    let view = NewPostView {
+        post_id,
+        user_id,
+        created_at: Utc::now().naive_utc(),
+        visitor_hash: Some(view_tracker::visitor_hash(&visitor)),
+        referrer_domain: referrer.and_then(view_tracker::referrer_domain),
+        utm_source: view_tracker::utm_value(query.utm_source.as_deref()),
+        utm_medium: view_tracker::utm_value(query.utm_medium.as_deref()),
+        utm_campaign: view_tracker::utm_value(query.utm_campaign.as_deref()),
+        device: Some(view_tracker::device_class(user_agent)),
+    };
+
+    let result = match view_tracker::record(&state.redis_pool, &view, &visitor).await {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            // Without Redis the view is stored right away, just not deduplicated
+            tracing::warn!("Failed to buffer view of post {}: {}", post_id, err);
+            PostView::record_batch(&state.db_pool, None, vec![view])
+                .await
+                .map(|_| ())
+        }
+    };
+
+    match result {
+        Ok(()) => (
             StatusCode::OK,
             Json(json!({ "message": "View tracked successfully" })),
         )
@@ -225,3 +1027,124 @@ pub async fn sitemap(State(state): State<AppState>) -> impl IntoResponse {
             .into_response(),
     }
 }
+
+#[debug_handler]
+pub async fn create_preview(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(post_id): Path<i32>,
+    payload: Valid<Json<V1CreatePreviewPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let new_token = payload
+        .into_inner()
+        .0
+        .into_new_preview_token(post_id, user.id);
+
+    match PostPreviewToken::create(&state.db_pool, user, new_token).await {
+        Ok(Some(link)) => (StatusCode::CREATED, Json(json!(link))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to create preview link",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn list_previews(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(post_id): Path<i32>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+
+    match PostPreviewToken::list(&state.db_pool, user, post_id).await {
+        Ok(Some(links)) => (StatusCode::OK, Json(json!(links))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch preview links",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn revoke_preview(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(token_id): Path<i32>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+
+    match PostPreviewToken::revoke(&state.db_pool, user, token_id).await {
+        Ok(Some(token)) => (StatusCode::OK, Json(json!(token))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Preview link does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to revoke preview link",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn analytics(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Valid(Query(query)): Valid<Query<V1PostAnalyticsQueryParams>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+
+    match PostAnalytics::report(&state.db_pool, user, query.into_analytics_query()).await {
+        Ok(Some(report)) => (StatusCode::OK, Json(json!(report))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Post does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch post analytics",
+            })),
+        )
+            .into_response(),
+    }
+}
@@ -1,74 +1,136 @@
 // use bool;
-use chrono::NaiveDateTime;
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
-use crate::db::models::post::{NewPost, PostQuery, PostSortBy, UpdatePost};
+use crate::{
+    db::models::{
+        post::{
+            ContentFormat, NewPost, PostQuery, PostSearchQuery, PostSortBy, PostStatus,
+            StatusChangeRequest, UpdatePost,
+        },
+        post_analytics::AnalyticsQuery,
+        post_contributor::{ContributorRole, NewPostContributor},
+        post_preview_token::NewPostPreviewToken,
+        post_ranking::RankingFilter,
+        post_reaction::ReactionKind,
+    },
+    services::{preview, search},
+};
 
 #[derive(Debug, Deserialize, Serialize, Validate)]
+#[validate(schema(function = "validate_create_schedule"))]
 pub struct V1CreatePostPayload {
     #[validate(length(min = 1, max = 255))]
     pub title: String,
     #[validate(length(min = 1))]
     pub content: String,
+    #[serde(default)]
+    pub content_format: ContentFormat,
     pub published_at: Option<NaiveDateTime>,
-    #[serde(default = "bool::default")]
-    pub is_published: bool,
+    /// `draft`, `in_review`, `published` or `scheduled`.
+    #[serde(default)]
+    #[validate(custom(function = "validate_initial_status"))]
+    pub status: PostStatus,
     #[validate(length(min = 1, max = 255))]
-    pub slug: String,
+    pub slug: Option<String>,
     #[validate(length(max = 500))]
     pub excerpt: Option<String>,
     pub featured_image_url: Option<String>,
+    /// Library image to feature; replaces `featured_image_url`.
+    pub featured_media_id: Option<i32>,
     pub category_id: Option<i32>,
     #[serde(default = "Vec::new")]
     pub tag_ids: Vec<i32>,
+    #[validate(custom(function = "validate_search_language"))]
+    pub search_language: Option<String>,
 }
 
 impl V1CreatePostPayload {
     pub fn into_new_post(self, author_id: i32) -> NewPost {
+        let now = chrono::Utc::now().naive_utc();
+        // Publishing with a future date schedules the post instead
+        let (status, published_at) = match (self.status, self.published_at) {
+            (PostStatus::Published | PostStatus::Scheduled, Some(date)) if date > now => {
+                (PostStatus::Scheduled, Some(date))
+            }
+            (PostStatus::Published, date) => (PostStatus::Published, Some(date.unwrap_or(now))),
+            (status, date) => (status, date),
+        };
+
         NewPost {
             title: self.title,
             content: self.content,
             author_id,
-            published_at: self.published_at,
-            is_published: self.is_published,
-            slug: self.slug,
+            published_at,
+            // An empty slug is generated from the title on insert
+            slug: self.slug.unwrap_or_default(),
             excerpt: self.excerpt,
             featured_image_url: self.featured_image_url,
             category_id: self.category_id,
             view_count: 0,
             likes_count: 0,
             tag_ids: self.tag_ids,
+            content_format: self.content_format,
+            search_language: self
+                .search_language
+                .unwrap_or_else(search::default_language),
+            status,
+            featured_media_id: self.featured_media_id,
         }
     }
 }
 
+fn validate_initial_status(status: &PostStatus) -> Result<(), ValidationError> {
+    match status {
+        PostStatus::Draft
+        | PostStatus::InReview
+        | PostStatus::Published
+        | PostStatus::Scheduled => Ok(()),
+        _ => Err(ValidationError::new("invalid_initial_status")),
+    }
+}
+
+fn validate_create_schedule(payload: &V1CreatePostPayload) -> Result<(), ValidationError> {
+    match (payload.status, payload.published_at) {
+        (PostStatus::Scheduled, Some(date)) => validate_future_date(&date),
+        (PostStatus::Scheduled, None) => Err(ValidationError::new("missing_published_at")),
+        _ => Ok(()),
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct V1UpdatePostPayload {
     #[validate(length(min = 1, max = 255))]
     pub title: Option<String>,
     #[validate(length(min = 1))]
     pub content: Option<String>,
-    pub published_at: Option<Option<NaiveDateTime>>,
-    pub is_published: Option<bool>,
+    pub content_format: Option<ContentFormat>,
     #[validate(length(min = 1, max = 255))]
     pub slug: Option<String>,
     #[validate(length(max = 500))]
     pub excerpt: Option<Option<String>>,
     pub featured_image_url: Option<Option<String>>,
+    pub featured_media_id: Option<Option<i32>>,
     pub category_id: Option<Option<i32>>,
     pub tag_ids: Option<Vec<i32>>,
+    #[validate(custom(function = "validate_search_language"))]
+    pub search_language: Option<String>,
+    /// Version the edit is based on, unless it is sent in `If-Match`.
+    pub version: Option<i32>,
 }
 
 impl V1UpdatePostPayload {
-    pub fn into_update_post(self, author_id: i32) -> UpdatePost {
+    pub fn into_update_post(self) -> UpdatePost {
+        // An excerpt written (or cleared) by the author replaces the generated one
+        let excerpt_generated = self.excerpt.as_ref().map(|_| false);
+
         UpdatePost {
             title: self.title,
             content: self.content,
-            author_id: Some(author_id),
-            published_at: self.published_at,
+            // Contributors edit the post without taking it over
+            author_id: None,
             updated_at: chrono::Utc::now().naive_utc(),
-            is_published: self.is_published,
             slug: self.slug,
             excerpt: self.excerpt,
             featured_image_url: self.featured_image_url,
@@ -76,6 +138,10 @@ impl V1UpdatePostPayload {
             view_count: None,
             likes_count: None,
             tag_ids: self.tag_ids,
+            content_format: self.content_format,
+            excerpt_generated,
+            search_language: self.search_language,
+            featured_media_id: self.featured_media_id,
         }
     }
 }
@@ -83,9 +149,12 @@ impl V1UpdatePostPayload {
 #[derive(Debug, Deserialize, Serialize, Validate, Clone)]
 pub struct V1PostQueryParams {
     pub page: Option<i64>,
+    /// Pages by cursor instead of number on the published listing; empty for the first page.
+    pub cursor: Option<String>,
     pub author_id: Option<i32>,
+    pub contributor_id: Option<i32>,
     pub category_id: Option<i32>,
-    pub is_published: Option<bool>,
+    pub status: Option<PostStatus>,
     pub search: Option<String>,
     pub sort_by: Option<PostSortBy>,
     pub sort_order: Option<String>,
@@ -97,8 +166,9 @@ impl V1PostQueryParams {
         PostQuery {
             page_no: self.page,
             author_id: self.author_id,
+            contributor_id: self.contributor_id,
             category_id: self.category_id,
-            is_published: self.is_published,
+            status: self.status,
             search: self.search,
             sort_by: self.sort_by,
             sort_order: self.sort_order,
@@ -106,3 +176,225 @@ impl V1PostQueryParams {
         }
     }
 }
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1SchedulePostPayload {
+    #[validate(custom(function = "validate_future_date"))]
+    pub published_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+#[validate(schema(function = "validate_status_change"))]
+pub struct V1ChangeStatusPayload {
+    pub status: PostStatus,
+    /// Required when requesting changes.
+    #[validate(length(min = 1, max = 2000))]
+    pub comment: Option<String>,
+    /// Required when scheduling.
+    pub published_at: Option<NaiveDateTime>,
+}
+
+impl V1ChangeStatusPayload {
+    pub fn into_status_change_request(self) -> StatusChangeRequest {
+        StatusChangeRequest {
+            status: self.status,
+            comment: self.comment,
+            publish_at: self.published_at,
+        }
+    }
+}
+
+fn validate_status_change(payload: &V1ChangeStatusPayload) -> Result<(), ValidationError> {
+    match (payload.status, payload.published_at) {
+        (PostStatus::ChangesRequested, _) if payload.comment.is_none() => {
+            Err(ValidationError::new("missing_review_comment"))
+        }
+        (PostStatus::Scheduled, Some(date)) => validate_future_date(&date),
+        (PostStatus::Scheduled, None) => Err(ValidationError::new("missing_published_at")),
+        _ => Ok(()),
+    }
+}
+
+fn validate_future_date(date: &NaiveDateTime) -> Result<(), ValidationError> {
+    if *date > chrono::Utc::now().naive_utc() {
+        Ok(())
+    } else {
+        Err(ValidationError::new("date_not_in_future"))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+pub struct V1PostSearchParams {
+    #[validate(length(min = 1, max = 200))]
+    pub q: String,
+    #[validate(custom(function = "validate_search_language"))]
+    pub language: Option<String>,
+    #[validate(range(min = 1))]
+    pub page: Option<i64>,
+}
+
+impl V1PostSearchParams {
+    pub fn into_search_query(self) -> PostSearchQuery {
+        PostSearchQuery {
+            terms: self.q,
+            language: self.language,
+            page_no: self.page,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+pub struct V1RelatedPostsQueryParams {
+    #[validate(range(min = 1, max = 12))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+pub struct V1RankedPostsQueryParams {
+    pub category_id: Option<i32>,
+    pub tag_id: Option<i32>,
+    /// Leaves out posts published more than this many days ago.
+    #[validate(range(min = 1, max = 3650))]
+    pub max_age_days: Option<i64>,
+    #[validate(range(min = 1, max = 50))]
+    pub limit: Option<i64>,
+}
+
+impl V1RankedPostsQueryParams {
+    pub fn into_ranking_filter(self) -> RankingFilter {
+        RankingFilter {
+            category_id: self.category_id,
+            tag_id: self.tag_id,
+            max_age_days: self.max_age_days,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1AddContributorPayload {
+    pub user_id: i32,
+    #[validate(custom(function = "validate_contributor_role"))]
+    pub role: ContributorRole,
+}
+
+impl V1AddContributorPayload {
+    pub fn into_new_post_contributor(self, post_id: i32) -> NewPostContributor {
+        NewPostContributor {
+            post_id,
+            user_id: self.user_id,
+            role: self.role,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1RemoveContributorPayload {
+    pub user_id: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1PostReactionPayload {
+    pub kind: ReactionKind,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1PostViewQueryParams {
+    pub preview_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1CreatePreviewPayload {
+    /// Up to 30 days.
+    #[validate(range(min = 1, max = 720))]
+    pub expires_in_hours: Option<i64>,
+    #[validate(length(min = 1, max = 255))]
+    pub label: Option<String>,
+}
+
+impl V1CreatePreviewPayload {
+    pub fn into_new_preview_token(self, post_id: i32, user_id: i32) -> NewPostPreviewToken {
+        let expires_in_hours = self.expires_in_hours.unwrap_or(preview::DEFAULT_TTL_HOURS);
+
+        NewPostPreviewToken {
+            post_id,
+            created_by: Some(user_id),
+            label: self.label,
+            expires_at: chrono::Utc::now().naive_utc() + chrono::Duration::hours(expires_in_hours),
+        }
+    }
+}
+
+/// The author credit belongs to the post's owner and is never granted by hand.
+fn validate_contributor_role(role: &ContributorRole) -> Result<(), ValidationError> {
+    if *role == ContributorRole::Author {
+        Err(ValidationError::new("author_role_reserved"))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_search_language(language: &str) -> Result<(), ValidationError> {
+    if search::is_supported_language(language) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unsupported_search_language"))
+    }
+}
+
+/// Where a view came from, as the page saw it: `document.referrer` and the UTM parameters of
+/// its URL. Without `referrer` the `Referer` header is used.
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1TrackViewQueryParams {
+    #[validate(length(max = 2048))]
+    pub referrer: Option<String>,
+    #[validate(length(max = 255))]
+    pub utm_source: Option<String>,
+    #[validate(length(max = 255))]
+    pub utm_medium: Option<String>,
+    #[validate(length(max = 255))]
+    pub utm_campaign: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+#[validate(schema(function = "validate_analytics_range"))]
+pub struct V1PostAnalyticsQueryParams {
+    /// First day, in UTC. Defaults to 29 days before `to`.
+    pub from: Option<NaiveDate>,
+    /// Last day, in UTC. Defaults to today.
+    pub to: Option<NaiveDate>,
+    pub post_id: Option<i32>,
+}
+
+impl V1PostAnalyticsQueryParams {
+    /// Longest range a report covers.
+    const MAX_DAYS: i64 = 366;
+
+    fn range(&self) -> (NaiveDate, NaiveDate) {
+        let to = self.to.unwrap_or_else(|| Utc::now().date_naive());
+        let from = self.from.unwrap_or(to - Duration::days(29));
+
+        (from, to)
+    }
+
+    pub fn into_analytics_query(self) -> AnalyticsQuery {
+        let (from, to) = self.range();
+
+        AnalyticsQuery {
+            from,
+            to,
+            post_id: self.post_id,
+        }
+    }
+}
+
+fn validate_analytics_range(params: &V1PostAnalyticsQueryParams) -> Result<(), ValidationError> {
+    let (from, to) = params.range();
+    if from > to {
+        return Err(ValidationError::new("from_after_to"));
+    }
+    if (to - from).num_days() >= V1PostAnalyticsQueryParams::MAX_DAYS {
+        return Err(ValidationError::new("range_too_long"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_macros::debug_handler;
+use axum_valid::Valid;
+use serde_json::json;
+
+use crate::{db::models::search_suggestion::SearchSuggestion, AppState};
+
+use super::validator::V1SuggestQueryParams;
+
+#[debug_handler]
+pub async fn suggest(
+    State(state): State<AppState>,
+    Valid(query): Valid<Query<V1SuggestQueryParams>>,
+) -> impl IntoResponse {
+    let Query(query) = query;
+    let limit = query.limit.unwrap_or(5);
+
+    match SearchSuggestion::find(&state.db_pool, query.q, limit).await {
+        Ok(suggestions) => (StatusCode::OK, Json(json!(suggestions))).into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch suggestions",
+            })),
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1SuggestQueryParams {
+    #[validate(length(min = 2, max = 100))]
+    pub q: String,
+    #[validate(range(min = 1, max = 20))]
+    pub limit: Option<i64>,
+}
@@ -16,12 +16,12 @@ use crate::db::models::user::AdminUserQuery;
 use crate::{
     db::models::{
         category::{Category, NewCategory},
-        post::{NewPost, Post},
+        post::{ContentFormat, NewPost, Post, PostStatus},
         post_comment::{NewPostComment, PostComment},
         tag::{NewTag, Tag},
         user::{AdminCreateUser, User, UserRole},
     },
-    services::auth::AuthSession,
+    services::{auth::AuthSession, search},
     AppState,
 };
 
@@ -106,6 +106,8 @@ pub async fn seed_categories(
             description: None,
             logo_image: None,
             cover_image: None,
+            cover_media_id: None,
+            logo_media_id: None,
         };
 
         match Category::create(&state.db_pool, new_cat).await {
@@ -215,7 +217,6 @@ pub async fn seed_posts(State(state): State<AppState>, _auth: AuthSession) -> im
             let tags_amount = rng.gen_range(1..10);
             let tag_ids: Vec<i32> = tags
                 .choose_multiple(&mut rng, tags_amount)
-                .cloned()
                 .map(|t| t.id)
                 .collect();
             let post_excerpt = l::Words(EN, 1..8).fake::<Vec<String>>().join(" ");
@@ -231,13 +232,20 @@ pub async fn seed_posts(State(state): State<AppState>, _auth: AuthSession) -> im
                 } else {
                     None
                 },
-                is_published,
                 slug: post_slug,
                 featured_image_url: None,
+                featured_media_id: None,
                 category_id,
                 view_count: 0,
                 likes_count: 0,
                 tag_ids,
+                content_format: ContentFormat::Markdown,
+                search_language: search::default_language(),
+                status: if is_published {
+                    PostStatus::Published
+                } else {
+                    PostStatus::Draft
+                },
             };
 
             // println!("{:?}", new_post);
@@ -327,7 +335,6 @@ pub async fn seed_post_comments(
         let posts_amount = rng.gen_range(posts_ratio..posts.len());
         let post_ids: Vec<i32> = posts
             .choose_multiple(&mut rng, posts_amount)
-            .cloned()
             .map(|t| t.id)
             .collect();
 
@@ -344,13 +351,13 @@ pub async fn seed_post_comments(
         }
     }
 
-    return (
+    (
         StatusCode::OK,
         Json(json!({
             "message": "Posts seeded successfully",
         })),
     )
-        .into_response();
+        .into_response()
 }
 
 #[debug_handler]
@@ -395,6 +402,8 @@ pub async fn seed(State(state): State<AppState>, _auth: AuthSession) -> impl Int
             parent_id: None,
             cover_image: None,
             logo_image: None,
+            cover_media_id: None,
+            logo_media_id: None,
         };
 
         match Category::create(&state.db_pool, new_category).await {
@@ -434,7 +443,6 @@ pub async fn seed(State(state): State<AppState>, _auth: AuthSession) -> impl Int
                 let tags_amount = rng.gen_range(1..4);
                 let tag_ids: Vec<i32> = tags
                     .choose_multiple(&mut rng, tags_amount)
-                    .cloned()
                     .map(|t| t.id)
                     .collect();
                 let post_title: String = l::Sentence(EN, 1..2).fake();
@@ -450,13 +458,20 @@ pub async fn seed(State(state): State<AppState>, _auth: AuthSession) -> impl Int
                     } else {
                         None
                     },
-                    is_published: rng.gen_bool(0.5),
                     slug: post_title.to_lowercase().replace(' ', "-"),
                     featured_image_url: None,
+                    featured_media_id: None,
                     category_id,
                     view_count: 0,
                     likes_count: 0,
                     tag_ids,
+                    content_format: ContentFormat::Markdown,
+                    search_language: search::default_language(),
+                    status: if rng.gen_bool(0.5) {
+                        PostStatus::Published
+                    } else {
+                        PostStatus::Draft
+                    },
                 };
 
                 match Post::create(&state.db_pool, new_post).await {
@@ -0,0 +1,263 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_macros::debug_handler;
+use axum_valid::Valid;
+use serde_json::json;
+
+use crate::{
+    db::{
+        errors::DBError,
+        models::series::{Series, SeriesWithPosts},
+    },
+    services::auth::AuthSession,
+    AppState,
+};
+
+use super::validator::{
+    V1AddSeriesPostPayload, V1CreateSeriesPayload, V1RemoveSeriesPostPayload,
+    V1ReorderSeriesPayload, V1UpdateSeriesPayload,
+};
+
+#[debug_handler]
+pub async fn create(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    payload: Valid<Json<V1CreateSeriesPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let new_series = payload.into_inner().0.into_new_series(user.id);
+
+    match Series::create(&state.db_pool, new_series).await {
+        Ok(series) => (StatusCode::CREATED, Json(json!(series))).into_response(),
+        Err(err) if err.is_unique_violation() => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "A series with this slug already exists",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to create series",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn update(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(series_id): Path<i32>,
+    payload: Valid<Json<V1UpdateSeriesPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let update_series = payload.into_inner().0.into_update_series();
+
+    match Series::update(&state.db_pool, series_id, user, update_series).await {
+        Ok(Some(series)) => (StatusCode::OK, Json(json!(series))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Series does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) if err.is_unique_violation() => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "A series with this slug already exists",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to update series",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn delete(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(series_id): Path<i32>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+
+    match Series::delete(&state.db_pool, user, series_id).await {
+        Ok(1) => (
+            StatusCode::OK,
+            Json(json!({ "message": "Series deleted successfully" })),
+        )
+            .into_response(),
+        Ok(0) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Series does not exist",
+            })),
+        )
+            .into_response(),
+        Ok(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "unexpected result",
+                "message": "Internal server error occurred while deleting series",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to delete series",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn list(State(state): State<AppState>, auth: AuthSession) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+
+    match Series::list(&state.db_pool, user).await {
+        Ok(series) => (StatusCode::OK, Json(json!(series))).into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch series",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn find_by_id_or_slug(
+    State(state): State<AppState>,
+    Path(slug_or_id): Path<String>,
+) -> impl IntoResponse {
+    let query = match slug_or_id.parse::<i32>() {
+        Ok(id) => Series::find_by_id_or_slug(&state.db_pool, Some(id), None).await,
+        Err(_) => Series::find_by_id_or_slug(&state.db_pool, None, Some(slug_or_id)).await,
+    };
+
+    match query {
+        Ok(Some(series)) => (StatusCode::OK, Json(json!(series))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "message": "Series not found" })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch series",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn add_post(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(series_id): Path<i32>,
+    payload: Valid<Json<V1AddSeriesPostPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let payload = payload.into_inner().0;
+
+    let result = Series::add_post(
+        &state.db_pool,
+        series_id,
+        user,
+        payload.post_id,
+        payload.position,
+    )
+    .await;
+
+    parts_response(result, "Failed to add post to series")
+}
+
+#[debug_handler]
+pub async fn remove_post(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(series_id): Path<i32>,
+    payload: Valid<Json<V1RemoveSeriesPostPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let post_id = payload.into_inner().0.post_id;
+
+    let result = Series::remove_post(&state.db_pool, series_id, user, post_id).await;
+
+    parts_response(result, "Failed to remove post from series")
+}
+
+#[debug_handler]
+pub async fn reorder(
+    State(state): State<AppState>,
+    auth: AuthSession,
+    Path(series_id): Path<i32>,
+    payload: Valid<Json<V1ReorderSeriesPayload>>,
+) -> impl IntoResponse {
+    let user = auth.user.unwrap();
+    let post_ids = payload.into_inner().0.post_ids;
+
+    let result = Series::reorder(&state.db_pool, series_id, user, post_ids).await;
+
+    parts_response(result, "Failed to reorder series")
+}
+
+fn parts_response(
+    result: Result<Option<SeriesWithPosts>, DBError>,
+    failure_message: &str,
+) -> axum::response::Response {
+    match result {
+        Ok(Some(series)) => (StatusCode::OK, Json(json!(series))).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Series or post does not exist",
+            })),
+        )
+            .into_response(),
+        Err(err) if err.is_unique_violation() => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Post is already part of another series",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": failure_message,
+            })),
+        )
+            .into_response(),
+    }
+}
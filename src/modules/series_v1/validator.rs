@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::db::models::series::{NewSeries, UpdateSeries};
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1CreateSeriesPayload {
+    #[validate(length(min = 1, max = 255))]
+    pub title: String,
+    #[validate(length(min = 1, max = 255))]
+    pub slug: String,
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+    #[validate(length(max = 255))]
+    pub cover_image: Option<String>,
+}
+
+impl V1CreateSeriesPayload {
+    pub fn into_new_series(self, author_id: i32) -> NewSeries {
+        NewSeries {
+            title: self.title,
+            slug: self.slug,
+            description: self.description,
+            cover_image: self.cover_image,
+            author_id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1UpdateSeriesPayload {
+    #[validate(length(min = 1, max = 255))]
+    pub title: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub slug: Option<String>,
+    #[validate(length(max = 1000))]
+    pub description: Option<Option<String>>,
+    #[validate(length(max = 255))]
+    pub cover_image: Option<Option<String>>,
+}
+
+impl V1UpdateSeriesPayload {
+    pub fn into_update_series(self) -> UpdateSeries {
+        UpdateSeries {
+            title: self.title,
+            slug: self.slug,
+            description: self.description,
+            cover_image: self.cover_image,
+            updated_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1AddSeriesPostPayload {
+    pub post_id: i32,
+    #[validate(range(min = 1))]
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1RemoveSeriesPostPayload {
+    pub post_id: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct V1ReorderSeriesPayload {
+    #[validate(length(min = 1, max = 500))]
+    pub post_ids: Vec<i32>,
+}
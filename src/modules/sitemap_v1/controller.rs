@@ -0,0 +1,144 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_macros::debug_handler;
+use serde_json::json;
+
+use crate::{
+    db::models::sitemap::Sitemap,
+    services::{cache, sitemap},
+    AppState,
+};
+
+/// How long a rendered sitemap stays in Redis; post, tag and category changes invalidate it.
+const SITEMAP_CACHE_TTL_SECS: i64 = 3600;
+
+#[debug_handler]
+pub async fn index(State(state): State<AppState>) -> impl IntoResponse {
+    let gzip = sitemap::gzip_enabled();
+    let cache_key = format!("index:{}", gzip);
+
+    if let Some(xml) =
+        cache::get_json::<String>(&state.redis_pool, cache::SITEMAPS, &cache_key).await
+    {
+        return xml_response(xml, false);
+    }
+
+    match Sitemap::pages(&state.db_pool, sitemap::MAX_URLS_PER_SITEMAP).await {
+        Ok(pages) => {
+            let xml = sitemap::render_index(&pages, gzip);
+            cache::set_json(
+                &state.redis_pool,
+                cache::SITEMAPS,
+                &cache_key,
+                &xml,
+                SITEMAP_CACHE_TTL_SECS,
+            )
+            .await;
+            xml_response(xml, false)
+        }
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to build sitemap index",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn sitemap(
+    State(state): State<AppState>,
+    Path(file_name): Path<String>,
+) -> impl IntoResponse {
+    let Some((section, page, gzip)) = sitemap::parse_file_name(&file_name) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Sitemap does not exist",
+            })),
+        )
+            .into_response();
+    };
+    let cache_key = format!("{}:{}", section.name(), page);
+
+    if let Some(xml) =
+        cache::get_json::<String>(&state.redis_pool, cache::SITEMAPS, &cache_key).await
+    {
+        return xml_response(xml, gzip);
+    }
+
+    match Sitemap::entries(&state.db_pool, section, page, sitemap::MAX_URLS_PER_SITEMAP).await {
+        Ok(entries) if entries.is_empty() => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Sitemap does not exist",
+            })),
+        )
+            .into_response(),
+        Ok(entries) => {
+            let xml = sitemap::render_urlset(section, &entries);
+            cache::set_json(
+                &state.redis_pool,
+                cache::SITEMAPS,
+                &cache_key,
+                &xml,
+                SITEMAP_CACHE_TTL_SECS,
+            )
+            .await;
+            xml_response(xml, gzip)
+        }
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to build sitemap",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn robots() -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        sitemap::render_robots(),
+    )
+}
+
+fn xml_response(xml: String, gzip: bool) -> axum::response::Response {
+    if !gzip {
+        return (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+            xml,
+        )
+            .into_response();
+    }
+
+    match sitemap::gzip(&xml) {
+        Ok(body) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/gzip")],
+            body,
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to compress sitemap",
+            })),
+        )
+            .into_response(),
+    }
+}
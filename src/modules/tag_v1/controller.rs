@@ -8,7 +8,11 @@ use axum_macros::debug_handler;
 use axum_valid::Valid;
 use serde_json::json;
 
-use crate::{db::models::tag::Tag, services::auth::AuthSession, AppState};
+use crate::{
+    db::models::tag::Tag,
+    services::{auth::AuthSession, cache},
+    AppState,
+};
 
 use super::validator::{V1CreateTagPayload, V1TagQueryParams, V1UpdateTagPayload};
 
@@ -43,7 +47,10 @@ pub async fn update(
     let update_tag = payload.into_inner().0.into_update_tag();
 
     match Tag::update(&state.db_pool, tag_id, update_tag).await {
-        Ok(Some(tag)) => (StatusCode::OK, Json(json!(tag))).into_response(),
+        Ok(Some(tag)) => {
+            cache::invalidate_posts(&state.redis_pool).await;
+            (StatusCode::OK, Json(json!(tag))).into_response()
+        }
         Ok(None) => (
             StatusCode::NOT_FOUND,
             Json(json!({
@@ -66,15 +73,19 @@ pub async fn update(
 #[debug_handler]
 pub async fn delete(
     State(state): State<AppState>,
-    _auth: AuthSession, // Assuming tag deletion requires authentication
+    auth: AuthSession,
     Path(tag_id): Path<i32>,
 ) -> impl IntoResponse {
-    match Tag::delete(&state.db_pool, tag_id).await {
-        Ok(1) => (
-            StatusCode::OK,
-            Json(json!({ "message": "Tag deleted successfully" })),
-        )
-            .into_response(),
+    let user = auth.user.unwrap();
+    match Tag::delete(&state.db_pool, tag_id, user.id).await {
+        Ok(1) => {
+            cache::invalidate_posts(&state.redis_pool).await;
+            (
+                StatusCode::OK,
+                Json(json!({ "message": "Tag moved to trash" })),
+            )
+                .into_response()
+        }
         Ok(0) => (
             StatusCode::NOT_FOUND,
             Json(json!({
@@ -167,3 +178,44 @@ pub async fn find_with_query(
             .into_response(),
     }
 }
+
+#[debug_handler]
+pub async fn trash(State(state): State<AppState>) -> impl IntoResponse {
+    match Tag::find_deleted(&state.db_pool).await {
+        Ok(tags) => (StatusCode::OK, Json(json!(tags))).into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to fetch deleted tags",
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[debug_handler]
+pub async fn restore(State(state): State<AppState>, Path(tag_id): Path<i32>) -> impl IntoResponse {
+    match Tag::restore(&state.db_pool, tag_id).await {
+        Ok(Some(tag)) => {
+            cache::invalidate_posts(&state.redis_pool).await;
+            (StatusCode::OK, Json(json!(tag))).into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "request failed",
+                "message": "Tag is not in the trash",
+            })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": err.to_string(),
+                "message": "Failed to restore tag",
+            })),
+        )
+            .into_response(),
+    }
+}
@@ -8,7 +8,7 @@ pub struct V1CreateTagPayload {
     #[validate(length(min = 1, max = 255))]
     pub name: String,
     #[validate(length(min = 1, max = 255))]
-    pub slug: String,
+    pub slug: Option<String>,
     #[validate(length(max = 1000))]
     pub description: Option<String>,
 }
@@ -17,7 +17,8 @@ impl V1CreateTagPayload {
     pub fn into_new_tag(self) -> NewTag {
         NewTag {
             name: self.name,
-            slug: self.slug,
+            // An empty slug is generated from the name on insert
+            slug: self.slug.unwrap_or_default(),
             description: self.description,
         }
     }
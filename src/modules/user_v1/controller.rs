@@ -9,7 +9,14 @@ use axum_valid::Valid;
 use serde_json::json;
 
 use super::validator::*;
-use crate::{db::models::user::User, services::auth::AuthSession, AppState};
+use crate::{
+    db::{
+        errors::DBError,
+        models::{pagination::PageRequest, user::User},
+    },
+    services::auth::AuthSession,
+    AppState,
+};
 
 #[debug_handler]
 pub async fn get_profile(auth: AuthSession) -> impl IntoResponse {
@@ -39,6 +46,26 @@ pub async fn update_profile(
             Ok(user) => {
                 return (StatusCode::OK, Json(json!(user))).into_response();
             }
+            Err(err @ DBError::MediaNotFound(_)) => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({
+                        "error": "request failed",
+                        "message": err.to_string(),
+                    })),
+                )
+                    .into_response();
+            }
+            Err(err @ DBError::MediaNotOwned(_)) => {
+                return (
+                    StatusCode::FORBIDDEN,
+                    Json(json!({
+                        "error": "request failed",
+                        "message": err.to_string(),
+                    })),
+                )
+                    .into_response();
+            }
             Err(err) => {
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -142,7 +169,34 @@ pub async fn admin_list(
     State(state): State<AppState>,
     payload: Valid<Json<V1AdminUserQueryParams>>,
 ) -> impl IntoResponse {
-    let query = payload.into_inner().0.into_user_query();
+    let params = payload.into_inner().0;
+    let cursor = params.cursor.clone();
+    let query = params.into_user_query();
+
+    if let Some(cursor) = cursor {
+        // Cursor mode answers with the page and its cursors rather than a bare list
+        let Some(PageRequest::Cursor(cursor)) =
+            PageRequest::from_params(None, Some(&cursor), User::ADMIN_LISTING)
+        else {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": "Invalid cursor",
+                    "message": "The cursor is malformed or from another listing",
+                })),
+            )
+                .into_response();
+        };
+
+        return match User::admin_list_by_cursor(&state.db_pool, query, cursor).await {
+            Ok(users) => (StatusCode::OK, Json(json!(users))).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": err.to_string() })),
+            )
+                .into_response(),
+        };
+    }
 
     match User::admin_list(&state.db_pool, query).await {
         Ok(users) => (StatusCode::OK, Json(json!(users))).into_response(),
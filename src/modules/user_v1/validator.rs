@@ -14,6 +14,8 @@ pub struct V1UpdateProfilePayload {
     pub email: Option<String>,
     #[validate(length(min = 4))]
     pub password: Option<String>,
+    /// Library image to use as avatar.
+    pub avatar_media_id: Option<Option<i32>>,
 }
 
 impl V1UpdateProfilePayload {
@@ -22,6 +24,9 @@ impl V1UpdateProfilePayload {
             name: self.name,
             email: self.email,
             updated_at: chrono::Utc::now().naive_utc(),
+            // Set from the media item
+            avatar: None,
+            avatar_media_id: self.avatar_media_id,
         }
     }
 }
@@ -102,8 +107,11 @@ pub struct AdminChangePassword {
 }
 
 #[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+#[validate(schema(function = "validate_admin_user_cursor"))]
 pub struct V1AdminUserQueryParams {
     pub page_no: Option<i64>,
+    /// Pages by cursor over creation time instead of number; empty for the first page.
+    pub cursor: Option<String>,
     pub email: Option<String>,
     pub name: Option<String>,
     #[validate(custom(function = "validate_role"))]
@@ -130,3 +138,12 @@ impl V1AdminUserQueryParams {
         }
     }
 }
+
+/// Cursor pages are always ordered by creation time.
+fn validate_admin_user_cursor(params: &V1AdminUserQueryParams) -> Result<(), ValidationError> {
+    if params.cursor.is_some() && params.sort_by.is_some() {
+        return Err(ValidationError::new("sort_by_with_cursor"));
+    }
+
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use axum::{
-    extract::State,
+    extract::{DefaultBodyLimit, State},
     http::StatusCode,
     middleware,
     response::{IntoResponse, Json},
@@ -8,13 +8,19 @@ use axum::{
 };
 use axum_login::login_required;
 use serde_json::json;
-use tower_http::trace::TraceLayer;
+use tower_http::{limit::RequestBodyLimitLayer, trace::TraceLayer};
 
 use crate::{
     middlewares::{user_permission, user_status},
-    modules::{category_v1, post_v1, seed_v1, tag_v1},
+    modules::{
+        bookmark_v1, category_v1, feed_v1, import_v1, media_v1, post_v1, search_v1, seed_v1,
+        series_v1, sitemap_v1, tag_v1,
+    },
+};
+use crate::{
+    modules::{post_comment_v1, post_note_v1},
+    services::{auth::AuthBackend, import, media},
 };
-use crate::{modules::post_comment_v1, services::auth::AuthBackend};
 
 use super::{
     modules::{auth_v1, email_verification_v1, forgot_password_v1, user_v1},
@@ -54,27 +60,135 @@ pub fn router() -> Router<AppState> {
         .route("/create", post(post_v1::controller::create))
         .route("/update/:post_id", post(post_v1::controller::update))
         .route("/delete/:post_id", post(post_v1::controller::delete))
+        .route("/trash", get(post_v1::controller::trash))
+        .route("/restore/:post_id", post(post_v1::controller::restore))
+        .route("/schedule/:post_id", post(post_v1::controller::schedule))
+        .route(
+            "/unschedule/:post_id",
+            post(post_v1::controller::unschedule),
+        )
+        .route("/status/:post_id", post(post_v1::controller::change_status))
+        .route(
+            "/status/history/:post_id",
+            get(post_v1::controller::status_history),
+        )
+        .route(
+            "/contributor/add/:post_id",
+            post(post_v1::controller::add_contributor),
+        )
+        .route(
+            "/contributor/remove/:post_id",
+            post(post_v1::controller::remove_contributor),
+        )
+        .route(
+            "/preview/create/:post_id",
+            post(post_v1::controller::create_preview),
+        )
+        .route(
+            "/preview/list/:post_id",
+            get(post_v1::controller::list_previews),
+        )
+        .route(
+            "/preview/revoke/:token_id",
+            post(post_v1::controller::revoke_preview),
+        )
         .route(
             "/list/query",
             post(post_v1::controller::find_posts_with_query),
         )
+        .route("/analytics", get(post_v1::controller::analytics))
         .route_layer(middleware::from_fn(user_permission::author))
+        .merge(
+            Router::new()
+                .route("/like/:post_id", post(post_v1::controller::like))
+                .route("/unlike/:post_id", post(post_v1::controller::unlike))
+                .route(
+                    "/reaction/add/:post_id",
+                    post(post_v1::controller::add_reaction),
+                )
+                .route(
+                    "/reaction/remove/:post_id",
+                    post(post_v1::controller::remove_reaction),
+                ),
+        )
         .route_layer(middleware::from_fn(user_status::only_verified))
         .route_layer(login_required!(AuthBackend))
         .route(
             "/view/:id_or_slug",
             post(post_v1::controller::find_by_id_or_slug),
         )
+        .route("/related/:id_or_slug", post(post_v1::controller::related))
         .route(
             "/list/published",
             post(post_v1::controller::find_published_posts),
         )
+        .route("/list/trending", post(post_v1::controller::trending))
+        .route("/list/popular", post(post_v1::controller::popular))
+        .route("/search", post(post_v1::controller::search))
         .route("/sitemap", post(post_v1::controller::sitemap))
         .route(
             "/track_view/:post_id",
             post(post_v1::controller::track_view),
         );
 
+    let series_v1_routes = Router::new()
+        .route("/create", post(series_v1::controller::create))
+        .route("/update/:series_id", post(series_v1::controller::update))
+        .route("/delete/:series_id", post(series_v1::controller::delete))
+        .route("/list", post(series_v1::controller::list))
+        .route(
+            "/add_post/:series_id",
+            post(series_v1::controller::add_post),
+        )
+        .route(
+            "/remove_post/:series_id",
+            post(series_v1::controller::remove_post),
+        )
+        .route("/reorder/:series_id", post(series_v1::controller::reorder))
+        .route_layer(middleware::from_fn(user_permission::author))
+        .route_layer(middleware::from_fn(user_status::only_verified))
+        .route_layer(login_required!(AuthBackend))
+        .route(
+            "/view/:id_or_slug",
+            post(series_v1::controller::find_by_id_or_slug),
+        );
+
+    let feed_v1_routes = Router::new().route("/:format", get(feed_v1::controller::feed));
+
+    let bookmark_v1_routes = Router::new()
+        .route(
+            "/collection/create",
+            post(bookmark_v1::controller::create_collection),
+        )
+        .route(
+            "/collection/update/:collection_id",
+            post(bookmark_v1::controller::update_collection),
+        )
+        .route(
+            "/collection/delete/:collection_id",
+            post(bookmark_v1::controller::delete_collection),
+        )
+        .route(
+            "/collection/list",
+            post(bookmark_v1::controller::list_collections),
+        )
+        .route("/add", post(bookmark_v1::controller::add))
+        .route(
+            "/remove/:collection_id",
+            post(bookmark_v1::controller::remove),
+        )
+        .route(
+            "/read/:collection_id",
+            post(bookmark_v1::controller::mark_read),
+        )
+        .route(
+            "/reorder/:collection_id",
+            post(bookmark_v1::controller::reorder),
+        )
+        .route("/list", post(bookmark_v1::controller::list))
+        .route_layer(middleware::from_fn(user_status::only_verified))
+        .route_layer(login_required!(AuthBackend));
+
     let post_comment_v1_routes = Router::new()
         .route("/list", get(post_comment_v1::controller::list_all))
         .route(
@@ -91,6 +205,16 @@ pub fn router() -> Router<AppState> {
             "/delete/:comment_id",
             post(post_comment_v1::controller::delete),
         )
+        .route("/trash", get(post_comment_v1::controller::trash))
+        .route(
+            "/restore/:comment_id",
+            post(post_comment_v1::controller::restore),
+        )
+        .route("/like/:comment_id", post(post_comment_v1::controller::like))
+        .route(
+            "/unlike/:comment_id",
+            post(post_comment_v1::controller::unlike),
+        )
         .route(
             "/list/user/:user_id",
             get(post_comment_v1::controller::list_by_user),
@@ -102,6 +226,27 @@ pub fn router() -> Router<AppState> {
             get(post_comment_v1::controller::list_by_post),
         );
 
+    let post_note_v1_routes = Router::new()
+        .route("/create", post(post_note_v1::controller::create))
+        .route("/reply/:note_id", post(post_note_v1::controller::reply))
+        .route("/list/:post_id", get(post_note_v1::controller::list))
+        .route("/resolve/:note_id", post(post_note_v1::controller::resolve))
+        .route(
+            "/unresolve/:note_id",
+            post(post_note_v1::controller::unresolve),
+        )
+        .route("/delete/:note_id", post(post_note_v1::controller::delete))
+        .route_layer(middleware::from_fn(user_status::only_verified))
+        .route_layer(login_required!(AuthBackend));
+
+    let media_v1_routes = Router::new()
+        .route("/list", get(media_v1::controller::list))
+        .route("/view/:media_id", get(media_v1::controller::find_by_id))
+        .route("/update/:media_id", post(media_v1::controller::update))
+        .route("/delete/:media_id", post(media_v1::controller::delete))
+        .route_layer(middleware::from_fn(user_status::only_verified))
+        .route_layer(login_required!(AuthBackend));
+
     let category_v1_routes = Router::new()
         .route("/create", post(category_v1::controller::create))
         .route(
@@ -112,6 +257,11 @@ pub fn router() -> Router<AppState> {
             "/delete/:category_id",
             post(category_v1::controller::delete),
         )
+        .route("/trash", get(category_v1::controller::trash))
+        .route(
+            "/restore/:category_id",
+            post(category_v1::controller::restore),
+        )
         .route_layer(middleware::from_fn(user_permission::admin))
         .route_layer(middleware::from_fn(user_status::only_verified))
         .route_layer(login_required!(AuthBackend))
@@ -125,6 +275,8 @@ pub fn router() -> Router<AppState> {
         .route("/create", post(tag_v1::controller::create))
         .route("/update/:tag_id", post(tag_v1::controller::update))
         .route("/delete/:tag_id", post(tag_v1::controller::delete))
+        .route("/trash", get(tag_v1::controller::trash))
+        .route("/restore/:tag_id", post(tag_v1::controller::restore))
         .route("/view/:tag_id", get(tag_v1::controller::find_by_id))
         .route("/list/query", get(tag_v1::controller::find_with_query))
         .route_layer(middleware::from_fn(user_permission::admin))
@@ -132,6 +284,8 @@ pub fn router() -> Router<AppState> {
         .route_layer(login_required!(AuthBackend))
         .route("/list", get(tag_v1::controller::find_all));
 
+    let search_v1_routes = Router::new().route("/suggest", get(search_v1::controller::suggest));
+
     let admin_user_v1_routes = Router::new()
         .route("/list", post(user_v1::controller::admin_list))
         .route("/view/:user_id", get(user_v1::controller::admin_view))
@@ -161,20 +315,58 @@ pub fn router() -> Router<AppState> {
 
     Router::new()
         .route("/", routing::get(handler))
+        .route("/sitemap.xml", get(sitemap_v1::controller::index))
+        .route("/sitemaps/:file_name", get(sitemap_v1::controller::sitemap))
+        .route("/robots.txt", get(sitemap_v1::controller::robots))
         .nest("/auth/v1", auth_v1_routes)
         .nest("/user/v1", user_v1_routes)
         .nest("/email_verification/v1", email_verification_v1_routes)
         .nest("/forgot_password/v1", forgot_password_v1_routes)
         .nest("/post/v1", post_v1_routes)
         .nest("/post/comment/v1", post_comment_v1_routes)
+        .nest("/post/note/v1", post_note_v1_routes)
+        .nest("/media/v1", media_v1_routes)
+        .nest("/series/v1", series_v1_routes)
+        .nest("/bookmark/v1", bookmark_v1_routes)
+        .nest("/feed/v1", feed_v1_routes)
         .nest("/category/v1", category_v1_routes)
         .nest("/tag/v1", tag_v1_routes)
+        .nest("/search/v1", search_v1_routes)
         .nest("/admin/user/v1", admin_user_v1_routes)
         // .nest("/super_admin/v1", super_admin_routes)
         .nest("/admin/seed/v1", seed_routes)
         .layer(TraceLayer::new_for_http())
 }
 
+/// Routes that take bodies above the global request size limit. They get their own limit, so
+/// merge them after that layer is applied to `router`.
+pub fn upload_router() -> Router<AppState> {
+    // Room for the multipart framing and the text fields around the file
+    let body_limit = media::max_upload_bytes() + 64 * 1024;
+
+    let media_v1_routes = Router::new()
+        .route("/upload", post(media_v1::controller::upload))
+        .route_layer(middleware::from_fn(user_status::only_verified))
+        .route_layer(login_required!(AuthBackend))
+        .layer(DefaultBodyLimit::max(body_limit))
+        .layer(RequestBodyLimitLayer::new(body_limit));
+
+    let import_body_limit = import::max_upload_bytes() + 64 * 1024;
+    let import_v1_routes = Router::new()
+        .route("/markdown", post(import_v1::controller::markdown))
+        .route("/wxr", post(import_v1::controller::wxr))
+        .route_layer(middleware::from_fn(user_permission::admin))
+        .route_layer(middleware::from_fn(user_status::only_verified))
+        .route_layer(login_required!(AuthBackend))
+        .layer(DefaultBodyLimit::max(import_body_limit))
+        .layer(RequestBodyLimitLayer::new(import_body_limit));
+
+    Router::new()
+        .nest("/media/v1", media_v1_routes)
+        .nest("/admin/import/v1", import_v1_routes)
+        .layer(TraceLayer::new_for_http())
+}
+
 async fn handler(s: State<AppState>) -> impl IntoResponse {
     println!("{:?}", s.db_pool.status());
     (StatusCode::OK, Json(json!({"message": "success"})))
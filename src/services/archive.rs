@@ -0,0 +1,295 @@
+//! Portable archives of a blog's content: a gzipped tarball with a `manifest.json`, a JSON
+//! lines file per table under `tables/` and the media files under `media/`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::{BufRead, BufReader, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use chrono::{NaiveDateTime, Utc};
+use deadpool_diesel::postgres::Pool;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::media::{storage::MediaStorage, MediaError};
+use crate::db::{
+    errors::DBError,
+    models::archive::{Archive, ArchiveRestore, ArchiveRows, RestoreReport, TABLES},
+};
+
+const FORMAT: &str = "ruxlog-archive";
+
+/// Version of the archive layout. Bumped when an archive written now couldn't be read the way
+/// older ones are; archives of a newer version than this are refused.
+pub const VERSION: u32 = 1;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ArchiveError {
+    #[error("Failed to read or write the archive: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid archive data: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    Database(#[from] DBError),
+
+    #[error("{0}")]
+    Media(#[from] MediaError),
+
+    #[error("Not a ruxlog archive")]
+    NotArchive,
+
+    #[error("Archive version {0} is newer than this server supports ({VERSION})")]
+    UnsupportedVersion(u32),
+
+    #[error("The database already has content, archives are only imported into an empty one")]
+    NotEmpty,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: NaiveDateTime,
+    pub includes_passwords: bool,
+    /// Rows per table.
+    pub rows: BTreeMap<String, usize>,
+    pub media_files: usize,
+    /// Files of media that were missing from storage when exporting.
+    #[serde(default)]
+    pub missing_media: Vec<String>,
+}
+
+/// Writes every table of content and the media files to a new archive at `path`. Password
+/// hashes are only included when `include_passwords` is set.
+pub async fn export(
+    pool: &Pool,
+    storage: &dyn MediaStorage,
+    path: &Path,
+    include_passwords: bool,
+) -> Result<Manifest, ArchiveError> {
+    let tables = Archive::export(pool, include_passwords).await?;
+
+    let mut builder =
+        tar::Builder::new(GzEncoder::new(File::create(path)?, Compression::default()));
+    let mut manifest = Manifest {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        exported_at: Utc::now().naive_utc(),
+        includes_passwords: include_passwords,
+        rows: BTreeMap::new(),
+        media_files: 0,
+        missing_media: vec![],
+    };
+
+    for table in &tables {
+        let mut lines = Vec::new();
+        for row in &table.rows {
+            serde_json::to_writer(&mut lines, row)?;
+            lines.push(b'\n');
+        }
+        append(
+            &mut builder,
+            &format!("tables/{}.jsonl", table.table),
+            &lines,
+        )?;
+        manifest
+            .rows
+            .insert(table.table.to_string(), table.rows.len());
+    }
+
+    let media = tables.iter().find(|table| table.table == "media");
+    for (key, _) in media.map(media_files).unwrap_or_default() {
+        match storage.get(&key).await {
+            Ok(bytes) => {
+                append(&mut builder, &format!("media/{}", key), &bytes)?;
+                manifest.media_files += 1;
+            }
+            Err(err) => {
+                tracing::warn!("Media file {} not exported: {}", key, err);
+                manifest.missing_media.push(key);
+            }
+        }
+    }
+
+    append(
+        &mut builder,
+        "manifest.json",
+        &serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    builder.into_inner()?.finish()?.flush()?;
+
+    Ok(manifest)
+}
+
+/// Restores the archive at `path` into an empty database, storing its media files in
+/// `storage`. Users exported without their password hash get one that matches nothing.
+pub async fn import(
+    pool: &Pool,
+    storage: &dyn MediaStorage,
+    path: &Path,
+) -> Result<RestoreReport, ArchiveError> {
+    let dir = unpack(path)?;
+    let result = restore(pool, storage, &dir).await;
+    if let Err(err) = fs::remove_dir_all(&dir) {
+        tracing::warn!("Failed to remove {}: {}", dir.display(), err);
+    }
+
+    result
+}
+
+async fn restore(
+    pool: &Pool,
+    storage: &dyn MediaStorage,
+    dir: &Path,
+) -> Result<RestoreReport, ArchiveError> {
+    let manifest: Manifest = match fs::read(dir.join("manifest.json")) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|_| ArchiveError::NotArchive)?,
+        Err(_) => return Err(ArchiveError::NotArchive),
+    };
+    if manifest.format != FORMAT {
+        return Err(ArchiveError::NotArchive);
+    }
+    if manifest.version > VERSION {
+        return Err(ArchiveError::UnsupportedVersion(manifest.version));
+    }
+    if !Archive::is_empty(pool).await? {
+        return Err(ArchiveError::NotEmpty);
+    }
+
+    let mut tables = vec![];
+    for table in TABLES {
+        let file = match File::open(dir.join("tables").join(format!("{}.jsonl", table.name))) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+        let mut rows = vec![];
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                rows.push(serde_json::from_str::<Map<String, Value>>(&line)?);
+            }
+        }
+        tables.push(ArchiveRows {
+            table: table.name,
+            rows,
+        });
+    }
+
+    let files = tables
+        .iter()
+        .find(|table| table.table == "media")
+        .map(media_files)
+        .unwrap_or_default();
+    let mut media_urls = HashMap::new();
+    for (key, old_url) in &files {
+        if let Some(old_url) = old_url {
+            media_urls.insert(old_url.clone(), storage.url(key));
+        }
+    }
+
+    let mut stored = vec![];
+    let mut warnings = vec![];
+    for (key, _) in &files {
+        let Some(file) = media_path(dir, key) else {
+            warnings.push(format!("media file {}: invalid key, skipped", key));
+            continue;
+        };
+        let bytes = match fs::read(file) {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                warnings.push(format!("media file {}: not in the archive", key));
+                continue;
+            }
+        };
+        let content_type = image::guess_format(&bytes)
+            .map(|format| format.to_mime_type())
+            .unwrap_or("application/octet-stream");
+
+        if let Err(err) = storage.put(key, bytes, content_type).await {
+            super::media::remove(storage, &stored).await;
+            return Err(err.into());
+        }
+        stored.push(key.clone());
+    }
+
+    match Archive::restore(pool, tables, media_urls).await {
+        Ok(ArchiveRestore::Restored(mut report)) => {
+            report.warnings.extend(warnings);
+            Ok(report)
+        }
+        Ok(ArchiveRestore::NotEmpty) => {
+            super::media::remove(storage, &stored).await;
+            Err(ArchiveError::NotEmpty)
+        }
+        Err(err) => {
+            super::media::remove(storage, &stored).await;
+            Err(err.into())
+        }
+    }
+}
+
+/// Storage keys of the files of exported media rows, each with the URL it was published at.
+fn media_files(media: &ArchiveRows) -> Vec<(String, Option<String>)> {
+    let mut files = vec![];
+    let file = |value: &Value| {
+        value["storage_key"]
+            .as_str()
+            .map(|key| (key.to_string(), value["url"].as_str().map(str::to_string)))
+    };
+
+    for row in &media.rows {
+        let row = Value::Object(row.clone());
+        files.extend(file(&row));
+        if let Some(variants) = row["variants"].as_array() {
+            files.extend(variants.iter().filter_map(file));
+        }
+    }
+
+    files
+}
+
+/// Where a media file is in the unpacked archive, unless its key would lead outside.
+fn media_path(dir: &Path, key: &str) -> Option<PathBuf> {
+    Path::new(key)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        .then(|| dir.join("media").join(key))
+}
+
+fn append<W: Write>(
+    builder: &mut tar::Builder<W>,
+    name: &str,
+    bytes: &[u8],
+) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp() as u64);
+    header.set_cksum();
+
+    builder.append_data(&mut header, name, bytes)
+}
+
+/// Unpacks the archive into a new temporary directory.
+fn unpack(path: &Path) -> Result<PathBuf, ArchiveError> {
+    let dir = std::env::temp_dir().join(format!(
+        "ruxlog-archive-{:016x}",
+        rand::thread_rng().gen::<u64>()
+    ));
+    fs::create_dir_all(&dir)?;
+
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+    if let Err(err) = archive.unpack(&dir) {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(err.into());
+    }
+
+    Ok(dir)
+}
@@ -0,0 +1,94 @@
+use fred::{
+    error::RedisError,
+    prelude::{KeysInterface, RedisPool},
+    types::Expiration,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+pub const RELATED_POSTS: &str = "related_posts";
+pub const FEEDS: &str = "feeds";
+pub const SITEMAPS: &str = "sitemaps";
+
+/// Namespaces built from published posts, invalidated together whenever posts change.
+const POST_NAMESPACES: [&str; 3] = [RELATED_POSTS, FEEDS, SITEMAPS];
+
+async fn generation(redis_pool: &RedisPool, namespace: &str) -> Result<i64, RedisError> {
+    let generation: Option<i64> = redis_pool
+        .get(format!("cache:generation:{}", namespace))
+        .await?;
+    Ok(generation.unwrap_or(0))
+}
+
+/// Cached results are grouped into namespaces, each with a generation counter that is part of
+/// every key. Bumping the generation invalidates the whole namespace at once; the stale keys
+/// simply expire.
+async fn versioned_key(
+    redis_pool: &RedisPool,
+    namespace: &str,
+    key: &str,
+) -> Result<String, RedisError> {
+    let generation = generation(redis_pool, namespace).await?;
+    Ok(format!("cache:{}:{}:{}", namespace, generation, key))
+}
+
+/// Returns the cached value, treating Redis errors and undecodable entries as a miss.
+pub async fn get_json<T: DeserializeOwned>(
+    redis_pool: &RedisPool,
+    namespace: &str,
+    key: &str,
+) -> Option<T> {
+    let lookup = async {
+        let key = versioned_key(redis_pool, namespace, key).await?;
+        redis_pool.get::<Option<String>, _>(key).await
+    };
+
+    match lookup.await {
+        Ok(value) => value.and_then(|value| serde_json::from_str(&value).ok()),
+        Err(err) => {
+            tracing::warn!("Cache read failed for {}:{}: {}", namespace, key, err);
+            None
+        }
+    }
+}
+
+/// Stores `value` for `ttl_secs`; failures are only logged since the cache is optional.
+pub async fn set_json<T: Serialize>(
+    redis_pool: &RedisPool,
+    namespace: &str,
+    key: &str,
+    value: &T,
+    ttl_secs: i64,
+) {
+    let Ok(value) = serde_json::to_string(value) else {
+        return;
+    };
+
+    let store = async {
+        let key = versioned_key(redis_pool, namespace, key).await?;
+        redis_pool
+            .set::<(), _, _>(key, value, Some(Expiration::EX(ttl_secs)), None, false)
+            .await
+    };
+
+    if let Err(err) = store.await {
+        tracing::warn!("Cache write failed for {}:{}: {}", namespace, key, err);
+    }
+}
+
+/// Invalidates every entry cached in `namespace`.
+pub async fn invalidate(redis_pool: &RedisPool, namespace: &str) {
+    let bumped: Result<i64, RedisError> = redis_pool
+        .incr(format!("cache:generation:{}", namespace))
+        .await;
+
+    if let Err(err) = bumped {
+        tracing::warn!("Cache invalidation failed for {}: {}", namespace, err);
+    }
+}
+
+/// Invalidates everything cached from published posts.
+pub async fn invalidate_posts(redis_pool: &RedisPool) {
+    for namespace in POST_NAMESPACES {
+        invalidate(redis_pool, namespace).await;
+    }
+}
@@ -0,0 +1,368 @@
+use std::{collections::HashSet, sync::OnceLock};
+
+use pulldown_cmark::{html, CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use regex::{Captures, Regex};
+use syntect::{
+    html::{ClassStyle, ClassedHTMLGenerator},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+
+use crate::db::models::post::{ContentFormat, PostHeading};
+
+const WORDS_PER_MINUTE: usize = 200;
+const EXCERPT_LENGTH: usize = 280;
+
+const BLOCK_TAGS: [&str; 20] = [
+    "p",
+    "br",
+    "div",
+    "li",
+    "ul",
+    "ol",
+    "pre",
+    "blockquote",
+    "table",
+    "tr",
+    "td",
+    "th",
+    "hr",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "sup",
+];
+
+pub struct RenderedContent {
+    pub html: String,
+    pub outline: Vec<PostHeading>,
+    pub word_count: usize,
+    pub excerpt: Option<String>,
+}
+
+impl RenderedContent {
+    /// Estimated reading time in whole minutes, rounded up.
+    pub fn reading_time(&self) -> usize {
+        self.word_count.div_ceil(WORDS_PER_MINUTE)
+    }
+}
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn heading_regex() -> &'static Regex {
+    static HEADING: OnceLock<Regex> = OnceLock::new();
+    HEADING.get_or_init(|| Regex::new(r#"(?s)<h([1-6])((?:\s[^>]*)?)>(.*?)</h[1-6]>"#).unwrap())
+}
+
+fn id_regex() -> &'static Regex {
+    static ID: OnceLock<Regex> = OnceLock::new();
+    ID.get_or_init(|| Regex::new(r#"\sid="([^"]*)""#).unwrap())
+}
+
+fn paragraph_regex() -> &'static Regex {
+    static PARAGRAPH: OnceLock<Regex> = OnceLock::new();
+    PARAGRAPH.get_or_init(|| Regex::new(r#"(?s)<p(?:\s[^>]*)?>(.*?)</p>"#).unwrap())
+}
+
+/// Renders post content into sanitized HTML along with its outline and reading stats.
+///
+/// Markdown gets tables, footnotes and class based syntax highlighting (`hl-*` classes, so
+/// the frontend picks the theme). Every format goes through the same sanitizer, so raw HTML
+/// in any of them can never carry scripts or event handlers. Headings without an id get a
+/// unique anchor afterwards, which is what the outline links to.
+pub fn render(content: &str, format: ContentFormat) -> RenderedContent {
+    let html = match format {
+        ContentFormat::Markdown => render_markdown(content),
+        ContentFormat::Html => content.to_string(),
+        ContentFormat::Plain => render_plain(content),
+    };
+
+    let (html, outline) = anchor_headings(&sanitize(&html));
+    let word_count = html_to_text(&html).split_whitespace().count();
+    let excerpt = paragraph_regex()
+        .captures_iter(&html)
+        .map(|caps| collapse_whitespace(&html_to_text(&caps[1])))
+        .find(|text| !text.is_empty())
+        .map(|text| truncate_words(&text, EXCERPT_LENGTH));
+
+    RenderedContent {
+        html,
+        outline,
+        word_count,
+        excerpt,
+    }
+}
+
+fn render_markdown(content: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_HEADING_ATTRIBUTES;
+
+    let mut events = Vec::new();
+    let mut code_block: Option<(String, String)> = None;
+
+    for event in Parser::new_ext(content, options) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code_block = Some((lang, String::new()));
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((lang, source)) = code_block.take() {
+                    events.push(Event::Html(highlight_code(&lang, &source).into()));
+                }
+            }
+            Event::Text(text) if code_block.is_some() => {
+                if let Some((_, source)) = code_block.as_mut() {
+                    source.push_str(&text);
+                }
+            }
+            event => events.push(event),
+        }
+    }
+
+    let mut html = String::new();
+    html::push_html(&mut html, events.into_iter());
+    html
+}
+
+/// Gives every heading a unique id and collects the document outline.
+///
+/// Ids set in the content are kept, and claim their anchors before generated ones; repeats
+/// of either get a numeric suffix.
+fn anchor_headings(html: &str) -> (String, Vec<PostHeading>) {
+    let mut used: HashSet<String> = HashSet::new();
+    let explicit: Vec<Option<String>> = heading_regex()
+        .captures_iter(html)
+        .map(|caps| {
+            id_regex()
+                .captures(&caps[2])
+                .map(|id| unique_anchor(&mut used, &id[1]))
+        })
+        .collect();
+    let mut explicit = explicit.into_iter();
+
+    let mut outline = Vec::new();
+    let html = heading_regex().replace_all(html, |caps: &Captures| {
+        let level = caps[1].parse().unwrap_or(1);
+        let text = collapse_whitespace(&html_to_text(&caps[3]));
+
+        let (attributes, anchor) = match explicit.next().flatten() {
+            Some(anchor) => {
+                let attributes =
+                    id_regex().replace(&caps[2], |_: &Captures| format!(" id=\"{}\"", anchor));
+                (attributes.into_owned(), anchor)
+            }
+            None => {
+                let anchor = unique_anchor(&mut used, &slugify(&text));
+                (format!(" id=\"{}\"{}", anchor, &caps[2]), anchor)
+            }
+        };
+
+        let heading = format!("<h{level}{attributes}>{}</h{level}>", &caps[3]);
+        outline.push(PostHeading {
+            level,
+            text,
+            anchor,
+        });
+        heading
+    });
+
+    (html.into_owned(), outline)
+}
+
+fn slugify(text: &str) -> String {
+    let mut base = String::new();
+    for c in text.trim().to_lowercase().chars() {
+        if c.is_alphanumeric() {
+            base.push(c);
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !base.ends_with('-') {
+            base.push('-');
+        }
+    }
+    match base.trim_matches('-') {
+        "" => "section".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+fn unique_anchor(used: &mut HashSet<String>, base: &str) -> String {
+    let mut anchor = base.to_string();
+    let mut suffix = 0;
+    while !used.insert(anchor.clone()) {
+        suffix += 1;
+        anchor = format!("{}-{}", base, suffix);
+    }
+    anchor
+}
+
+fn highlight_code(lang: &str, source: &str) -> String {
+    let syntax_set = syntax_set();
+    let syntax = syntax_set
+        .find_syntax_by_token(lang)
+        .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(
+        syntax,
+        syntax_set,
+        ClassStyle::SpacedPrefixed { prefix: "hl-" },
+    );
+
+    let mut highlighted = true;
+    for line in LinesWithEndings::from(source) {
+        if generator
+            .parse_html_for_line_which_includes_newline(line)
+            .is_err()
+        {
+            highlighted = false;
+            break;
+        }
+    }
+
+    let code = if highlighted {
+        generator.finalize()
+    } else {
+        escape_html(source)
+    };
+
+    if lang.is_empty() {
+        format!("<pre class=\"code\"><code>{}</code></pre>\n", code)
+    } else {
+        format!(
+            "<pre class=\"code\"><code class=\"language-{}\">{}</code></pre>\n",
+            escape_html(lang),
+            code
+        )
+    }
+}
+
+fn render_plain(content: &str) -> String {
+    content
+        .replace("\r\n", "\n")
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| {
+            format!(
+                "<p>{}</p>\n",
+                escape_html(paragraph).replace('\n', "<br>\n")
+            )
+        })
+        .collect()
+}
+
+/// Extracts the readable text from sanitized HTML, separating block elements with spaces.
+fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut tag: Option<String> = None;
+    let mut quote: Option<char> = None;
+
+    for c in html.chars() {
+        match tag.as_mut() {
+            None if c == '<' => tag = Some(String::new()),
+            None => text.push(c),
+            Some(_) if quote.is_some() => {
+                if quote == Some(c) {
+                    quote = None;
+                }
+            }
+            Some(_) if c == '"' || c == '\'' => quote = Some(c),
+            Some(name) if c == '>' => {
+                let name = name
+                    .trim_start_matches('/')
+                    .split(|c: char| c.is_whitespace() || c == '/')
+                    .next()
+                    .unwrap_or("")
+                    .to_lowercase();
+                if BLOCK_TAGS.contains(&name.as_str()) {
+                    text.push(' ');
+                }
+                tag = None;
+            }
+            Some(name) => name.push(c),
+        }
+    }
+
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn truncate_words(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let cut: String = text.chars().take(max_chars).collect();
+    let cut = match cut.rfind(' ') {
+        Some(index) => &cut[..index],
+        None => &cut,
+    };
+    format!(
+        "{}…",
+        cut.trim_end_matches(|c: char| c.is_ascii_punctuation())
+    )
+}
+
+/// Escapes text for HTML and XML, in element content as well as quoted attributes.
+pub(crate) fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn sanitize(html: &str) -> String {
+    ammonia::Builder::default()
+        .add_generic_attributes(["class"])
+        .add_tag_attributes("h1", ["id"])
+        .add_tag_attributes("h2", ["id"])
+        .add_tag_attributes("h3", ["id"])
+        .add_tag_attributes("h4", ["id"])
+        .add_tag_attributes("h5", ["id"])
+        .add_tag_attributes("h6", ["id"])
+        .add_tag_attributes("div", ["id"])
+        .add_tag_attributes("th", ["style"])
+        .add_tag_attributes("td", ["style"])
+        .attribute_filter(|_element, attribute, value| {
+            // Table cells only keep the alignment emitted by the Markdown renderer
+            if attribute == "style"
+                && !matches!(
+                    value.trim().trim_end_matches(';'),
+                    "text-align: left" | "text-align: center" | "text-align: right"
+                )
+            {
+                return None;
+            }
+            Some(value.into())
+        })
+        .clean(html)
+        .to_string()
+}
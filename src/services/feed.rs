@@ -0,0 +1,284 @@
+use chrono::{NaiveDateTime, SecondsFormat};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::db::models::{
+    post::{PostFeed, PostWithRelations},
+    post_contributor::ContributorRole,
+};
+use crate::services::{content_renderer::escape_html, site};
+
+/// Number of posts in every feed.
+pub const FEED_SIZE: i64 = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedFormat {
+    Rss,
+    Atom,
+    Json,
+}
+
+impl FeedFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            FeedFormat::Rss => "rss",
+            FeedFormat::Atom => "atom",
+            FeedFormat::Json => "json",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            FeedFormat::Rss => "application/rss+xml; charset=utf-8",
+            FeedFormat::Atom => "application/atom+xml; charset=utf-8",
+            FeedFormat::Json => "application/feed+json; charset=utf-8",
+        }
+    }
+}
+
+/// Whether entries carry the rendered post or only its excerpt.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedContent {
+    #[default]
+    Summary,
+    Full,
+}
+
+/// A feed document ready to be served, along with its validators for conditional GETs.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RenderedFeed {
+    pub body: String,
+    pub etag: String,
+    pub last_modified: NaiveDateTime,
+}
+
+struct FeedMeta {
+    title: String,
+    description: String,
+    home_url: String,
+    self_url: String,
+    updated: NaiveDateTime,
+}
+
+/// Renders `feed` in the requested format. `self_path` is the canonical path and query of the
+/// feed, used for its self link.
+pub fn render(
+    format: FeedFormat,
+    content: FeedContent,
+    feed: &PostFeed,
+    self_path: &str,
+) -> RenderedFeed {
+    let title = match &feed.scope_name {
+        Some(scope_name) => format!("{} - {}", site::title(), scope_name),
+        None => site::title(),
+    };
+    let meta = FeedMeta {
+        title,
+        description: site::description(),
+        home_url: site::url(),
+        self_url: format!("{}{}", site::api_url(), self_path),
+        updated: feed.last_modified,
+    };
+
+    let body = match format {
+        FeedFormat::Rss => render_rss(&meta, content, &feed.posts),
+        FeedFormat::Atom => render_atom(&meta, content, &feed.posts),
+        FeedFormat::Json => render_json(&meta, content, &feed.posts),
+    };
+
+    RenderedFeed {
+        etag: format!("\"{:x}\"", md5::compute(&body)),
+        body,
+        last_modified: meta.updated,
+    }
+}
+
+fn render_rss(meta: &FeedMeta, content: FeedContent, posts: &[PostWithRelations]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<rss version=\"2.0\" \
+         xmlns:atom=\"http://www.w3.org/2005/Atom\" \
+         xmlns:content=\"http://purl.org/rss/1.0/modules/content/\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n<channel>\n",
+    );
+    xml.push_str(&format!(
+        "<title>{}</title>\n<link>{}</link>\n<description>{}</description>\n\
+         <lastBuildDate>{}</lastBuildDate>\n\
+         <atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\"/>\n",
+        escape_html(&meta.title),
+        escape_html(&meta.home_url),
+        escape_html(&meta.description),
+        meta.updated.and_utc().to_rfc2822(),
+        escape_html(&meta.self_url),
+    ));
+
+    for entry in posts {
+        let url = site::post_url(&entry.post.slug);
+        xml.push_str("<item>\n");
+        xml.push_str(&format!(
+            "<title>{}</title>\n<link>{}</link>\n<guid isPermaLink=\"true\">{}</guid>\n\
+             <pubDate>{}</pubDate>\n",
+            escape_html(&entry.post.title),
+            escape_html(&url),
+            escape_html(&url),
+            published_at(entry).and_utc().to_rfc2822(),
+        ));
+        for author in authors(entry) {
+            xml.push_str(&format!(
+                "<dc:creator>{}</dc:creator>\n",
+                escape_html(author)
+            ));
+        }
+        for category in categories(entry) {
+            xml.push_str(&format!("<category>{}</category>\n", escape_html(category)));
+        }
+        if let Some(summary) = &entry.post.excerpt {
+            xml.push_str(&format!(
+                "<description>{}</description>\n",
+                escape_html(summary)
+            ));
+        }
+        if let Some(html) = full_content(content, entry) {
+            xml.push_str(&format!(
+                "<content:encoded>{}</content:encoded>\n",
+                escape_html(html)
+            ));
+        }
+        xml.push_str("</item>\n");
+    }
+
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+fn render_atom(meta: &FeedMeta, content: FeedContent, posts: &[PostWithRelations]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n",
+    );
+    xml.push_str(&format!(
+        "<id>{}</id>\n<title>{}</title>\n<subtitle>{}</subtitle>\n<updated>{}</updated>\n\
+         <link href=\"{}\"/>\n<link href=\"{}\" rel=\"self\" type=\"application/atom+xml\"/>\n",
+        escape_html(&meta.self_url),
+        escape_html(&meta.title),
+        escape_html(&meta.description),
+        rfc3339(meta.updated),
+        escape_html(&meta.home_url),
+        escape_html(&meta.self_url),
+    ));
+
+    for entry in posts {
+        let url = site::post_url(&entry.post.slug);
+        xml.push_str("<entry>\n");
+        xml.push_str(&format!(
+            "<id>{}</id>\n<title>{}</title>\n<link href=\"{}\"/>\n\
+             <published>{}</published>\n<updated>{}</updated>\n",
+            escape_html(&url),
+            escape_html(&entry.post.title),
+            escape_html(&url),
+            rfc3339(published_at(entry)),
+            rfc3339(entry.post.updated_at),
+        ));
+        for author in authors(entry) {
+            xml.push_str(&format!(
+                "<author><name>{}</name></author>\n",
+                escape_html(author)
+            ));
+        }
+        for category in categories(entry) {
+            xml.push_str(&format!("<category term=\"{}\"/>\n", escape_html(category)));
+        }
+        if let Some(summary) = &entry.post.excerpt {
+            xml.push_str(&format!("<summary>{}</summary>\n", escape_html(summary)));
+        }
+        if let Some(html) = full_content(content, entry) {
+            xml.push_str(&format!(
+                "<content type=\"html\">{}</content>\n",
+                escape_html(html)
+            ));
+        }
+        xml.push_str("</entry>\n");
+    }
+
+    xml.push_str("</feed>\n");
+    xml
+}
+
+fn render_json(meta: &FeedMeta, content: FeedContent, posts: &[PostWithRelations]) -> String {
+    let items: Vec<serde_json::Value> = posts
+        .iter()
+        .map(|entry| {
+            let url = site::post_url(&entry.post.slug);
+            let mut item = json!({
+                "id": url,
+                "url": url,
+                "title": entry.post.title,
+                "date_published": rfc3339(published_at(entry)),
+                "date_modified": rfc3339(entry.post.updated_at),
+                "authors": authors(entry)
+                    .map(|name| json!({ "name": name }))
+                    .collect::<Vec<_>>(),
+                "tags": categories(entry).collect::<Vec<_>>(),
+            });
+            if let Some(summary) = &entry.post.excerpt {
+                item["summary"] = json!(summary);
+            }
+            if let Some(image) = &entry.post.featured_image_url {
+                item["image"] = json!(image);
+            }
+            // JSON Feed requires some content on every item
+            item["content_html"] = match full_content(content, entry) {
+                Some(html) => json!(html),
+                None => json!(escape_html(
+                    entry.post.excerpt.as_deref().unwrap_or_default()
+                )),
+            };
+            item
+        })
+        .collect();
+
+    json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": meta.title,
+        "description": meta.description,
+        "home_page_url": meta.home_url,
+        "feed_url": meta.self_url,
+        "items": items,
+    })
+    .to_string()
+}
+
+fn published_at(entry: &PostWithRelations) -> NaiveDateTime {
+    entry.post.published_at.unwrap_or(entry.post.created_at)
+}
+
+/// The owning author followed by any co-authors.
+fn authors(entry: &PostWithRelations) -> impl Iterator<Item = &str> {
+    std::iter::once(entry.author.name.as_str()).chain(
+        entry
+            .contributors
+            .iter()
+            .filter(|credit| credit.role == ContributorRole::CoAuthor)
+            .map(|credit| credit.name.as_str()),
+    )
+}
+
+/// The post's category followed by its tags.
+fn categories(entry: &PostWithRelations) -> impl Iterator<Item = &str> {
+    entry
+        .category
+        .iter()
+        .map(|category| category.name.as_str())
+        .chain(entry.tags.iter().map(|tag| tag.name.as_str()))
+}
+
+fn full_content(content: FeedContent, entry: &PostWithRelations) -> Option<&str> {
+    match content {
+        FeedContent::Full => entry.post.rendered_html.as_deref(),
+        FeedContent::Summary => None,
+    }
+}
+
+fn rfc3339(date: NaiveDateTime) -> String {
+    date.and_utc().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
@@ -0,0 +1,201 @@
+use std::{fs, path::Path};
+
+use chrono::{NaiveDate, Utc};
+use serde_yaml::{Mapping, Value};
+
+use super::{parse_date, ImportBatch, ImportError, ImportedPost};
+use crate::db::models::post::{ContentFormat, PostStatus};
+
+/// Source name import records of Markdown posts from `site` are kept under. File paths only
+/// identify a post within one site, so each site or repository gets its own source.
+pub fn source(site: &str) -> String {
+    format!("markdown:{}", site.trim())
+}
+
+/// Reads every `.md` and `.markdown` file under `root`, e.g. a Jekyll `_posts` directory, as
+/// the posts of `site`.
+pub fn read_dir(root: &Path, site: &str) -> Result<ImportBatch, ImportError> {
+    let mut files = vec![];
+    collect_files(root, root, &mut files)?;
+    files.sort();
+
+    parse_files(site, files)
+}
+
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    files: &mut Vec<(String, String)>,
+) -> Result<(), ImportError> {
+    let io_error = |err| ImportError::Io(dir.display().to_string(), err);
+
+    for entry in fs::read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+            continue;
+        }
+        if !matches!(
+            path.extension().and_then(|extension| extension.to_str()),
+            Some("md" | "markdown")
+        ) {
+            continue;
+        }
+
+        let raw = fs::read_to_string(&path)
+            .map_err(|err| ImportError::Io(path.display().to_string(), err))?;
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        files.push((relative.to_string_lossy().replace('\\', "/"), raw));
+    }
+
+    Ok(())
+}
+
+/// Reads posts of `site` from `(path, contents)` pairs. The site and path identify the post
+/// on re-import, so they should stay the same between runs.
+pub fn parse_files(site: &str, files: Vec<(String, String)>) -> Result<ImportBatch, ImportError> {
+    let mut batch = ImportBatch {
+        source: source(site),
+        ..Default::default()
+    };
+
+    for (path, raw) in files {
+        let post = parse_post(&path, &raw, &mut batch.warnings)?;
+        batch.posts.push(post);
+    }
+
+    Ok(batch)
+}
+
+/// Reads one post. Understands the front matter keys of Jekyll and Hugo: `title`, `date`,
+/// `slug`, `author`, `excerpt` (or `description`, `summary`), `categories` (or `category`),
+/// `tags` (or `tag`), `published` and `draft`. The date and slug fall back to a Jekyll style
+/// file name, `2020-01-31-some-title.md`. Only the first category is kept as the post's
+/// category, the others become tags.
+pub fn parse_post(
+    path: &str,
+    raw: &str,
+    warnings: &mut Vec<String>,
+) -> Result<ImportedPost, ImportError> {
+    let (front_matter, body) = match split_front_matter(raw) {
+        Some((front_matter, body)) => (front_matter, body),
+        None => {
+            warnings.push(format!("{}: no front matter", path));
+            ("", raw)
+        }
+    };
+    let front_matter: Mapping = if front_matter.trim().is_empty() {
+        Mapping::new()
+    } else {
+        serde_yaml::from_str(front_matter)
+            .map_err(|err| ImportError::FrontMatter(path.to_string(), err))?
+    };
+
+    let stem = Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(path);
+    let (file_date, file_slug) = match stem
+        .get(..10)
+        .map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+    {
+        Some(Ok(date)) if stem[10..].starts_with('-') => {
+            (date.and_hms_opt(0, 0, 0), stem[11..].to_string())
+        }
+        _ => (None, stem.to_string()),
+    };
+
+    let date = match string(&front_matter, &["date"]) {
+        Some(value) => {
+            let date = parse_date(&value);
+            if date.is_none() {
+                warnings.push(format!("{}: unreadable date {:?}", path, value));
+            }
+            date.or(file_date)
+        }
+        None => file_date,
+    };
+
+    let draft = boolean(&front_matter, "draft") == Some(true)
+        || boolean(&front_matter, "published") == Some(false);
+    let status = match date {
+        _ if draft => PostStatus::Draft,
+        Some(date) if date > Utc::now().naive_utc() => PostStatus::Scheduled,
+        _ => PostStatus::Published,
+    };
+
+    let mut categories = list(&front_matter, &["categories", "category"]);
+    let category = (!categories.is_empty()).then(|| categories.remove(0));
+    let mut tags = list(&front_matter, &["tags", "tag"]);
+    tags.extend(categories);
+
+    Ok(ImportedPost {
+        external_id: path.to_string(),
+        title: string(&front_matter, &["title"]).unwrap_or_else(|| file_slug.replace('-', " ")),
+        slug: string(&front_matter, &["slug"]).or(Some(file_slug)),
+        content: body.trim().to_string(),
+        content_format: ContentFormat::Markdown,
+        excerpt: string(&front_matter, &["excerpt", "description", "summary"]),
+        author: string(&front_matter, &["author"]),
+        category,
+        tags,
+        published_at: date,
+        status,
+        comments: vec![],
+    })
+}
+
+/// Splits `---` delimited front matter off the body.
+fn split_front_matter(raw: &str) -> Option<(&str, &str)> {
+    let raw = raw.strip_prefix('\u{feff}').unwrap_or(raw);
+    let rest = raw
+        .strip_prefix("---\n")
+        .or_else(|| raw.strip_prefix("---\r\n"))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if matches!(line.trim_end(), "---" | "...") {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+
+    None
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.trim().to_string()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+    .filter(|value| !value.is_empty())
+}
+
+fn string(front_matter: &Mapping, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .find_map(|key| front_matter.get(*key).and_then(scalar))
+}
+
+fn boolean(front_matter: &Mapping, key: &str) -> Option<bool> {
+    front_matter.get(key).and_then(Value::as_bool)
+}
+
+/// A list of names, given as a YAML sequence or, like Jekyll does, a space separated string.
+fn list(front_matter: &Mapping, keys: &[&str]) -> Vec<String> {
+    let mut names = vec![];
+    for key in keys {
+        match front_matter.get(*key) {
+            Some(Value::Sequence(values)) => names.extend(values.iter().filter_map(scalar)),
+            Some(Value::String(value)) => {
+                names.extend(value.split_whitespace().map(str::to_string))
+            }
+            Some(value) => names.extend(scalar(value)),
+            None => {}
+        }
+    }
+    names.dedup();
+
+    names
+}
@@ -0,0 +1,116 @@
+use std::env;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+use crate::db::models::post::{ContentFormat, PostStatus};
+
+pub mod markdown;
+pub mod wxr;
+
+const DEFAULT_MAX_UPLOAD_BYTES: usize = 50 * 1024 * 1024;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ImportError {
+    #[error("Failed to read {0}: {1}")]
+    Io(String, std::io::Error),
+
+    #[error("Invalid front matter in {0}: {1}")]
+    FrontMatter(String, serde_yaml::Error),
+
+    #[error("Invalid WXR document: {0}")]
+    Xml(#[from] roxmltree::Error),
+
+    #[error("Not a WXR document: {0}")]
+    NotWxr(&'static str),
+}
+
+/// Largest file the import endpoint accepts (`IMPORT_MAX_UPLOAD_BYTES`, 50 MiB by default).
+pub fn max_upload_bytes() -> usize {
+    env::var("IMPORT_MAX_UPLOAD_BYTES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_MAX_UPLOAD_BYTES)
+}
+
+/// Everything read from one export, before it is matched against the database.
+#[derive(Debug, Default)]
+pub struct ImportBatch {
+    /// Identifies the export, so records of different sites don't collide.
+    pub source: String,
+    pub authors: Vec<ImportedAuthor>,
+    pub categories: Vec<ImportedCategory>,
+    pub posts: Vec<ImportedPost>,
+    /// Things that were skipped or guessed while reading.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedAuthor {
+    /// What posts refer to the author by, e.g. the WordPress login.
+    pub key: String,
+    pub name: String,
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedCategory {
+    pub slug: String,
+    pub name: String,
+    pub parent_slug: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedPost {
+    /// Stable id of the post in the export.
+    pub external_id: String,
+    pub title: String,
+    pub slug: Option<String>,
+    pub content: String,
+    pub content_format: ContentFormat,
+    pub excerpt: Option<String>,
+    /// Key of an entry in `ImportBatch::authors`, or a name or email to match users on.
+    pub author: Option<String>,
+    /// Slug or name of the category.
+    pub category: Option<String>,
+    /// Tag names.
+    pub tags: Vec<String>,
+    pub published_at: Option<NaiveDateTime>,
+    pub status: PostStatus,
+    pub comments: Vec<ImportedComment>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedComment {
+    pub external_id: String,
+    pub author_name: String,
+    pub author_email: Option<String>,
+    pub content: String,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// Reads the date formats found in Jekyll front matter and WordPress exports, as UTC.
+pub fn parse_date(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.naive_utc());
+    }
+    for format in [
+        "%Y-%m-%d %H:%M:%S %z",
+        "%Y-%m-%d %H:%M %z",
+        "%Y-%m-%dT%H:%M:%S%z",
+    ] {
+        if let Ok(date) = DateTime::parse_from_str(value, format) {
+            return Some(date.naive_utc());
+        }
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(date);
+        }
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+}
@@ -0,0 +1,174 @@
+use roxmltree::{Document, Node, ParsingOptions};
+
+use super::{
+    parse_date, ImportBatch, ImportError, ImportedAuthor, ImportedCategory, ImportedComment,
+    ImportedPost,
+};
+use crate::db::models::post::{ContentFormat, PostStatus};
+
+/// Reads a WordPress export (WXR 1.0 to 1.2). Only items of type `post` are imported; pages,
+/// attachments, pingbacks and comments that weren't approved are skipped.
+pub fn parse(xml: &str) -> Result<ImportBatch, ImportError> {
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let document = Document::parse_with_options(xml, options)?;
+    let channel = document
+        .root_element()
+        .children()
+        .find(|node| node.has_tag_name("channel"))
+        .ok_or(ImportError::NotWxr("missing channel"))?;
+    if child(channel, "wxr_version").is_none() {
+        return Err(ImportError::NotWxr("missing wxr_version"));
+    }
+
+    let site = child(channel, "base_blog_url")
+        .or_else(|| child(channel, "link"))
+        .unwrap_or_default();
+    let mut batch = ImportBatch {
+        source: format!("wordpress:{}", site.trim_end_matches('/')),
+        ..Default::default()
+    };
+
+    for node in channel.children().filter(|node| node.is_element()) {
+        match node.tag_name().name() {
+            "author" => {
+                let Some(key) = child(node, "author_login") else {
+                    continue;
+                };
+                batch.authors.push(ImportedAuthor {
+                    name: child(node, "author_display_name").unwrap_or_else(|| key.clone()),
+                    email: child(node, "author_email"),
+                    key,
+                });
+            }
+            "category" => {
+                let Some(slug) = child(node, "category_nicename") else {
+                    continue;
+                };
+                batch.categories.push(ImportedCategory {
+                    name: child(node, "cat_name").unwrap_or_else(|| slug.clone()),
+                    parent_slug: child(node, "category_parent"),
+                    slug,
+                });
+            }
+            "item" => {
+                if let Some(post) = parse_item(node, &mut batch.warnings) {
+                    batch.posts.push(post);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(batch)
+}
+
+fn parse_item(item: Node, warnings: &mut Vec<String>) -> Option<ImportedPost> {
+    if child(item, "post_type").as_deref() != Some("post") {
+        return None;
+    }
+    let external_id = child(item, "post_id")?;
+    let title = child(item, "title").unwrap_or_else(|| format!("Untitled {}", external_id));
+
+    let status = match child(item, "status").as_deref() {
+        Some("publish") => PostStatus::Published,
+        Some("future") => PostStatus::Scheduled,
+        Some("pending") => PostStatus::InReview,
+        Some("trash") => {
+            warnings.push(format!("{}: skipped, it is in the trash", title));
+            return None;
+        }
+        _ => PostStatus::Draft,
+    };
+    // Drafts carry a zero GMT date
+    let published_at = child(item, "post_date_gmt")
+        .and_then(|date| parse_date(&date))
+        .or_else(|| child(item, "post_date").and_then(|date| parse_date(&date)));
+
+    let mut category = None;
+    let mut tags = vec![];
+    for node in item.children().filter(|node| node.has_tag_name("category")) {
+        let name = node.text().unwrap_or_default().trim().to_string();
+        match node.attribute("domain") {
+            Some("category") if category.is_none() => {
+                category = node
+                    .attribute("nicename")
+                    .map(str::to_string)
+                    .or(Some(name))
+            }
+            // Further categories are kept as tags
+            Some("category") | Some("post_tag") if !name.is_empty() => tags.push(name),
+            _ => {}
+        }
+    }
+
+    let comments = item
+        .children()
+        .filter(|node| node.tag_name().name() == "comment")
+        .filter_map(|node| parse_comment(node, &external_id))
+        .collect();
+
+    Some(ImportedPost {
+        title,
+        slug: child(item, "post_name"),
+        content: encoded(item, "content").unwrap_or_default(),
+        content_format: ContentFormat::Html,
+        excerpt: encoded(item, "excerpt"),
+        author: child(item, "creator"),
+        category,
+        tags,
+        published_at,
+        status,
+        comments,
+        external_id,
+    })
+}
+
+fn parse_comment(node: Node, post_external_id: &str) -> Option<ImportedComment> {
+    if child(node, "comment_approved").as_deref() != Some("1") {
+        return None;
+    }
+    if matches!(
+        child(node, "comment_type").as_deref(),
+        Some("pingback" | "trackback")
+    ) {
+        return None;
+    }
+    let comment_id = child(node, "comment_id")?;
+
+    Some(ImportedComment {
+        external_id: format!("{}#comment-{}", post_external_id, comment_id),
+        author_name: child(node, "comment_author").unwrap_or_else(|| "Anonymous".to_string()),
+        author_email: child(node, "comment_author_email"),
+        content: child(node, "comment_content")?,
+        created_at: child(node, "comment_date_gmt")
+            .and_then(|date| parse_date(&date))
+            .or_else(|| child(node, "comment_date").and_then(|date| parse_date(&date))),
+    })
+}
+
+/// Trimmed text of the first child called `name`, whatever its namespace, if not empty.
+fn child(node: Node, name: &str) -> Option<String> {
+    node.children()
+        .find(|child| child.tag_name().name() == name)
+        .and_then(|child| child.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// `content:encoded` or `excerpt:encoded`, told apart by their namespace.
+fn encoded(node: Node, kind: &str) -> Option<String> {
+    node.children()
+        .find(|child| {
+            child.tag_name().name() == "encoded"
+                && child
+                    .tag_name()
+                    .namespace()
+                    .is_some_and(|namespace| namespace.contains(kind))
+        })
+        .and_then(|child| child.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
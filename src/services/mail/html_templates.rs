@@ -1,3 +1,5 @@
+use crate::services::content_renderer::escape_html;
+
 pub fn email_otp_html(code: &str) -> String {
     format!(r#"
     <!DOCTYPE html>
@@ -55,3 +57,41 @@ pub fn email_otp_html(code: &str) -> String {
     </html>
     "#, code).to_string()
 }
+
+pub fn editorial_note_html(note_author: &str, post_title: &str, note: &str) -> String {
+    format!(r#"
+    <!DOCTYPE html>
+    <html lang="en">
+      <body>
+        <div
+          style='background-color:#000000;color:#FFFFFF;font-family:"Iowan Old Style", "Palatino Linotype", "URW Palladio L", P052, serif;font-size:16px;font-weight:400;letter-spacing:0.15008px;line-height:1.5;margin:0;padding:32px 0;min-height:100%;width:100%'
+        >
+          <table
+            align="center"
+            width="100%"
+            style="margin:0 auto;max-width:600px;background-color:#000000"
+            role="presentation"
+            cellspacing="0"
+            cellpadding="0"
+            border="0"
+          >
+            <tbody>
+              <tr style="width:100%">
+                <td>
+                  <div
+                    style="color:#ffffff;font-size:16px;font-weight:normal;text-align:center;padding:16px 24px 16px 24px"
+                  >
+                    {} left a note on <strong>{}</strong>:
+                  </div>
+                  <div
+                    style="color:#868686;font-size:16px;font-weight:normal;white-space:pre-wrap;padding:16px 24px 16px 24px"
+                  >{}</div>
+                </td>
+              </tr>
+            </tbody>
+          </table>
+        </div>
+      </body>
+    </html>
+    "#, escape_html(note_author), escape_html(post_title), escape_html(note)).to_string()
+}
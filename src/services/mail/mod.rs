@@ -50,3 +50,18 @@ pub async fn send_forgot_password_email(
 
     send_email(mailer, email, &no_reply, subject, body).await
 }
+
+/// Lets a post's author know someone left an editorial note on it.
+pub async fn send_editorial_note_email(
+    mailer: &AsyncSmtpTransport<lettre::Tokio1Executor>,
+    email: &str,
+    note_author: &str,
+    post_title: &str,
+    note: &str,
+) -> Result<(), String> {
+    let no_reply = format!("No reply <no-reply@{}>", DOMAIN);
+    let subject = format!("New editorial note on \"{}\"", post_title);
+    let body = html_templates::editorial_note_html(note_author, post_title, note);
+
+    send_email(mailer, email, &no_reply, &subject, body).await
+}
@@ -14,11 +14,9 @@ pub async fn create_connection() -> AsyncSmtpTransport<Tokio1Executor> {
     let creds = Credentials::new(username, password);
 
     // Open a remote connection to gmail
-    let mailer = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)
+    AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)
         .unwrap()
         .credentials(creds)
         // .port(port)
-        .build();
-
-    mailer
+        .build()
 }
@@ -0,0 +1,92 @@
+use std::{
+    env,
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+};
+
+use super::{
+    storage::{MediaStorage, StorageFuture},
+    MediaError,
+};
+use crate::services::site;
+
+/// Path the API serves local uploads under.
+pub const PUBLIC_PATH: &str = "/media/files";
+
+/// Keeps uploads in a directory on disk (`MEDIA_LOCAL_DIR`, `./uploads` by default), published
+/// under `MEDIA_PUBLIC_URL` or this API's own `PUBLIC_PATH`.
+pub struct LocalStorage {
+    root: PathBuf,
+    public_url: String,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>, public_url: impl Into<String>) -> Self {
+        Self {
+            root: root.into(),
+            public_url: public_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let root = env::var("MEDIA_LOCAL_DIR").unwrap_or_else(|_| "./uploads".to_string());
+        let public_url = env::var("MEDIA_PUBLIC_URL")
+            .unwrap_or_else(|_| format!("{}{}", site::api_url(), PUBLIC_PATH));
+
+        Self::new(root, public_url)
+    }
+
+    /// Where `key` lives on disk. Keys are generated by the server, but anything that could
+    /// step outside the root is refused anyway.
+    fn path_of(&self, key: &str) -> Result<PathBuf, MediaError> {
+        let key = Path::new(key);
+        if !key
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(MediaError::Storage(format!("invalid key {:?}", key)));
+        }
+
+        Ok(self.root.join(key))
+    }
+}
+
+impl MediaStorage for LocalStorage {
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        bytes: Vec<u8>,
+        _content_type: &'a str,
+    ) -> StorageFuture<'a> {
+        Box::pin(async move {
+            let path = self.path_of(key)?;
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(path, bytes).await?;
+
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Vec<u8>> {
+        Box::pin(async move { Ok(tokio::fs::read(self.path_of(key)?).await?) })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a> {
+        Box::pin(async move {
+            match tokio::fs::remove_file(self.path_of(key)?).await {
+                Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_url, key)
+    }
+
+    fn local_root(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+}
@@ -0,0 +1,236 @@
+use std::{env, io::Cursor};
+
+use chrono::Utc;
+use image::{imageops::FilterType, DynamicImage, ImageFormat, ImageReader, Limits};
+use rand::{distributions::Alphanumeric, Rng};
+
+use crate::db::models::media::MediaVariant;
+use storage::MediaStorage;
+
+pub mod local;
+pub mod object_storage;
+pub mod storage;
+
+/// Widths the resized copies are generated at, when the original is wider.
+const VARIANT_WIDTHS: [u32; 3] = [320, 768, 1536];
+
+/// Largest width or height accepted, so a tiny file can't decode into a huge bitmap.
+const MAX_DIMENSION: u32 = 12_000;
+
+const DEFAULT_MAX_UPLOAD_BYTES: usize = 10 * 1024 * 1024;
+
+#[derive(thiserror::Error, Debug)]
+pub enum MediaError {
+    #[error("Unsupported file type, expected a JPEG, PNG, GIF or WebP image")]
+    UnsupportedType,
+
+    #[error("Image could not be processed {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("Storage error {0}")]
+    Storage(String),
+
+    #[error("Storage error {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Storage error {0}")]
+    S3(#[from] s3::error::S3Error),
+}
+
+/// Largest file accepted by the upload endpoint (`MEDIA_MAX_UPLOAD_BYTES`, 10 MiB by default).
+pub fn max_upload_bytes() -> usize {
+    env::var("MEDIA_MAX_UPLOAD_BYTES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_MAX_UPLOAD_BYTES)
+}
+
+/// An upload that was recognised as one of the accepted image types.
+#[derive(Debug)]
+pub struct ProcessedImage {
+    pub content_type: &'static str,
+    pub extension: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub variants: Vec<EncodedVariant>,
+}
+
+/// A resized WebP copy of an upload.
+#[derive(Debug)]
+pub struct EncodedVariant {
+    pub width: u32,
+    pub height: u32,
+    pub bytes: Vec<u8>,
+}
+
+/// Sniffs the type of `bytes` from its content rather than the name or header the client sent,
+/// decodes it and encodes its WebP variants: one per width in `VARIANT_WIDTHS` below the
+/// original's, and a full size copy unless the upload already is WebP. Animated GIFs only keep
+/// their first frame in the variants. CPU bound, so run it off the async runtime.
+pub fn process(bytes: &[u8]) -> Result<ProcessedImage, MediaError> {
+    let format = image::guess_format(bytes).map_err(|_| MediaError::UnsupportedType)?;
+    let (content_type, extension) = match format {
+        ImageFormat::Jpeg => ("image/jpeg", "jpg"),
+        ImageFormat::Png => ("image/png", "png"),
+        ImageFormat::Gif => ("image/gif", "gif"),
+        ImageFormat::WebP => ("image/webp", "webp"),
+        _ => return Err(MediaError::UnsupportedType),
+    };
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    reader.limits(limits);
+    let original = reader.decode()?;
+
+    let mut widths: Vec<u32> = VARIANT_WIDTHS
+        .into_iter()
+        .filter(|width| *width < original.width())
+        .collect();
+    if format != ImageFormat::WebP {
+        widths.push(original.width());
+    }
+
+    let variants = widths
+        .into_iter()
+        .map(|width| encode_webp(&original, width))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ProcessedImage {
+        content_type,
+        extension,
+        width: original.width(),
+        height: original.height(),
+        variants,
+    })
+}
+
+fn encode_webp(original: &DynamicImage, width: u32) -> Result<EncodedVariant, MediaError> {
+    let resized = if width < original.width() {
+        original.resize(width, original.height(), FilterType::Lanczos3)
+    } else {
+        original.clone()
+    };
+    // The WebP encoder only takes 8-bit RGB(A)
+    let resized = if resized.color().has_alpha() {
+        DynamicImage::ImageRgba8(resized.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(resized.to_rgb8())
+    };
+
+    let mut bytes = Vec::new();
+    resized.write_to(&mut Cursor::new(&mut bytes), ImageFormat::WebP)?;
+
+    Ok(EncodedVariant {
+        width: resized.width(),
+        height: resized.height(),
+        bytes,
+    })
+}
+
+/// An upload and its variants, as put in storage.
+#[derive(Debug)]
+pub struct StoredUpload {
+    pub storage_key: String,
+    pub url: String,
+    pub content_type: &'static str,
+    pub byte_size: usize,
+    pub width: u32,
+    pub height: u32,
+    pub variants: Vec<MediaVariant>,
+}
+
+impl StoredUpload {
+    /// Keys of the upload and its variants in storage.
+    pub fn storage_keys(&self) -> Vec<String> {
+        std::iter::once(self.storage_key.clone())
+            .chain(
+                self.variants
+                    .iter()
+                    .map(|variant| variant.storage_key.clone()),
+            )
+            .collect()
+    }
+}
+
+/// Processes an upload and stores it along with its variants. If any file fails to store,
+/// the ones already stored are removed again.
+pub async fn store(storage: &dyn MediaStorage, bytes: Vec<u8>) -> Result<StoredUpload, MediaError> {
+    let (bytes, processed) = tokio::task::spawn_blocking(move || {
+        let processed = process(&bytes);
+        (bytes, processed)
+    })
+    .await
+    .map_err(|err| MediaError::Storage(err.to_string()))?;
+    let processed = processed?;
+
+    let key_stem = new_key_stem();
+    let storage_key = format!("{}.{}", key_stem, processed.extension);
+    let byte_size = bytes.len();
+    let mut stored_keys = vec![];
+
+    let result = async {
+        storage
+            .put(&storage_key, bytes, processed.content_type)
+            .await?;
+        stored_keys.push(storage_key.clone());
+
+        let mut variants = vec![];
+        for variant in processed.variants {
+            let key = variant_key(&key_stem, variant.width);
+            storage.put(&key, variant.bytes, "image/webp").await?;
+            stored_keys.push(key.clone());
+            variants.push(MediaVariant {
+                width: variant.width as i32,
+                height: variant.height as i32,
+                url: storage.url(&key),
+                storage_key: key,
+            });
+        }
+
+        Ok(variants)
+    }
+    .await;
+
+    match result {
+        Ok(variants) => Ok(StoredUpload {
+            url: storage.url(&storage_key),
+            storage_key,
+            content_type: processed.content_type,
+            byte_size,
+            width: processed.width,
+            height: processed.height,
+            variants,
+        }),
+        Err(err) => {
+            remove(storage, &stored_keys).await;
+            Err(err)
+        }
+    }
+}
+
+/// Removes stored files, logging the ones that couldn't be removed.
+pub async fn remove(storage: &dyn MediaStorage, keys: &[String]) {
+    for key in keys {
+        if let Err(err) = storage.delete(key).await {
+            tracing::warn!("Failed to remove stored media {}: {}", key, err);
+        }
+    }
+}
+
+/// A fresh key for an upload, `<year>/<month>/<random>`, without extension.
+pub fn new_key_stem() -> String {
+    let random: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(24)
+        .map(char::from)
+        .collect();
+
+    format!("{}/{}", Utc::now().format("%Y/%m"), random.to_lowercase())
+}
+
+/// Key of the variant `width` pixels wide of the upload stored under `key_stem`.
+pub fn variant_key(key_stem: &str, width: u32) -> String {
+    format!("{}-{}w.webp", key_stem, width)
+}
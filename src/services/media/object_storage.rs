@@ -0,0 +1,187 @@
+use std::env;
+
+use s3::{creds::Credentials, Bucket, Region};
+
+use super::{
+    storage::{MediaStorage, StorageFuture},
+    MediaError,
+};
+
+/// Keeps uploads in an S3 compatible bucket. Settings come from `S3_BUCKET`, `S3_REGION`,
+/// `S3_ENDPOINT` (for anything that isn't AWS, e.g. a local MinIO), `S3_ACCESS_KEY`,
+/// `S3_SECRET_KEY` and `S3_PATH_STYLE`. Files are published under `MEDIA_PUBLIC_URL`, or the
+/// bucket's own URL when it isn't set.
+pub struct S3Storage {
+    bucket: Box<Bucket>,
+    public_url: String,
+}
+
+impl S3Storage {
+    pub fn from_env() -> Result<Self, MediaError> {
+        let name = env::var("S3_BUCKET")
+            .map_err(|_| MediaError::Storage("S3_BUCKET must be set".to_string()))?;
+        let region_name = env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+        let region = match env::var("S3_ENDPOINT") {
+            Ok(endpoint) => Region::Custom {
+                region: region_name,
+                endpoint,
+            },
+            Err(_) => region_name
+                .parse()
+                .map_err(|_| MediaError::Storage(format!("unknown S3 region {:?}", region_name)))?,
+        };
+        let credentials = Credentials::new(
+            env::var("S3_ACCESS_KEY").ok().as_deref(),
+            env::var("S3_SECRET_KEY").ok().as_deref(),
+            None,
+            None,
+            None,
+        )
+        .map_err(|err| MediaError::Storage(err.to_string()))?;
+
+        let mut bucket = Bucket::new(&name, region, credentials)?;
+        if env::var("S3_PATH_STYLE").is_ok_and(|value| value == "true") {
+            bucket = bucket.with_path_style();
+        }
+        let public_url = env::var("MEDIA_PUBLIC_URL").unwrap_or_else(|_| bucket.url());
+
+        Ok(Self {
+            bucket,
+            public_url: public_url.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+impl MediaStorage for S3Storage {
+    fn put<'a>(&'a self, key: &'a str, bytes: Vec<u8>, content_type: &'a str) -> StorageFuture<'a> {
+        Box::pin(async move {
+            self.bucket
+                .put_object_with_content_type(key, &bytes, content_type)
+                .await?;
+
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Vec<u8>> {
+        Box::pin(async move { Ok(self.bucket.get_object(key).await?.to_vec()) })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a> {
+        Box::pin(async move {
+            self.bucket.delete_object(key).await?;
+
+            Ok(())
+        })
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_url, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use axum::{
+        body::Bytes,
+        extract::{Path, State},
+        http::StatusCode,
+        routing::put,
+        Router,
+    };
+
+    use super::*;
+
+    type Objects = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+    /// Serves just enough of the S3 API for the storage backend: path style object PUT, GET
+    /// and DELETE, kept in memory.
+    async fn stand_in_server(objects: Objects) -> String {
+        async fn put_object(
+            State(objects): State<Objects>,
+            Path((_, key)): Path<(String, String)>,
+            body: Bytes,
+        ) -> StatusCode {
+            objects.lock().unwrap().insert(key, body.to_vec());
+            StatusCode::OK
+        }
+
+        async fn get_object(
+            State(objects): State<Objects>,
+            Path((_, key)): Path<(String, String)>,
+        ) -> Result<Vec<u8>, StatusCode> {
+            objects
+                .lock()
+                .unwrap()
+                .get(&key)
+                .cloned()
+                .ok_or(StatusCode::NOT_FOUND)
+        }
+
+        async fn delete_object(
+            State(objects): State<Objects>,
+            Path((_, key)): Path<(String, String)>,
+        ) -> StatusCode {
+            objects.lock().unwrap().remove(&key);
+            StatusCode::NO_CONTENT
+        }
+
+        let app = Router::new()
+            .route(
+                "/:bucket/*key",
+                put(put_object).get(get_object).delete(delete_object),
+            )
+            .with_state(objects);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        endpoint
+    }
+
+    fn storage(endpoint: String) -> S3Storage {
+        let region = Region::Custom {
+            region: "us-east-1".to_string(),
+            endpoint,
+        };
+        let credentials =
+            Credentials::new(Some("access"), Some("secret"), None, None, None).unwrap();
+
+        S3Storage {
+            bucket: Bucket::new("media", region, credentials)
+                .unwrap()
+                .with_path_style(),
+            public_url: "https://cdn.example.com".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn stores_reads_and_deletes_objects() {
+        let objects = Objects::default();
+        let storage = storage(stand_in_server(objects.clone()).await);
+        let key = "2026/10/photo.webp";
+
+        storage
+            .put(key, b"image bytes".to_vec(), "image/webp")
+            .await
+            .unwrap();
+        assert_eq!(
+            objects.lock().unwrap().get(key).map(Vec::as_slice),
+            Some(&b"image bytes"[..])
+        );
+        assert_eq!(storage.get(key).await.unwrap(), b"image bytes");
+        assert_eq!(
+            storage.url(key),
+            "https://cdn.example.com/2026/10/photo.webp"
+        );
+
+        storage.delete(key).await.unwrap();
+        assert!(objects.lock().unwrap().is_empty());
+        assert!(storage.get(key).await.is_err());
+    }
+}
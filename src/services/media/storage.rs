@@ -0,0 +1,38 @@
+use std::{env, future::Future, path::Path, pin::Pin, sync::Arc};
+
+use super::{local::LocalStorage, object_storage::S3Storage, MediaError};
+
+pub type StorageFuture<'a, T = ()> =
+    Pin<Box<dyn Future<Output = Result<T, MediaError>> + Send + 'a>>;
+
+/// Where uploaded files are kept.
+pub trait MediaStorage: Send + Sync {
+    /// Stores `bytes` under `key`, replacing anything already there.
+    fn put<'a>(&'a self, key: &'a str, bytes: Vec<u8>, content_type: &'a str) -> StorageFuture<'a>;
+
+    /// Reads the file under `key`.
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Vec<u8>>;
+
+    /// Removes the file under `key`. Removing a missing file is not an error.
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a>;
+
+    /// Public URL of the file under `key`.
+    fn url(&self, key: &str) -> String;
+
+    /// Directory the files are in when this API has to serve them itself.
+    fn local_root(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// The backend picked by `MEDIA_STORAGE`: `local` (the default) or `s3`.
+pub fn from_env() -> Result<Arc<dyn MediaStorage>, MediaError> {
+    match env::var("MEDIA_STORAGE").as_deref() {
+        Ok("s3") => Ok(Arc::new(S3Storage::from_env()?)),
+        Ok("local") | Err(_) => Ok(Arc::new(LocalStorage::from_env())),
+        Ok(other) => Err(MediaError::Storage(format!(
+            "unknown MEDIA_STORAGE backend {:?}",
+            other
+        ))),
+    }
+}
@@ -1,4 +1,19 @@
 pub mod abuse_limiter;
+pub mod archive;
 pub mod auth;
+pub mod cache;
+pub mod content_renderer;
+pub mod feed;
+pub mod import;
 pub mod mail;
+pub mod media;
+pub mod preview;
+pub mod ranking;
 pub mod redis;
+pub mod scheduler;
+pub mod search;
+pub mod site;
+pub mod sitemap;
+pub mod slug;
+pub mod version;
+pub mod view_tracker;
@@ -0,0 +1,55 @@
+use std::env;
+
+use chrono::NaiveDateTime;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// How long a preview link stays valid unless asked otherwise.
+pub const DEFAULT_TTL_HOURS: i64 = 72;
+
+/// Key preview tokens are signed with (`PREVIEW_TOKEN_SECRET`, falling back to `COOKIE_KEY`).
+/// Empty values don't count, anyone could sign tokens with them. Checked at startup.
+pub fn secret() -> Option<String> {
+    let non_empty = |name| {
+        env::var(name)
+            .ok()
+            .filter(|value: &String| !value.is_empty())
+    };
+    non_empty("PREVIEW_TOKEN_SECRET").or_else(|| non_empty("COOKIE_KEY"))
+}
+
+fn mac(token_id: i32, post_id: i32, expires: i64) -> HmacSha256 {
+    let secret = secret().expect("preview secret is checked at startup");
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{}.{}.{}", token_id, post_id, expires).as_bytes());
+    mac
+}
+
+/// Token of a preview link, `<token id>.<expiry>.<signature>`. The post id is signed but left
+/// out, so a token only opens the post it was issued for.
+pub fn sign(token_id: i32, post_id: i32, expires_at: NaiveDateTime) -> String {
+    let expires = expires_at.and_utc().timestamp();
+    let signature = mac(token_id, post_id, expires).finalize().into_bytes();
+    format!("{}.{}.{}", token_id, expires, hex::encode(signature))
+}
+
+/// Id of the token if it was issued for `post_id` and hasn't expired by `now`. Whether it has
+/// been revoked is up to the caller.
+pub fn verify(token: &str, post_id: i32, now: NaiveDateTime) -> Option<i32> {
+    let mut parts = token.splitn(3, '.');
+    let token_id: i32 = parts.next()?.parse().ok()?;
+    let expires: i64 = parts.next()?.parse().ok()?;
+    let signature = hex::decode(parts.next()?).ok()?;
+
+    if expires <= now.and_utc().timestamp() {
+        return None;
+    }
+    mac(token_id, post_id, expires)
+        .verify_slice(&signature)
+        .ok()?;
+
+    Some(token_id)
+}
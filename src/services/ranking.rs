@@ -0,0 +1,76 @@
+//! Post rankings kept in Redis sorted sets, so listing them doesn't score every post. The
+//! scheduler refreshes them; a missing ranking is computed on the spot.
+
+use fred::{
+    error::RedisError,
+    prelude::{KeysInterface, RedisPool, SortedSetsInterface},
+    types::Expiration,
+};
+
+use crate::db::models::post_ranking::{PostScore, RankingKind};
+
+/// How long an empty ranking is trusted. Redis can't store an empty sorted set, so a marker
+/// stands in for it; it expires soon so the first engagement shows up before the next refresh.
+const EMPTY_TTL_SECS: i64 = 60;
+
+fn key(kind: RankingKind) -> String {
+    format!("post_ranking:{}", kind.name())
+}
+
+fn empty_key(kind: RankingKind) -> String {
+    format!("post_ranking:{}:empty", kind.name())
+}
+
+/// Replaces the ranking. It is built under another key and renamed, so readers never see a
+/// partial one.
+pub async fn store(
+    redis_pool: &RedisPool,
+    kind: RankingKind,
+    scores: &[PostScore],
+) -> Result<(), RedisError> {
+    let key = key(kind);
+    if scores.is_empty() {
+        let _: () = redis_pool.del(key).await?;
+        return redis_pool
+            .set(
+                empty_key(kind),
+                1,
+                Some(Expiration::EX(EMPTY_TTL_SECS)),
+                None,
+                false,
+            )
+            .await;
+    }
+
+    let staging = format!("{}:next", key);
+    let _: () = redis_pool.del(&staging).await?;
+    let members: Vec<(f64, i32)> = scores
+        .iter()
+        .map(|scored| (scored.score, scored.id))
+        .collect();
+    let _: () = redis_pool
+        .zadd(&staging, None, None, false, false, members)
+        .await?;
+
+    let _: () = redis_pool.rename(staging, key).await?;
+    redis_pool.del(empty_key(kind)).await
+}
+
+/// The ranking, best first, or `None` if it isn't stored.
+pub async fn load(
+    redis_pool: &RedisPool,
+    kind: RankingKind,
+) -> Result<Option<Vec<PostScore>>, RedisError> {
+    let members: Vec<(i32, f64)> = redis_pool.zrevrange(key(kind), 0, -1, true).await?;
+    if members.is_empty() {
+        let empty: bool = redis_pool.exists(empty_key(kind)).await?;
+        return Ok(empty.then(Vec::new));
+    }
+
+    Ok(Some(
+        members
+            .into_iter()
+            .map(|(id, score)| PostScore { id, score })
+            .collect(),
+    ))
+}
@@ -0,0 +1,347 @@
+use std::{
+    env,
+    future::Future,
+    pin::Pin,
+    time::{Duration, Instant},
+};
+
+use chrono::{NaiveTime, Utc};
+use fred::{
+    error::RedisError,
+    prelude::{KeysInterface, LuaInterface, RedisPool},
+    types::{Expiration, SetOptions},
+};
+use rand::{distributions::Alphanumeric, Rng};
+use tokio::time::MissedTickBehavior;
+
+use crate::{
+    db::{
+        errors::DBError,
+        models::{
+            category::Category,
+            post::Post,
+            post_analytics::PostAnalytics,
+            post_comment::PostComment,
+            post_ranking::{PostRanking, RankingFilter, RankingKind},
+            post_view::PostView,
+            tag::Tag,
+        },
+    },
+    services::{cache, ranking, view_tracker},
+    AppState,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum JobError {
+    #[error("Database error {0}")]
+    Database(#[from] DBError),
+
+    #[error("Redis error {0}")]
+    Redis(#[from] RedisError),
+}
+
+pub type JobResult = Result<(), JobError>;
+type JobFuture = Pin<Box<dyn Future<Output = JobResult> + Send>>;
+
+pub struct ScheduledJob {
+    pub name: &'static str,
+    pub interval: Duration,
+    pub run: fn(AppState) -> JobFuture,
+}
+
+fn jobs() -> Vec<ScheduledJob> {
+    vec![
+        ScheduledJob {
+            name: "publish_scheduled_posts",
+            interval: Duration::from_secs(30),
+            run: |state| Box::pin(publish_scheduled_posts(state)),
+        },
+        ScheduledJob {
+            name: "render_pending_posts",
+            interval: Duration::from_secs(60),
+            run: |state| Box::pin(render_pending_posts(state)),
+        },
+        ScheduledJob {
+            name: "flush_post_views",
+            interval: Duration::from_secs(15),
+            run: |state| Box::pin(flush_post_views(state)),
+        },
+        ScheduledJob {
+            name: "rank_posts",
+            interval: Duration::from_secs(10 * 60),
+            run: |state| Box::pin(rank_posts(state)),
+        },
+        ScheduledJob {
+            name: "aggregate_post_views",
+            interval: Duration::from_secs(60 * 60),
+            run: |state| Box::pin(aggregate_post_views(state)),
+        },
+        ScheduledJob {
+            name: "purge_trash",
+            interval: Duration::from_secs(60 * 60),
+            run: |state| Box::pin(purge_trash(state)),
+        },
+    ]
+}
+
+async fn publish_scheduled_posts(state: AppState) -> JobResult {
+    let published = Post::publish_scheduled(&state.db_pool).await?;
+    if published > 0 {
+        tracing::info!("Published {} scheduled post(s).", published);
+        cache::invalidate_posts(&state.redis_pool).await;
+    }
+
+    Ok(())
+}
+
+async fn render_pending_posts(state: AppState) -> JobResult {
+    let rendered = Post::render_pending(&state.db_pool, 50).await?;
+    if rendered > 0 {
+        tracing::info!("Rendered {} post(s) with an empty HTML cache.", rendered);
+    }
+
+    Ok(())
+}
+
+/// Writes the views buffered in Redis to Postgres.
+async fn flush_post_views(state: AppState) -> JobResult {
+    let Some(batch) = view_tracker::start_flush(&state.redis_pool).await? else {
+        return Ok(());
+    };
+
+    let stored =
+        PostView::record_batch(&state.db_pool, Some(batch.id.clone()), batch.views).await?;
+    view_tracker::finish_flush(&state.redis_pool, &batch.id).await?;
+    tracing::debug!("Flushed {} buffered post view(s).", stored);
+
+    Ok(())
+}
+
+/// Recomputes the trending and popular rankings.
+async fn rank_posts(state: AppState) -> JobResult {
+    for kind in RankingKind::ALL {
+        let scores = PostRanking::compute(
+            &state.db_pool,
+            kind,
+            RankingFilter::default(),
+            PostRanking::SIZE,
+        )
+        .await?;
+        ranking::store(&state.redis_pool, kind, &scores).await?;
+    }
+
+    Ok(())
+}
+
+/// Rolls the raw views of past days into the daily analytics tables, once the buffered views
+/// of the day had an hour to be flushed.
+async fn aggregate_post_views(state: AppState) -> JobResult {
+    let before = (Utc::now() - chrono::Duration::hours(1))
+        .date_naive()
+        .and_time(NaiveTime::MIN);
+
+    let rolled = PostAnalytics::aggregate(&state.db_pool, before).await?;
+    if rolled > 0 {
+        tracing::info!("Rolled {} post view(s) into daily analytics.", rolled);
+    }
+
+    Ok(())
+}
+
+/// Days deleted content stays in the trash before it is purged (`TRASH_RETENTION_DAYS`).
+fn trash_retention_days() -> i64 {
+    env::var("TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(30)
+}
+
+async fn purge_trash(state: AppState) -> JobResult {
+    let cutoff = Utc::now().naive_utc() - chrono::Duration::days(trash_retention_days());
+
+    let comments = PostComment::purge_deleted(&state.db_pool, cutoff).await?;
+    let posts = Post::purge_deleted(&state.db_pool, cutoff).await?;
+    let categories = Category::purge_deleted(&state.db_pool, cutoff).await?;
+    let tags = Tag::purge_deleted(&state.db_pool, cutoff).await?;
+
+    if comments + posts + categories + tags > 0 {
+        tracing::info!(
+            "Purged {} post(s), {} comment(s), {} categor(ies) and {} tag(s) from the trash.",
+            posts,
+            comments,
+            categories,
+            tags
+        );
+    }
+    if posts + categories + tags > 0 {
+        cache::invalidate_posts(&state.redis_pool).await;
+    }
+
+    Ok(())
+}
+
+/// Spawns every background job on its own interval.
+///
+/// Before each run the job takes a Redis lease named after it, so when several replicas are
+/// running only one of them executes a job per tick. The lease is renewed while the job runs and
+/// is only ever extended or released by the replica that holds it, so a slow run never overlaps
+/// with another replica's run of the same job.
+pub fn start(state: AppState) {
+    let instance_id: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect();
+
+    for job in jobs() {
+        let state = state.clone();
+        let instance_id = instance_id.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(job.interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            let key = format!("scheduler:lease:{}", job.name);
+            let ttl = lease_ttl(job.interval);
+
+            loop {
+                ticker.tick().await;
+
+                match acquire_lease(&state.redis_pool, &key, &instance_id, ttl).await {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(err) => {
+                        tracing::error!("Failed to acquire lease for job {}: {}", job.name, err);
+                        continue;
+                    }
+                }
+
+                let started = Instant::now();
+                let run = (job.run)(state.clone());
+                tokio::pin!(run);
+
+                let mut renewal = tokio::time::interval((ttl / 3).max(Duration::from_secs(1)));
+                renewal.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                renewal.tick().await;
+
+                let result = loop {
+                    tokio::select! {
+                        result = &mut run => break Some(result),
+                        _ = renewal.tick() => {
+                            match extend_lease(&state.redis_pool, &key, &instance_id, ttl).await {
+                                Ok(true) => {}
+                                Ok(false) => {
+                                    tracing::error!(
+                                        "Lost the lease for job {}, stopping this run",
+                                        job.name
+                                    );
+                                    break None;
+                                }
+                                Err(err) => {
+                                    tracing::error!(
+                                        "Failed to renew lease for job {}: {}",
+                                        job.name,
+                                        err
+                                    );
+                                }
+                            }
+                        }
+                    }
+                };
+
+                if let Some(Err(err)) = result {
+                    tracing::error!("Scheduled job {} failed: {}", job.name, err);
+                }
+
+                // Keep the lease until the end of this tick's window so other replicas don't run
+                // the job again, or drop it straight away when the run overran the window
+                let remaining = ttl.saturating_sub(started.elapsed());
+                if let Err(err) =
+                    release_lease(&state.redis_pool, &key, &instance_id, remaining).await
+                {
+                    tracing::error!("Failed to release lease for job {}: {}", job.name, err);
+                }
+            }
+        });
+    }
+}
+
+/// Extends the lease to `ttl` if `ARGV[1]` still holds it.
+const EXTEND_LEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+/// Lets the lease expire after `ARGV[2]` milliseconds, or deletes it right away when that is 0,
+/// if `ARGV[1]` still holds it.
+const RELEASE_LEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    if tonumber(ARGV[2]) > 0 then
+        return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    end
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+fn lease_ttl(interval: Duration) -> Duration {
+    // Expire slightly before the next tick so timer drift never makes a replica skip a run
+    interval
+        .saturating_sub(Duration::from_secs(1))
+        .max(Duration::from_secs(1))
+}
+
+async fn acquire_lease(
+    redis_pool: &RedisPool,
+    key: &str,
+    instance_id: &str,
+    ttl: Duration,
+) -> Result<bool, RedisError> {
+    let acquired: Option<String> = redis_pool
+        .set(
+            key,
+            instance_id,
+            Some(Expiration::PX(ttl.as_millis() as i64)),
+            Some(SetOptions::NX),
+            false,
+        )
+        .await?;
+
+    Ok(acquired.is_some())
+}
+
+async fn extend_lease(
+    redis_pool: &RedisPool,
+    key: &str,
+    instance_id: &str,
+    ttl: Duration,
+) -> Result<bool, RedisError> {
+    let extended: i64 = redis_pool
+        .eval(
+            EXTEND_LEASE_SCRIPT,
+            key,
+            vec![instance_id.to_string(), ttl.as_millis().to_string()],
+        )
+        .await?;
+
+    Ok(extended == 1)
+}
+
+async fn release_lease(
+    redis_pool: &RedisPool,
+    key: &str,
+    instance_id: &str,
+    remaining: Duration,
+) -> Result<(), RedisError> {
+    let _: i64 = redis_pool
+        .eval(
+            RELEASE_LEASE_SCRIPT,
+            key,
+            vec![instance_id.to_string(), remaining.as_millis().to_string()],
+        )
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use std::env;
+
+/// Text search configurations shipped with Postgres that posts can be indexed with.
+pub const SEARCH_LANGUAGES: [&str; 22] = [
+    "simple",
+    "arabic",
+    "danish",
+    "dutch",
+    "english",
+    "finnish",
+    "french",
+    "german",
+    "greek",
+    "hungarian",
+    "indonesian",
+    "irish",
+    "italian",
+    "lithuanian",
+    "nepali",
+    "norwegian",
+    "portuguese",
+    "romanian",
+    "russian",
+    "spanish",
+    "swedish",
+    "turkish",
+];
+
+pub fn is_supported_language(language: &str) -> bool {
+    SEARCH_LANGUAGES.contains(&language)
+}
+
+/// Language used for new posts and for searches that don't ask for one (`SEARCH_LANGUAGE`).
+pub fn default_language() -> String {
+    env::var("SEARCH_LANGUAGE")
+        .ok()
+        .filter(|language| is_supported_language(language))
+        .unwrap_or_else(|| "english".to_string())
+}
+
+/// Turns user input into a `to_tsquery` expression.
+///
+/// Words are AND-ed, `"quoted words"` must appear next to each other, `word*` matches by
+/// prefix, `-word` excludes and `or` between two terms matches either. Only alphanumeric
+/// characters reach the query, so its syntax can never be broken by the input. Returns
+/// `None` when nothing searchable is left.
+pub fn build_tsquery(input: &str) -> Option<String> {
+    let mut query = String::new();
+    let mut either = false;
+    let mut rest = input.trim();
+
+    while !rest.is_empty() {
+        let negated = rest.starts_with('-');
+        if negated {
+            rest = &rest[1..];
+        }
+
+        let (words, prefix) = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            let words = lexemes(&quoted[..end]);
+            rest = quoted.get(end + 1..).unwrap_or("");
+            (words, false)
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let token = &rest[..end];
+            rest = &rest[end..];
+
+            if !negated && token.eq_ignore_ascii_case("or") {
+                either = !query.is_empty();
+                rest = rest.trim_start();
+                continue;
+            }
+            (lexemes(token), token.ends_with('*'))
+        };
+        rest = rest.trim_start();
+
+        let Some(mut term) = phrase(&words, prefix) else {
+            continue;
+        };
+        if negated {
+            term = format!("!{}", term);
+        }
+
+        if !query.is_empty() {
+            query.push_str(if either { " | " } else { " & " });
+        }
+        query.push_str(&term);
+        either = false;
+    }
+
+    (!query.is_empty()).then_some(query)
+}
+
+fn lexemes(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+fn phrase(words: &[&str], prefix: bool) -> Option<String> {
+    let mut terms: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+    if prefix {
+        terms.last_mut()?.push_str(":*");
+    }
+
+    match terms.len() {
+        0 => None,
+        1 => terms.pop(),
+        _ => Some(format!("({})", terms.join(" <-> "))),
+    }
+}
@@ -0,0 +1,39 @@
+use std::env;
+
+/// Public URL of the blog front end, without a trailing slash (`SITE_URL`).
+pub fn url() -> String {
+    env::var("SITE_URL")
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or_else(|_| "http://localhost:3000".to_string())
+}
+
+/// Public URL this API is served from, without a trailing slash (`API_URL`).
+pub fn api_url() -> String {
+    env::var("API_URL")
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or_else(|_| "http://localhost:8888".to_string())
+}
+
+pub fn title() -> String {
+    env::var("SITE_TITLE").unwrap_or_else(|_| "Ruxlog".to_string())
+}
+
+pub fn description() -> String {
+    env::var("SITE_DESCRIPTION").unwrap_or_else(|_| "Latest posts".to_string())
+}
+
+pub fn post_url(slug: &str) -> String {
+    format!("{}/posts/{}", url(), slug)
+}
+
+pub fn category_url(slug: &str) -> String {
+    format!("{}/categories/{}", url(), slug)
+}
+
+pub fn tag_url(slug: &str) -> String {
+    format!("{}/tags/{}", url(), slug)
+}
+
+pub fn author_url(author_id: &str) -> String {
+    format!("{}/authors/{}", url(), author_id)
+}
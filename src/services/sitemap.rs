@@ -0,0 +1,114 @@
+use std::{env, io::Write};
+
+use chrono::{NaiveDateTime, SecondsFormat};
+use flate2::{write::GzEncoder, Compression};
+
+use crate::db::models::sitemap::{SitemapEntry, SitemapPage, SitemapSection};
+use crate::services::{content_renderer::escape_html, site};
+
+/// Most URLs a single sitemap may list under the sitemap protocol.
+pub const MAX_URLS_PER_SITEMAP: i64 = 50_000;
+
+/// API paths crawlers have no business visiting.
+const DISALLOWED_PATHS: [&str; 6] = [
+    "/auth/",
+    "/user/",
+    "/admin/",
+    "/bookmark/",
+    "/email_verification/",
+    "/forgot_password/",
+];
+
+/// Whether the index links the gzipped sitemaps (`SITEMAP_GZIP`).
+pub fn gzip_enabled() -> bool {
+    env::var("SITEMAP_GZIP").is_ok_and(|value| value == "true" || value == "1")
+}
+
+pub fn page_path(section: SitemapSection, page: i64, gzip: bool) -> String {
+    format!(
+        "/sitemaps/{}-{}.xml{}",
+        section.name(),
+        page,
+        if gzip { ".gz" } else { "" }
+    )
+}
+
+/// Parses a sitemap file name such as `posts-2.xml` or `tags-1.xml.gz` into its section,
+/// page and whether it is gzipped.
+pub fn parse_file_name(file_name: &str) -> Option<(SitemapSection, i64, bool)> {
+    let (stem, gzip) = match file_name.strip_suffix(".xml.gz") {
+        Some(stem) => (stem, true),
+        None => (file_name.strip_suffix(".xml")?, false),
+    };
+    let (section, page) = stem.rsplit_once('-')?;
+    let page = page.parse::<i64>().ok().filter(|&page| page > 0)?;
+
+    Some((SitemapSection::from_name(section)?, page, gzip))
+}
+
+pub fn render_index(pages: &[SitemapPage], gzip: bool) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+
+    for page in pages {
+        xml.push_str("<sitemap>\n");
+        // Crawlers only accept sitemaps on the host of the URLs they list
+        xml.push_str(&format!(
+            "<loc>{}{}</loc>\n",
+            escape_html(&site::url()),
+            page_path(page.section, page.page, gzip)
+        ));
+        if let Some(lastmod) = page.lastmod {
+            xml.push_str(&format!("<lastmod>{}</lastmod>\n", w3c_date(lastmod)));
+        }
+        xml.push_str("</sitemap>\n");
+    }
+
+    xml.push_str("</sitemapindex>\n");
+    xml
+}
+
+pub fn render_urlset(section: SitemapSection, entries: &[SitemapEntry]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+
+    for entry in entries {
+        let loc = match section {
+            SitemapSection::Posts => site::post_url(&entry.key),
+            SitemapSection::Categories => site::category_url(&entry.key),
+            SitemapSection::Tags => site::tag_url(&entry.key),
+            SitemapSection::Authors => site::author_url(&entry.key),
+        };
+        xml.push_str(&format!(
+            "<url>\n<loc>{}</loc>\n<lastmod>{}</lastmod>\n</url>\n",
+            escape_html(&loc),
+            w3c_date(entry.lastmod)
+        ));
+    }
+
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+pub fn render_robots() -> String {
+    let mut robots = String::from("User-agent: *\n");
+    for path in DISALLOWED_PATHS {
+        robots.push_str(&format!("Disallow: {}\n", path));
+    }
+    robots.push_str(&format!("\nSitemap: {}/sitemap.xml\n", site::url()));
+    robots
+}
+
+pub fn gzip(body: &str) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body.as_bytes())?;
+    encoder.finish()
+}
+
+fn w3c_date(date: NaiveDateTime) -> String {
+    date.and_utc().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
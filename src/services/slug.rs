@@ -0,0 +1,39 @@
+use std::collections::HashSet;
+
+use deunicode::deunicode;
+
+/// Longest generated slug, leaving room for a `-N` suffix within the 255 character column.
+const MAX_SLUG_LEN: usize = 200;
+
+/// Turns `text` into a URL slug: transliterated to ASCII, lowercased, with every run of
+/// other characters collapsed into a single hyphen. Falls back to `fallback` when nothing
+/// usable is left.
+pub fn slugify(text: &str, fallback: &str) -> String {
+    let mut slug = String::new();
+    for c in deunicode(text).chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(MAX_SLUG_LEN);
+    let slug = slug.trim_end_matches('-');
+
+    if slug.is_empty() {
+        fallback.to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// First of `base`, `base-2`, `base-3`, ... that is not in `taken`.
+pub fn unique(base: &str, taken: &HashSet<String>) -> String {
+    if !taken.contains(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap()
+}
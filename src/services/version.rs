@@ -0,0 +1,57 @@
+use axum::http::{header, HeaderMap, StatusCode};
+
+/// The version an edit was based on, as sent by the client.
+#[derive(Clone, Debug)]
+pub enum ExpectedVersion {
+    /// Entity tags from `If-Match`, or `None` for `If-Match: *`.
+    IfMatch(Option<Vec<i32>>),
+    /// The `version` field of the payload.
+    Payload(i32),
+}
+
+impl ExpectedVersion {
+    /// `If-Match` wins over the payload field when both are sent; `None` if neither is.
+    pub fn from_request(headers: &HeaderMap, payload_version: Option<i32>) -> Option<Self> {
+        let if_match = headers
+            .get(header::IF_MATCH)
+            .and_then(|value| value.to_str().ok());
+
+        match (if_match, payload_version) {
+            (Some(if_match), _) if if_match.trim() == "*" => Some(Self::IfMatch(None)),
+            (Some(if_match), _) => Some(Self::IfMatch(Some(
+                if_match
+                    .split(',')
+                    .filter_map(|tag| {
+                        tag.trim()
+                            .trim_start_matches("W/")
+                            .trim_matches('"')
+                            .parse()
+                            .ok()
+                    })
+                    .collect(),
+            ))),
+            (None, Some(version)) => Some(Self::Payload(version)),
+            (None, None) => None,
+        }
+    }
+
+    /// Versions the edit may be applied to, or `None` if any version will do.
+    pub fn versions(&self) -> Option<Vec<i32>> {
+        match self {
+            Self::IfMatch(versions) => versions.clone(),
+            Self::Payload(version) => Some(vec![*version]),
+        }
+    }
+
+    /// A failed `If-Match` precondition is a 412, a stale payload version a 409.
+    pub fn conflict_status(&self) -> StatusCode {
+        match self {
+            Self::IfMatch(_) => StatusCode::PRECONDITION_FAILED,
+            Self::Payload(_) => StatusCode::CONFLICT,
+        }
+    }
+}
+
+pub fn etag(version: i32) -> String {
+    format!("\"{}\"", version)
+}
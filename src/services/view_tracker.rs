@@ -0,0 +1,271 @@
+use std::{env, net::IpAddr};
+
+use chrono::Utc;
+use fred::{
+    error::{RedisError, RedisErrorKind},
+    prelude::{KeysInterface, ListInterface, LuaInterface, RedisPool},
+    types::{Expiration, SetOptions},
+};
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+
+use super::site;
+use crate::db::models::post_view::{DeviceClass, NewPostView};
+
+/// Views waiting to be written to Postgres, as JSON encoded `NewPostView`s.
+const BUFFER_KEY: &str = "post_views:buffer";
+/// Id of the batch being flushed. Kept until the batch is stored, so a failed flush is retried.
+const PENDING_KEY: &str = "post_views:pending";
+/// Prefix of the key holding the views of a batch, followed by the batch id.
+const BATCH_KEY_PREFIX: &str = "post_views:batch:";
+
+/// Returns the pending batch id, or moves the buffer to a new batch named after `ARGV[1]` and
+/// marks it pending. Runs as one script so concurrent flushes can't take the buffer twice.
+const START_FLUSH_SCRIPT: &str = r#"
+local pending = redis.call('GET', KEYS[2])
+if pending then
+    return pending
+end
+if redis.call('EXISTS', KEYS[1]) == 0 then
+    return false
+end
+redis.call('RENAME', KEYS[1], ARGV[2] .. ARGV[1])
+redis.call('SET', KEYS[2], ARGV[1])
+return ARGV[1]
+"#;
+
+/// Deletes a stored batch and clears the pending id if it still belongs to that batch.
+const FINISH_FLUSH_SCRIPT: &str = r#"
+redis.call('DEL', KEYS[2])
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    redis.call('DEL', KEYS[1])
+end
+return 1
+"#;
+
+const DEFAULT_DEDUP_WINDOW_SECS: i64 = 30 * 60;
+
+/// Parts of user agents sent by crawlers, link previews, monitors and HTTP libraries.
+const BOT_USER_AGENTS: [&str; 24] = [
+    "bot",
+    "crawl",
+    "spider",
+    "slurp",
+    "curl",
+    "wget",
+    "python-requests",
+    "python-urllib",
+    "go-http-client",
+    "okhttp",
+    "java/",
+    "libwww",
+    "httpclient",
+    "axios",
+    "node-fetch",
+    "scrapy",
+    "headlesschrome",
+    "phantomjs",
+    "lighthouse",
+    "facebookexternalhit",
+    "embedly",
+    "feedfetcher",
+    "pingdom",
+    "uptime",
+];
+
+/// How long repeated views of a post by the same visitor count once (`VIEW_DEDUP_WINDOW_SECS`,
+/// 30 minutes by default).
+pub fn dedup_window_secs() -> i64 {
+    env::var("VIEW_DEDUP_WINDOW_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_DEDUP_WINDOW_SECS)
+}
+
+/// Whether a request comes from something other than a person reading. Browsers always send a
+/// user agent, so requests without one count as bots too.
+pub fn is_bot(user_agent: Option<&str>) -> bool {
+    let Some(user_agent) = user_agent.map(str::trim).filter(|agent| !agent.is_empty()) else {
+        return true;
+    };
+    let user_agent = user_agent.to_lowercase();
+
+    BOT_USER_AGENTS
+        .iter()
+        .any(|pattern| user_agent.contains(pattern))
+}
+
+/// Anonymous identifier of a visitor: signed in users by id, everyone else by a hash of their
+/// IP address and user agent. The hash is keyed with `COOKIE_KEY` and the current day, so it
+/// can't be reversed into an address or used to follow a visitor across days.
+pub fn visitor_key(user_id: Option<i32>, ip: IpAddr, user_agent: &str) -> String {
+    if let Some(user_id) = user_id {
+        return format!("u:{}", user_id);
+    }
+
+    format!("v:{}", daily_hash(&[&ip.to_string(), user_agent]))
+}
+
+/// What analytics store to count unique visitors: the visitor key hashed the same way, so
+/// signed in users aren't identifiable either.
+pub fn visitor_hash(visitor: &str) -> String {
+    daily_hash(&[visitor])
+}
+
+fn daily_hash(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(env::var("COOKIE_KEY").unwrap_or_default());
+    hasher.update(Utc::now().date_naive().to_string());
+    for part in parts {
+        hasher.update(part);
+    }
+    let hash = hex::encode(hasher.finalize());
+
+    hash[..32].to_string()
+}
+
+/// Rough class of the device a user agent belongs to.
+pub fn device_class(user_agent: &str) -> DeviceClass {
+    let user_agent = user_agent.to_lowercase();
+
+    if user_agent.contains("ipad")
+        || user_agent.contains("tablet")
+        || (user_agent.contains("android") && !user_agent.contains("mobile"))
+    {
+        DeviceClass::Tablet
+    } else if user_agent.contains("mobi")
+        || user_agent.contains("iphone")
+        || user_agent.contains("android")
+    {
+        DeviceClass::Mobile
+    } else {
+        DeviceClass::Desktop
+    }
+}
+
+/// Host of a referring URL without a `www.` prefix, or `None` for links within the blog and
+/// anything that isn't an http(s) URL.
+pub fn referrer_domain(referrer: &str) -> Option<String> {
+    let host = url_host(referrer)?;
+
+    if url_host(&site::url()).as_deref() == Some(host.as_str()) {
+        return None;
+    }
+
+    Some(host)
+}
+
+fn url_host(url: &str) -> Option<String> {
+    let url = url.trim();
+    let (scheme, rest) = url.split_once("://")?;
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return None;
+    }
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = match host.strip_prefix('[') {
+        // IPv6 address
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    let host = host.trim_end_matches('.').to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+
+    (!host.is_empty() && host.len() <= 255).then(|| host.to_string())
+}
+
+/// A UTM parameter as it is stored: trimmed, lowercase and at most 100 characters.
+pub fn utm_value(value: Option<&str>) -> Option<String> {
+    let value = value?.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    Some(value.to_lowercase().chars().take(100).collect())
+}
+
+/// Buffers a view unless the visitor already viewed the post within the dedup window.
+/// Returns whether the view counts.
+pub async fn record(
+    redis_pool: &RedisPool,
+    view: &NewPostView,
+    visitor: &str,
+) -> Result<bool, RedisError> {
+    let first: Option<String> = redis_pool
+        .set(
+            format!("post_views:seen:{}:{}", view.post_id, visitor),
+            1,
+            Some(Expiration::EX(dedup_window_secs())),
+            Some(SetOptions::NX),
+            false,
+        )
+        .await?;
+    if first.is_none() {
+        return Ok(false);
+    }
+
+    let entry = serde_json::to_string(view)
+        .map_err(|err| RedisError::new(RedisErrorKind::Parse, err.to_string()))?;
+    let _: () = redis_pool.rpush(BUFFER_KEY, entry).await?;
+
+    Ok(true)
+}
+
+/// Buffered views taken for flushing.
+pub struct ViewBatch {
+    /// Stays the same when a failed flush returns the batch again.
+    pub id: String,
+    pub views: Vec<NewPostView>,
+}
+
+/// Takes the buffered views for flushing. A batch left over by a flush that failed is returned
+/// again, with the same id, before any new views; call `finish_flush` once the batch is stored.
+pub async fn start_flush(redis_pool: &RedisPool) -> Result<Option<ViewBatch>, RedisError> {
+    let new_id: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+    // New views keep going to a fresh buffer while this batch is stored
+    let id: Option<String> = redis_pool
+        .eval(
+            START_FLUSH_SCRIPT,
+            vec![BUFFER_KEY, PENDING_KEY],
+            vec![new_id, BATCH_KEY_PREFIX.to_string()],
+        )
+        .await?;
+    let Some(id) = id else {
+        return Ok(None);
+    };
+
+    let entries: Vec<String> = redis_pool.lrange(batch_key(&id), 0, -1).await?;
+    let views = entries
+        .iter()
+        .filter_map(|entry| match serde_json::from_str(entry) {
+            Ok(view) => Some(view),
+            Err(err) => {
+                tracing::warn!("Dropping unreadable buffered view {:?}: {}", entry, err);
+                None
+            }
+        })
+        .collect();
+
+    Ok(Some(ViewBatch { id, views }))
+}
+
+/// Discards the batch with the given id once it is stored. Leaves any newer batch alone.
+pub async fn finish_flush(redis_pool: &RedisPool, id: &str) -> Result<(), RedisError> {
+    redis_pool
+        .eval(
+            FINISH_FLUSH_SCRIPT,
+            vec![PENDING_KEY.to_string(), batch_key(id)],
+            id,
+        )
+        .await
+}
+
+fn batch_key(id: &str) -> String {
+    format!("{}{}", BATCH_KEY_PREFIX, id)
+}
@@ -1,10 +1,15 @@
+use std::sync::Arc;
+
 use deadpool_diesel::postgres::Pool;
 use fred::prelude::RedisPool;
 use lettre;
 
+use crate::services::media::storage::MediaStorage;
+
 #[derive(Clone)]
 pub struct AppState {
     pub db_pool: Pool,
     pub redis_pool: RedisPool,
     pub mailer: lettre::AsyncSmtpTransport<lettre::Tokio1Executor>,
+    pub media_storage: Arc<dyn MediaStorage>,
 }